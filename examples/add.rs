@@ -13,16 +13,20 @@ fn main() {
                 a_in: Input::new("c1", 0),
 
                 b_in: Input::new("c2", 0),
+                bit_width: 32,
+                delay: None,
             }),
             Rc::new(Constant {
                 id: "c1".to_string(),
                 pos: (100.0, 100.0),
                 value: 3,
+                bit_width: 32,
             }),
             Rc::new(Constant {
                 id: "c2".to_string(),
                 pos: (100.0, 140.0),
                 value: 4,
+                bit_width: 32,
             }),
             Rc::new(Wire {
                 id: "w1".to_string(),
@@ -54,8 +58,8 @@ fn main() {
     cs.save_file(&path);
 
     #[cfg(feature = "gui-egui")]
-    syncrim::gui_egui::gui(&cs, &path).ok();
+    syncrim::gui_egui::gui(&cs, &path, None).ok();
 
     #[cfg(feature = "gui-vizia")]
-    syncrim::gui_vizia::gui(&cs, &path);
+    syncrim::gui_vizia::gui(&cs, &path, None);
 }
@@ -12,16 +12,22 @@ fn main() {
                 pos: (200.0, 120.0),
                 a_in: Input::new("c", 0),
                 b_in: Input::new("r1", 0),
+                bit_width: 32,
+                delay: None,
             }),
             Rc::new(Constant {
                 id: "c".to_string(),
                 pos: (100.0, 100.0),
                 value: 1,
+                bit_width: 32,
             }),
             Rc::new(Register {
                 id: "r1".to_string(),
                 pos: (100.0, 140.0),
                 r_in: Input::new("add", 0),
+                bit_width: 32,
+                reset_value: None,
+                delay: None,
             }),
             Rc::new(Wire {
                 id: "w1".to_string(),
@@ -85,26 +91,32 @@ fn main() {
                     Input::new("mc3", 0),
                     Input::new("mc4", 0),
                 ],
+                bit_width: 32,
+                delay: None,
             }),
             Rc::new(Constant {
                 id: "mc1".to_string(),
                 pos: (190.0, 270.0),
                 value: 0,
+                bit_width: 32,
             }),
             Rc::new(Constant {
                 id: "mc2".to_string(),
                 pos: (190.0, 290.0),
                 value: 1,
+                bit_width: 32,
             }),
             Rc::new(Constant {
                 id: "mc3".to_string(),
                 pos: (190.0, 310.0),
                 value: 2,
+                bit_width: 32,
             }),
             Rc::new(Constant {
                 id: "mc4".to_string(),
                 pos: (190.0, 330.0),
                 value: 3,
+                bit_width: 32,
             }),
             Rc::new(Wire {
                 id: "wm_sel".to_string(),
@@ -154,8 +166,8 @@ fn main() {
     cs.save_file(&path);
 
     #[cfg(feature = "gui-egui")]
-    syncrim::gui_egui::gui(&cs, &path).ok();
+    syncrim::gui_egui::gui(&cs, &path, None).ok();
 
     #[cfg(feature = "gui-vizia")]
-    syncrim::gui_vizia::gui(&cs, &path);
+    syncrim::gui_vizia::gui(&cs, &path, None);
 }
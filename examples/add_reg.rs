@@ -12,16 +12,22 @@ fn main() {
                 pos: (200.0, 120.0),
                 a_in: Input::new("c", 0),
                 b_in: Input::new("reg", 0),
+                bit_width: 32,
+                delay: None,
             }),
             Rc::new(Constant {
                 id: "c".to_string(),
                 pos: (100.0, 100.0),
                 value: 3,
+                bit_width: 32,
             }),
             Rc::new(Register {
                 id: "reg".to_string(),
                 pos: (100.0, 140.0),
                 r_in: Input::new("add", 0),
+                bit_width: 32,
+                reset_value: None,
+                delay: None,
             }),
             Rc::new(Wire {
                 id: "w1".to_string(),
@@ -82,8 +88,8 @@ fn main() {
     cs.save_file(&path);
 
     #[cfg(feature = "gui-egui")]
-    syncrim::gui_egui::gui(&cs, &path).ok();
+    syncrim::gui_egui::gui(&cs, &path, None).ok();
 
     #[cfg(feature = "gui-vizia")]
-    syncrim::gui_vizia::gui(&cs, &path);
+    syncrim::gui_vizia::gui(&cs, &path, None);
 }
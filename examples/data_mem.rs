@@ -1,6 +1,6 @@
 use std::{path::PathBuf, rc::Rc};
 use syncrim::{
-    common::{ComponentStore, Input, Signal},
+    common::{ComponentStore, Input, ResetMode, Signal},
     components::*,
 };
 
@@ -27,31 +27,38 @@ fn main() {
                 // memory
                 memory: Memory::new(),
                 // later history... tbd
+                reset: ResetMode::Reload,
+                delay: None,
             }),
             Rc::new(Constant {
                 id: "data".to_string(),
                 pos: (100.0, 100.0),
                 value: 3,
+                bit_width: 32,
             }),
             Rc::new(Constant {
                 id: "addr".to_string(),
                 pos: (120.0, 100.0),
                 value: 4,
+                bit_width: 32,
             }),
             Rc::new(Constant {
                 id: "ctrl".to_string(),
                 pos: (140.0, 100.0),
                 value: MemCtrl::Write as Signal,
+                bit_width: 32,
             }),
             Rc::new(Constant {
                 id: "sext".to_string(),
                 pos: (160.0, 100.0),
                 value: false as Signal,
+                bit_width: 32,
             }),
             Rc::new(Constant {
                 id: "size".to_string(),
                 pos: (180.0, 100.0),
                 value: 1, // byte
+                bit_width: 32,
             }),
             // Wires
             Rc::new(Wire {
@@ -108,8 +115,8 @@ fn main() {
     cs.save_file(&path);
 
     #[cfg(feature = "gui-egui")]
-    syncrim::gui_egui::gui(&cs, &path).ok();
+    syncrim::gui_egui::gui(&cs, &path, None).ok();
 
     #[cfg(feature = "gui-vizia")]
-    syncrim::gui_vizia::gui(&cs, &path);
+    syncrim::gui_vizia::gui(&cs, &path, None);
 }
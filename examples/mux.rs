@@ -17,11 +17,14 @@ fn main() {
                     Input::new("c3", 0),
                     Input::new("c4", 0),
                 ],
+                bit_width: 32,
+                delay: None,
             }),
             Rc::new(Constant {
                 id: "c0".to_string(),
                 pos: (190.0, 100.0),
                 value: 3,
+                bit_width: 32,
             }),
             Rc::new(Wire {
                 id: "w0".to_string(),
@@ -33,21 +36,25 @@ fn main() {
                 id: "c1".to_string(),
                 pos: (140.0, 170.0),
                 value: 0,
+                bit_width: 32,
             }),
             Rc::new(Constant {
                 id: "c2".to_string(),
                 pos: (140.0, 190.0),
                 value: 1,
+                bit_width: 32,
             }),
             Rc::new(Constant {
                 id: "c3".to_string(),
                 pos: (140.0, 210.0),
                 value: 2,
+                bit_width: 32,
             }),
             Rc::new(Constant {
                 id: "c4".to_string(),
                 pos: (140.0, 230.0),
                 value: 3,
+                bit_width: 32,
             }),
             Rc::new(Wire {
                 id: "w1".to_string(),
@@ -91,8 +98,8 @@ fn main() {
     cs.save_file(&path);
 
     #[cfg(feature = "gui-egui")]
-    syncrim::gui_egui::gui(&cs, &path).ok();
+    syncrim::gui_egui::gui(&cs, &path, None).ok();
 
     #[cfg(feature = "gui-vizia")]
-    syncrim::gui_vizia::gui(&cs, &path);
+    syncrim::gui_vizia::gui(&cs, &path, None);
 }
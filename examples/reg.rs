@@ -11,11 +11,15 @@ fn main() {
                 id: "c".to_string(),
                 pos: (150.0, 100.0),
                 value: 3,
+                bit_width: 32,
             }),
             Rc::new(Register {
                 id: "reg".to_string(),
                 pos: (200.0, 100.0),
                 r_in: Input::new("c", 0),
+                bit_width: 32,
+                reset_value: None,
+                delay: None,
             }),
             Rc::new(Wire {
                 id: "w1".to_string(),
@@ -41,8 +45,8 @@ fn main() {
     cs.save_file(&path);
 
     #[cfg(feature = "gui-egui")]
-    syncrim::gui_egui::gui(&cs, &path).ok();
+    syncrim::gui_egui::gui(&cs, &path, None).ok();
 
     #[cfg(feature = "gui-vizia")]
-    syncrim::gui_vizia::gui(&cs, &path);
+    syncrim::gui_vizia::gui(&cs, &path, None);
 }
@@ -11,6 +11,7 @@ fn main() {
                 id: "c0".to_string(),
                 pos: (100.0, 110.0),
                 value: 32768,
+                bit_width: 32,
             }),
             Rc::new(Wire {
                 id: "w0".to_string(),
@@ -24,6 +25,7 @@ fn main() {
                 sext_in: Input::new("c0", 0),
                 in_size: 16,
                 out_size: 24,
+                delay: None,
             }),
             Rc::new(Wire {
                 id: "w1".to_string(),
@@ -43,8 +45,8 @@ fn main() {
     cs.save_file(&path);
 
     #[cfg(feature = "gui-egui")]
-    syncrim::gui_egui::gui(&cs, &path).ok();
+    syncrim::gui_egui::gui(&cs, &path, None).ok();
 
     #[cfg(feature = "gui-vizia")]
-    syncrim::gui_vizia::gui(&cs, &path);
+    syncrim::gui_vizia::gui(&cs, &path, None);
 }
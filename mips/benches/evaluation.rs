@@ -0,0 +1,84 @@
+// Benchmarks of full versus event-driven evaluation, on the MIPS example model
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use std::rc::Rc;
+use syncrim::{
+    common::{set_debug_output, ComponentStore, Input, Simulator},
+    components::*,
+};
+
+// the example model, its `main` is not used
+#[allow(dead_code)]
+#[path = "../examples/mips.rs"]
+mod example;
+
+// the MIPS example model, extended by a chain of `nr_static` adders from the constant,
+// each with a wire and probe, which are unchanged after the first clock
+fn mips(nr_static: usize) -> ComponentStore {
+    let mut cs = example::mips();
+    for i in 0..nr_static {
+        let previous = match i {
+            0 => "c1".to_string(),
+            _ => format!("add_c1_{}", i - 1),
+        };
+        cs.store.push(Rc::new(Add {
+            id: format!("add_c1_{}", i),
+            pos: (200.0, 200.0),
+            a_in: Input::new(&previous, 0),
+            b_in: Input::new("c1", 0),
+            bit_width: 32,
+            delay: None,
+        }));
+        cs.store.push(Rc::new(Wire {
+            id: format!("w_c1_{}", i),
+            pos: (220.0, 200.0),
+            delta: (40.0, 0.0),
+            input: Input::new(&format!("add_c1_{}", i), 0),
+        }));
+        cs.store.push(Rc::new(Probe {
+            id: format!("p_c1_{}", i),
+            pos: (260.0, 200.0),
+            input: Input::new(&format!("add_c1_{}", i), 0),
+        }));
+    }
+    cs
+}
+
+fn evaluation(c: &mut Criterion) {
+    // the debug output would be measured along with the simulation
+    set_debug_output(false);
+    let mut group = c.benchmark_group("evaluation");
+    for nr_static in [0, 1000] {
+        let cs = mips(nr_static);
+        for event_driven in [false, true] {
+            let mode = if event_driven { "event-driven" } else { "full" };
+            let mut clock = 0;
+            let mut simulator = Simulator::new(&cs, &mut clock);
+            simulator.set_event_driven(event_driven);
+            // steady-state clocking, a copy of the simulator is reset and clocked once
+            // (evaluating all components) in setup, the instruction memory holds 10 instructions
+            group.bench_function(BenchmarkId::new(mode, nr_static), |b| {
+                b.iter_batched(
+                    || {
+                        let mut simulator = simulator.clone();
+                        let mut clock = 0;
+                        simulator.reset(&mut clock);
+                        simulator.clock(&mut clock);
+                        (simulator, clock)
+                    },
+                    |(mut simulator, mut clock)| {
+                        for _ in 0..8 {
+                            simulator.clock(&mut clock);
+                        }
+                        simulator
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, evaluation);
+criterion_main!(benches);
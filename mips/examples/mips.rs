@@ -7,24 +7,31 @@ use syncrim::{
     components::*,
 };
 
-fn main() {
-    let cs = ComponentStore {
+// the example model, also simulated by the benchmarks (see `benches/evaluation.rs`)
+pub fn mips() -> ComponentStore {
+    ComponentStore {
         store: vec![
             Rc::new(Add {
                 id: "add".to_string(),
                 pos: (200.0, 120.0),
                 a_in: Input::new("c1", 0),
                 b_in: Input::new("reg", 0),
+                bit_width: 32,
+                delay: None,
             }),
             Rc::new(Constant {
                 id: "c1".to_string(),
                 pos: (100.0, 100.0),
                 value: 4,
+                bit_width: 32,
             }),
             Rc::new(Register {
                 id: "reg".to_string(),
                 pos: (100.0, 140.0),
                 r_in: Input::new("add", 0),
+                bit_width: 32,
+                reset_value: None,
+                delay: None,
             }),
             Rc::new(Wire {
                 id: "c1_to_add_a".to_string(),
@@ -86,6 +93,7 @@ fn main() {
                 pc: Input::new("reg", 0),
                 // fake instructions just to show the relation between input address and instruction
                 instr: vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+                delay: None,
             }),
             Rc::new(Wire {
                 id: "w8".to_string(),
@@ -104,14 +112,18 @@ fn main() {
                 input: Input::new("instr_mem", 0),
             }),
         ],
-    };
+    }
+}
+
+fn main() {
+    let cs = mips();
 
     let path = PathBuf::from("mips.json");
     cs.save_file(&path);
 
     #[cfg(feature = "gui-egui")]
-    syncrim::gui_egui::gui(&cs, &path).ok();
+    syncrim::gui_egui::gui(&cs, &path, None).ok();
 
     #[cfg(feature = "gui-vizia")]
-    syncrim::gui_vizia::gui(&cs, &path);
+    syncrim::gui_vizia::gui(&cs, &path, None);
 }
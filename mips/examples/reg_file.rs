@@ -1,8 +1,8 @@
 use mips::components::*;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::{path::PathBuf, rc::Rc};
 use syncrim::{
-    common::{ComponentStore, Input, Signal},
+    common::{ComponentStore, Input, ResetMode, Signal},
     components::*,
 };
 
@@ -14,26 +14,31 @@ fn main() {
                 id: "c_read_reg_1".to_string(),
                 pos: (100.0, 100.0),
                 value: 3,
+                bit_width: 5,
             }),
             Rc::new(Constant {
                 id: "c_read_reg_2".to_string(),
                 pos: (100.0, 200.0),
                 value: 4,
+                bit_width: 5,
             }),
             Rc::new(Constant {
                 id: "c_write_data".to_string(),
                 pos: (100.0, 140.0),
                 value: 42,
+                bit_width: 32,
             }),
             Rc::new(Constant {
                 id: "c_write_addr".to_string(),
                 pos: (100.0, 160.0),
                 value: 4,
+                bit_width: 5,
             }),
             Rc::new(Constant {
                 id: "c_write_enable".to_string(),
                 pos: (100.0, 180.0),
                 value: true as Signal,
+                bit_width: 1,
             }),
             // regfile
             Rc::new(RegFile {
@@ -51,6 +56,11 @@ fn main() {
 
                 // data
                 registers: vec![Cell::new(0); 32],
+                written: Cell::new(0),
+                saved: RefCell::new(None),
+                last_write: Cell::new(None),
+                reset: ResetMode::Reload,
+                delay: None,
             }),
         ],
     };
@@ -59,8 +69,8 @@ fn main() {
     cs.save_file(&path);
 
     #[cfg(feature = "gui-egui")]
-    syncrim::gui_egui::gui(&cs, &path).ok();
+    syncrim::gui_egui::gui(&cs, &path, None).ok();
 
     #[cfg(feature = "gui-vizia")]
-    syncrim::gui_vizia::gui(&cs, &path);
+    syncrim::gui_vizia::gui(&cs, &path, None);
 }
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use syncrim::common::{Component, Input, Output, OutputType, Ports, Simulator};
+use syncrim::common::{port_names, Component, Input, Output, OutputType, Ports, Signal, Simulator};
+use syncrim::debug;
 
 #[derive(Serialize, Deserialize)]
 pub struct InstrMem {
@@ -7,12 +8,15 @@ pub struct InstrMem {
     pub pos: (f32, f32),
     pub instr: Vec<u32>,
     pub pc: Input,
+    // propagation delay in ns, if not given 10 ns (see `Component::delay`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<f32>,
 }
 
 #[typetag::serde()]
 impl Component for InstrMem {
     fn to_(&self) {
-        println!("InstrMem");
+        debug!("InstrMem");
     }
 
     fn get_id_ports(&self) -> (String, Ports) {
@@ -20,20 +24,29 @@ impl Component for InstrMem {
             self.id.clone(),
             Ports {
                 inputs: vec![self.pc.clone()],
+                input_widths: vec![Signal::BITS],
+                input_names: port_names(&["pc"]),
+                clocked_inputs: vec![],
                 out_type: OutputType::Combinatorial,
                 outputs: vec![Output::Function],
+                output_widths: vec![32],
+                output_names: port_names(&["instruction"]),
             },
         )
     }
 
+    fn delay(&self) -> f32 {
+        self.delay.unwrap_or(10.0)
+    }
+
     fn evaluate(&self, simulator: &mut Simulator) {
         // get instr at pc/4
         let pc = simulator.get_input_val(&self.pc);
 
-        println!("--- evaluate instr mem: pc {}", pc);
+        debug!("--- evaluate instr mem: pc {}", pc);
         let instr = self.instr[(pc / 4) as usize];
         // set output
-        println!("--- output {}", instr);
-        simulator.set_id_index(&self.id, 0, instr);
+        debug!("--- output {}", instr);
+        simulator.set_id_index(&self.id, 0, instr as Signal);
     }
 }
@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
-use std::cell::Cell;
-use syncrim::common::{Component, Input, Output, OutputType, Ports, Signal, Simulator};
+use std::cell::{Cell, RefCell};
+use syncrim::common::{
+    port_names, Component, Input, Output, OutputType, Ports, ResetMode, Signal, SignalValue,
+    Simulator, SimulatorError,
+};
+use syncrim::debug;
 
 #[derive(Serialize, Deserialize)]
 pub struct RegFile {
@@ -18,18 +22,55 @@ pub struct RegFile {
 
     // data, should be an array of 32 Cells, but its harder to manage in Rust (Cell not Copy)
     pub registers: Vec<Cell<u32>>,
+
+    // registers written since start (bit per register),
+    // in four-state mode registers not yet written read as unknown (X)
+    #[serde(skip)]
+    pub written: Cell<u32>,
+
+    // registers before the write of the current clock, taken by `undo_log`
+    #[serde(skip)]
+    pub saved: RefCell<Option<serde_json::Value>>,
+
+    // register written by the latest clock edge, for coverage
+    #[serde(skip)]
+    pub last_write: Cell<Option<usize>>,
+
+    // registers after reset, if not given reloaded as the simulator was created
+    #[serde(default)]
+    pub reset: ResetMode,
+
+    // propagation delay in ns, if not given 3 ns (see `Component::delay`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<f32>,
 }
 
 impl RegFile {
-    fn read_reg(&self, simulator: &Simulator, input: &Input) -> u32 {
-        let read_addr = simulator.get_input_val(input) as usize;
-        println!("read_addr {}", read_addr);
+    fn read_reg(&self, simulator: &Simulator, input: &Input) -> SignalValue {
+        let read_addr = match simulator.get_input_value(input) {
+            SignalValue::Data(read_addr) => read_addr as usize,
+            _ => return SignalValue::Unknown,
+        };
+        debug!("read_addr {}", read_addr);
 
         // mips always reads 0;
-        if read_addr > 0 {
-            self.registers[read_addr].get()
+        if read_addr == 0 {
+            SignalValue::Data(0)
+        } else if simulator.four_state && self.written.get() & (1 << read_addr) == 0 {
+            SignalValue::Unknown
         } else {
-            0
+            SignalValue::Data(self.registers[read_addr].get() as Signal)
+        }
+    }
+
+    fn write_reg(&self, write_addr: usize, data: SignalValue) {
+        match data {
+            SignalValue::Data(data) => {
+                self.registers[write_addr].set(data as u32);
+                self.written.set(self.written.get() | 1 << write_addr);
+            }
+            // unknown data, the register becomes unknown
+            _ => self.written.set(self.written.get() & !(1 << write_addr)),
         }
     }
 }
@@ -37,39 +78,152 @@ impl RegFile {
 #[typetag::serde()]
 impl Component for RegFile {
     fn to_(&self) {
-        println!("RegFile");
+        debug!("RegFile");
     }
 
     fn get_id_ports(&self) -> (String, Ports) {
         (
             self.id.clone(),
             Ports {
-                inputs: vec![self.read_addr1.clone(), self.read_addr2.clone()],
+                inputs: vec![
+                    self.read_addr1.clone(),
+                    self.read_addr2.clone(),
+                    self.write_data.clone(),
+                    self.write_addr.clone(),
+                    self.write_enable.clone(),
+                ],
+                // 5 bit register addresses, 32 bit data and a 1 bit write enable
+                input_widths: vec![5, 5, 32, 5, 1],
+                input_names: port_names(&[
+                    "read_addr1",
+                    "read_addr2",
+                    "write_data",
+                    "write_addr",
+                    "write_enable",
+                ]),
+                // written on the clock edge, such that the written data may depend on the
+                // registers read (e.g., the write-back of a CPU)
+                clocked_inputs: vec![2, 3, 4],
                 out_type: OutputType::Combinatorial,
                 outputs: vec![Output::Function; 2],
+                output_widths: vec![32; 2],
+                output_names: port_names(&["reg_a", "reg_b"]),
             },
         )
     }
 
-    fn evaluate(&self, simulator: &mut Simulator) {
-        if simulator.get_input_val(&self.write_enable) == true as Signal {
-            let data = simulator.get_input_val(&self.write_data);
-            println!("data {}", data);
-            let write_addr = simulator.get_input_val(&self.write_addr) as usize;
-            println!("write_addr {}", write_addr);
-            self.registers[write_addr].set(data);
+    fn delay(&self) -> f32 {
+        self.delay.unwrap_or(3.0)
+    }
+
+    fn clock_edge(&self, simulator: &Simulator) {
+        self.last_write.set(None);
+        let write_enable = simulator.get_input_value(&self.write_enable);
+        let data = match write_enable {
+            SignalValue::Data(write_enable) if write_enable == true as Signal => {
+                Some(simulator.get_input_value(&self.write_data))
+            }
+            SignalValue::Data(_) => None,
+            // unknown write enable, the register may or may not be written
+            _ => Some(SignalValue::Unknown),
+        };
+        if let Some(data) = data {
+            self.saved.replace(self.snapshot());
+            debug!("data {}", data);
+            match simulator.get_input_value(&self.write_addr) {
+                SignalValue::Data(write_addr) => {
+                    debug!("write_addr {}", write_addr);
+                    self.write_reg(write_addr as usize, data);
+                    if write_enable == SignalValue::Data(true as Signal) {
+                        self.last_write.set(Some(write_addr as usize));
+                    }
+                }
+                // unknown write address, any register may have been written
+                _ => self.written.set(0),
+            }
         }
+    }
 
+    fn evaluate(&self, simulator: &mut Simulator) {
         let base = simulator.get_id_start_index(&self.id);
-        println!("base {}", base);
-        println!("sim_state {:?}", simulator.sim_state);
+        debug!("base {}", base);
+        debug!("sim_state {:?}", simulator.sim_state);
 
         let reg_value = self.read_reg(simulator, &self.read_addr1);
-        println!("reg_value {}", reg_value);
-        simulator.set(base, reg_value);
+        debug!("reg_value {}", reg_value);
+        simulator.set_value(base, reg_value);
 
         let reg_value = self.read_reg(simulator, &self.read_addr2);
-        println!("reg_value {}", reg_value);
-        simulator.set(base + 1, reg_value);
+        debug!("reg_value {}", reg_value);
+        simulator.set_value(base + 1, reg_value);
+    }
+
+    // reads and writes of each register
+    fn coverage(&self) -> Vec<String> {
+        (0..self.registers.len())
+            .flat_map(|r| [format!("r{} read", r), format!("r{} write", r)])
+            .collect()
+    }
+
+    fn covered(&self, simulator: &Simulator) -> Vec<String> {
+        let mut covered = vec![];
+        for input in [&self.read_addr1, &self.read_addr2] {
+            if let SignalValue::Data(read_addr) = simulator.get_input_value(input) {
+                covered.push(format!("r{} read", read_addr));
+            }
+        }
+        // written on the clock edge, from the write address before the clock
+        if let Some(write_addr) = self.last_write.get() {
+            covered.push(format!("r{} write", write_addr));
+        }
+        covered
+    }
+
+    fn snapshot(&self) -> Option<serde_json::Value> {
+        Some(serde_json::to_value((&self.registers, self.written.get())).unwrap())
+    }
+
+    fn restore(&self, state: &serde_json::Value) -> Result<(), SimulatorError> {
+        let mismatch =
+            |msg: String| SimulatorError::CheckpointMismatch(format!("{}: {}", self.id, msg));
+        let (registers, written): (Vec<u32>, u32) =
+            serde_json::from_value(state.clone()).map_err(|err| mismatch(err.to_string()))?;
+        if registers.len() != self.registers.len() {
+            return Err(mismatch(format!(
+                "{} registers, where the register file has {}",
+                registers.len(),
+                self.registers.len()
+            )));
+        }
+        for (register, value) in self.registers.iter().zip(registers) {
+            register.set(value);
+        }
+        self.written.set(written);
+        self.saved.replace(None);
+        self.last_write.set(None);
+        Ok(())
+    }
+
+    // the register file is small, so its state before the write is kept as a whole
+    fn undo_log(&self) -> Option<serde_json::Value> {
+        self.saved.take()
+    }
+
+    fn undo(&self, log: &serde_json::Value) {
+        self.restore(log).unwrap();
+    }
+
+    // reload the registers the simulator was created with, or zero all registers
+    fn reset(&self, _simulator: &mut Simulator, initial: Option<&serde_json::Value>) {
+        match (self.reset, initial) {
+            // the state captured on creation
+            (ResetMode::Reload, Some(initial)) => self.restore(initial).unwrap(),
+            _ => {
+                self.registers.iter().for_each(|register| register.set(0));
+                self.written.set(0);
+                self.saved.replace(None);
+                self.last_write.set(None);
+            }
+        }
     }
 }
@@ -1,9 +1,10 @@
 use mips::components::RegFile;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use syncrim::{
-    common::{ComponentStore, Input, Signal, Simulator},
+    common::{ComponentStore, Input, ResetMode, Signal, SignalValue, Simulator, SimulatorError},
     components::*,
+    validate::Severity,
 };
 
 // an example of integration test for a mips specific component
@@ -11,11 +12,23 @@ use syncrim::{
 fn test_reg_file() {
     let cs = ComponentStore {
         store: vec![
-            Rc::new(ProbeOut::new("read_reg_1")),
-            Rc::new(ProbeOut::new("read_reg_2")),
+            Rc::new(ProbeOut {
+                id: "read_reg_1".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "read_reg_2".to_string(),
+                bit_width: 5,
+            }),
             Rc::new(ProbeOut::new("write_data")),
-            Rc::new(ProbeOut::new("write_addr")),
-            Rc::new(ProbeOut::new("write_enable")),
+            Rc::new(ProbeOut {
+                id: "write_addr".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "write_enable".to_string(),
+                bit_width: 1,
+            }),
             // regfile
             Rc::new(RegFile {
                 id: "reg_file".to_string(),
@@ -32,6 +45,11 @@ fn test_reg_file() {
 
                 // data
                 registers: vec![Cell::new(0); 32],
+                written: Cell::new(0),
+                saved: RefCell::new(None),
+                last_write: Cell::new(None),
+                reset: ResetMode::Reload,
+                delay: None,
             }),
         ],
     };
@@ -79,6 +97,685 @@ fn test_reg_file() {
     assert_eq!(simulator.get_input_val(out_reg_2), 1337);
 }
 
+#[test]
+fn test_reg_file_four_state() {
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut {
+                id: "read_reg_1".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "read_reg_2".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut::new("write_data")),
+            Rc::new(ProbeOut {
+                id: "write_addr".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "write_enable".to_string(),
+                bit_width: 1,
+            }),
+            // regfile
+            Rc::new(RegFile {
+                id: "reg_file".to_string(),
+                pos: (200.0, 150.0),
+                width: 100.0,
+                height: 150.0,
+
+                // ports
+                read_addr1: Input::new("read_reg_1", 0),
+                read_addr2: Input::new("read_reg_2", 0),
+                write_data: Input::new("write_data", 0),
+                write_addr: Input::new("write_addr", 0),
+                write_enable: Input::new("write_enable", 0),
+
+                // data
+                registers: vec![Cell::new(0); 32],
+                written: Cell::new(0),
+                saved: RefCell::new(None),
+                last_write: Cell::new(None),
+                reset: ResetMode::Reload,
+                delay: None,
+            }),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+    simulator.set_four_state(true, &mut clock);
+
+    // outputs
+    let out_reg_1 = &Input::new("reg_file", 0);
+    let out_reg_2 = &Input::new("reg_file", 1);
+
+    // reg #0 always reads 0, reg #1 is not yet written
+    simulator.set_id_index("read_reg_1", 0, 0);
+    simulator.set_id_index("read_reg_2", 0, 1);
+    simulator.set_id_index("write_enable", 0, false as Signal);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_value(out_reg_1), SignalValue::Data(0));
+    assert_eq!(simulator.get_input_value(out_reg_2), SignalValue::Unknown);
+
+    // write reg #1
+    simulator.set_id_index("write_data", 0, 1337);
+    simulator.set_id_index("write_addr", 0, 1);
+    simulator.set_id_index("write_enable", 0, true as Signal);
+    simulator.clock(&mut clock);
+    assert_eq!(
+        simulator.get_input_value(out_reg_2),
+        SignalValue::Data(1337)
+    );
+
+    // unknown write address, any register may have been written
+    simulator.set_id_index_value("write_addr", 0, SignalValue::Unknown);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_value(out_reg_2), SignalValue::Unknown);
+
+    // unknown read address
+    simulator.set_id_index_value("read_reg_1", 0, SignalValue::Unknown);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_value(out_reg_1), SignalValue::Unknown);
+}
+
+#[test]
+fn test_reg_file_un_clock() {
+    let reg_file = Rc::new(RegFile {
+        id: "reg_file".to_string(),
+        pos: (200.0, 150.0),
+        width: 100.0,
+        height: 150.0,
+
+        // ports
+        read_addr1: Input::new("read_reg_1", 0),
+        read_addr2: Input::new("read_reg_2", 0),
+        write_data: Input::new("write_data", 0),
+        write_addr: Input::new("write_addr", 0),
+        write_enable: Input::new("write_enable", 0),
+
+        // data
+        registers: vec![Cell::new(0); 32],
+        written: Cell::new(0),
+        saved: RefCell::new(None),
+        last_write: Cell::new(None),
+        reset: ResetMode::Reload,
+        delay: None,
+    });
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut {
+                id: "read_reg_1".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "read_reg_2".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut::new("write_data")),
+            Rc::new(ProbeOut {
+                id: "write_addr".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "write_enable".to_string(),
+                bit_width: 1,
+            }),
+            reg_file.clone(),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+
+    // outputs
+    let out_reg_2 = &Input::new("reg_file", 1);
+
+    // write 1337 to reg # 1
+    simulator.set_id_index("read_reg_2", 0, 1);
+    simulator.set_id_index("write_data", 0, 1337);
+    simulator.set_id_index("write_addr", 0, 1);
+    simulator.set_id_index("write_enable", 0, true as Signal);
+    simulator.clock(&mut clock);
+    assert_eq!(clock, 2);
+    assert_eq!(reg_file.registers[1].get(), 1337);
+
+    // step back, the register file is as before the write
+    simulator.un_clock(&mut clock);
+    assert_eq!(clock, 1);
+    assert_eq!(reg_file.registers[1].get(), 0);
+    assert_eq!(simulator.get_input_val(out_reg_2), 0);
+
+    // re-reading after the step back gives the restored value
+    simulator.set_id_index("write_enable", 0, false as Signal);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(out_reg_2), 0);
+}
+
+#[test]
+fn test_reg_file_checkpoint() {
+    let reg_file = Rc::new(RegFile {
+        id: "reg_file".to_string(),
+        pos: (200.0, 150.0),
+        width: 100.0,
+        height: 150.0,
+
+        // ports
+        read_addr1: Input::new("read_reg_1", 0),
+        read_addr2: Input::new("read_reg_2", 0),
+        write_data: Input::new("write_data", 0),
+        write_addr: Input::new("write_addr", 0),
+        write_enable: Input::new("write_enable", 0),
+
+        // data
+        registers: vec![Cell::new(0); 32],
+        written: Cell::new(0),
+        saved: RefCell::new(None),
+        last_write: Cell::new(None),
+        reset: ResetMode::Reload,
+        delay: None,
+    });
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut {
+                id: "read_reg_1".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "read_reg_2".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut::new("write_data")),
+            Rc::new(ProbeOut {
+                id: "write_addr".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "write_enable".to_string(),
+                bit_width: 1,
+            }),
+            reg_file.clone(),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+
+    // write 1337 to reg # 1
+    simulator.set_id_index("write_data", 0, 1337);
+    simulator.set_id_index("write_addr", 0, 1);
+    simulator.set_id_index("write_enable", 0, true as Signal);
+    simulator.clock(&mut clock);
+    let checkpoint = simulator.checkpoint(false);
+
+    // write 42 to reg # 1
+    simulator.set_id_index("write_data", 0, 42);
+    simulator.clock(&mut clock);
+    assert_eq!(reg_file.registers[1].get(), 42);
+
+    // resume from checkpoint, restores the register file
+    simulator
+        .restore_checkpoint(checkpoint, &mut clock)
+        .unwrap();
+    assert_eq!(clock, 2);
+    assert_eq!(reg_file.registers[1].get(), 1337);
+    assert_eq!(simulator.get_input_val(&Input::new("write_data", 0)), 1337);
+
+    // the registers of a smaller register file, or not registers at all, are not restored
+    let mut checkpoint = simulator.checkpoint(false);
+    checkpoint
+        .components
+        .insert("reg_file".to_string(), serde_json::json!([[0, 42], 0]));
+    assert_eq!(
+        simulator.restore_checkpoint(checkpoint.clone(), &mut clock),
+        Err(SimulatorError::CheckpointMismatch(
+            "reg_file: 2 registers, where the register file has 32".to_string()
+        ))
+    );
+    checkpoint
+        .components
+        .insert("reg_file".to_string(), serde_json::json!("registers"));
+    assert!(matches!(
+        simulator.restore_checkpoint(checkpoint, &mut clock),
+        Err(SimulatorError::CheckpointMismatch(_))
+    ));
+    assert_eq!(reg_file.registers[1].get(), 1337);
+}
+
+#[test]
+fn test_reg_file_dependencies() {
+    // write data driven by a combinatorial component
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(RegFile {
+                id: "reg_file".to_string(),
+                pos: (200.0, 150.0),
+                width: 100.0,
+                height: 150.0,
+
+                // ports
+                read_addr1: Input::new("read_reg_1", 0),
+                read_addr2: Input::new("read_reg_2", 0),
+                write_data: Input::new("add", 0),
+                write_addr: Input::new("write_addr", 0),
+                write_enable: Input::new("write_enable", 0),
+
+                // data
+                registers: vec![Cell::new(0); 32],
+                written: Cell::new(0),
+                saved: RefCell::new(None),
+                last_write: Cell::new(None),
+                reset: ResetMode::Reload,
+                delay: None,
+            }),
+            Rc::new(Add {
+                id: "add".to_string(),
+                pos: (0.0, 0.0),
+                a_in: Input::new("a", 0),
+                b_in: Input::new("b", 0),
+                bit_width: 32,
+                delay: None,
+            }),
+            Rc::new(ProbeOut::new("a")),
+            Rc::new(ProbeOut::new("b")),
+            Rc::new(ProbeOut {
+                id: "read_reg_1".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "read_reg_2".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "write_addr".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "write_enable".to_string(),
+                bit_width: 1,
+            }),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+    simulator.set_debug(true);
+
+    // the write ports are sampled on the clock edge, the register file does not depend on them
+    assert!(!simulator
+        .graph
+        .raw_edges()
+        .iter()
+        .any(|e| e.weight.id == "add"));
+
+    // write 40 + 2 to reg # 1, the sum is propagated in the first cycle,
+    // written on the next clock edge and read in the second cycle
+    simulator.set_id_index("a", 0, 40);
+    simulator.set_id_index("b", 0, 2);
+    simulator.set_id_index("read_reg_1", 0, 1);
+    simulator.set_id_index("write_addr", 0, 1);
+    simulator.set_id_index("write_enable", 0, true as Signal);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(&Input::new("add", 0)), 42);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(&Input::new("reg_file", 0)), 42);
+
+    // all inputs read are declared
+    assert_eq!(simulator.undeclared_inputs(), vec![]);
+}
+
+#[test]
+fn test_reg_file_reset() {
+    // register image 7 in reg # 2, reloaded or cleared on reset
+    for (reset, image) in [(ResetMode::Reload, 7), (ResetMode::Clear, 0)] {
+        let mut registers = vec![Cell::new(0); 32];
+        registers[2] = Cell::new(7);
+        let reg_file = Rc::new(RegFile {
+            id: "reg_file".to_string(),
+            pos: (200.0, 150.0),
+            width: 100.0,
+            height: 150.0,
+
+            // ports
+            read_addr1: Input::new("read_reg_1", 0),
+            read_addr2: Input::new("read_reg_2", 0),
+            write_data: Input::new("write_data", 0),
+            write_addr: Input::new("write_addr", 0),
+            write_enable: Input::new("write_enable", 0),
+
+            // data
+            registers,
+            written: Cell::new(0),
+            saved: RefCell::new(None),
+            last_write: Cell::new(None),
+            reset,
+            delay: None,
+        });
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(ProbeOut {
+                    id: "read_reg_1".to_string(),
+                    bit_width: 5,
+                }),
+                Rc::new(ProbeOut {
+                    id: "read_reg_2".to_string(),
+                    bit_width: 5,
+                }),
+                Rc::new(ProbeOut::new("write_data")),
+                Rc::new(ProbeOut {
+                    id: "write_addr".to_string(),
+                    bit_width: 5,
+                }),
+                Rc::new(ProbeOut {
+                    id: "write_enable".to_string(),
+                    bit_width: 1,
+                }),
+                reg_file.clone(),
+            ],
+        };
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        assert_eq!(reg_file.registers[2].get(), image);
+
+        // overwrite reg # 2, and write reg # 3
+        simulator.set_id_index("write_data", 0, 1337);
+        simulator.set_id_index("write_addr", 0, 2);
+        simulator.set_id_index("write_enable", 0, true as Signal);
+        simulator.clock(&mut clock);
+        simulator.set_id_index("write_addr", 0, 3);
+        simulator.clock(&mut clock);
+        assert_eq!(reg_file.registers[2].get(), 1337);
+        assert_eq!(reg_file.registers[3].get(), 1337);
+
+        // reset reloads the register image, or zeroes all registers
+        simulator.reset(&mut clock);
+        assert_eq!(clock, 1);
+        assert_eq!(reg_file.registers[2].get(), image);
+        assert_eq!(reg_file.registers[3].get(), 0);
+        assert_eq!(reg_file.written.get(), 0);
+    }
+}
+
+#[test]
+fn test_reg_file_write_back() {
+    // r1 = r1 + 1 on each clock, the written data depends on the register read
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(Constant {
+                id: "one".to_string(),
+                pos: (0.0, 0.0),
+                value: 1,
+                bit_width: 5,
+            }),
+            Rc::new(Constant {
+                id: "enable".to_string(),
+                pos: (0.0, 0.0),
+                value: true as Signal,
+                bit_width: 1,
+            }),
+            Rc::new(RegFile {
+                id: "reg_file".to_string(),
+                pos: (200.0, 150.0),
+                width: 100.0,
+                height: 150.0,
+
+                // ports
+                read_addr1: Input::new("one", 0),
+                read_addr2: Input::new("one", 0),
+                write_data: Input::new("add", 0),
+                write_addr: Input::new("one", 0),
+                write_enable: Input::new("enable", 0),
+
+                // data
+                registers: vec![Cell::new(0); 32],
+                written: Cell::new(0),
+                saved: RefCell::new(None),
+                last_write: Cell::new(None),
+                reset: ResetMode::Reload,
+                delay: None,
+            }),
+            Rc::new(Add {
+                id: "add".to_string(),
+                pos: (0.0, 0.0),
+                a_in: Input::new("reg_file", 0),
+                b_in: Input::new("one", 0),
+                bit_width: 32,
+                delay: None,
+            }),
+        ],
+    };
+    assert!(cs
+        .validate()
+        .iter()
+        .all(|problem| problem.severity != Severity::Error));
+    let mut clock = 0;
+    let mut simulator = Simulator::try_new(&cs, &mut clock).unwrap();
+    let reg_a = &Input::new("reg_file", 0);
+
+    // written on the clock edge, and read back in the next cycle
+    assert_eq!(simulator.get_input_val(reg_a), 0);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(reg_a), 1);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(reg_a), 2);
+    assert_eq!(simulator.get_input_val(&Input::new("add", 0)), 3);
+
+    // the same in event-driven mode
+    simulator.set_event_driven(true);
+    simulator.clock(&mut clock);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(reg_a), 4);
+}
+
+#[test]
+fn test_reg_file_coverage() {
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut {
+                id: "read_reg_1".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "read_reg_2".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut::new("write_data")),
+            Rc::new(ProbeOut {
+                id: "write_addr".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "write_enable".to_string(),
+                bit_width: 1,
+            }),
+            Rc::new(RegFile {
+                id: "reg_file".to_string(),
+                pos: (200.0, 150.0),
+                width: 100.0,
+                height: 150.0,
+
+                // ports
+                read_addr1: Input::new("read_reg_1", 0),
+                read_addr2: Input::new("read_reg_2", 0),
+                write_data: Input::new("write_data", 0),
+                write_addr: Input::new("write_addr", 0),
+                write_enable: Input::new("write_enable", 0),
+
+                // data
+                registers: vec![Cell::new(0); 32],
+                written: Cell::new(0),
+                saved: RefCell::new(None),
+                last_write: Cell::new(None),
+                reset: ResetMode::Reload,
+                delay: None,
+            }),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+    simulator.set_activity(true);
+
+    // write reg # 2 and read it back on port 1, port 2 reads reg # 0 on both cycles
+    simulator.set_id_index("write_addr", 0, 2);
+    simulator.set_id_index("write_enable", 0, true as Signal);
+    simulator.clock(&mut clock);
+    simulator.set_id_index("write_enable", 0, false as Signal);
+    simulator.set_id_index("read_reg_1", 0, 2);
+    simulator.clock(&mut clock);
+
+    let report = simulator.activity_report().unwrap();
+    let coverage = &report.coverage[0];
+    assert_eq!(coverage.id, "reg_file");
+    assert_eq!(coverage.points.len(), 64);
+    let hit: Vec<_> = coverage
+        .points
+        .iter()
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    assert_eq!(
+        hit,
+        vec![
+            &("r0 read".to_string(), 3),
+            &("r2 read".to_string(), 1),
+            &("r2 write".to_string(), 1)
+        ]
+    );
+}
+
+#[test]
+fn test_reg_file_coverage_write() {
+    // the write address counts up on each clock, as sampled on the clock edge
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(Constant {
+                id: "one".to_string(),
+                pos: (0.0, 0.0),
+                value: 1,
+                bit_width: 5,
+            }),
+            Rc::new(Constant {
+                id: "enable".to_string(),
+                pos: (0.0, 0.0),
+                value: true as Signal,
+                bit_width: 1,
+            }),
+            Rc::new(Register {
+                id: "write_addr".to_string(),
+                pos: (0.0, 0.0),
+                r_in: Input::new("add", 0),
+                bit_width: 5,
+                reset_value: None,
+                delay: None,
+            }),
+            Rc::new(Add {
+                id: "add".to_string(),
+                pos: (0.0, 0.0),
+                a_in: Input::new("write_addr", 0),
+                b_in: Input::new("one", 0),
+                bit_width: 5,
+                delay: None,
+            }),
+            Rc::new(RegFile {
+                id: "reg_file".to_string(),
+                pos: (200.0, 150.0),
+                width: 100.0,
+                height: 150.0,
+
+                // ports
+                read_addr1: Input::new("one", 0),
+                read_addr2: Input::new("one", 0),
+                write_data: Input::new("one", 0),
+                write_addr: Input::new("write_addr", 0),
+                write_enable: Input::new("enable", 0),
+
+                // data
+                registers: vec![Cell::new(0); 32],
+                written: Cell::new(0),
+                saved: RefCell::new(None),
+                last_write: Cell::new(None),
+                reset: ResetMode::Reload,
+                delay: None,
+            }),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+    simulator.set_activity(true);
+
+    // reg # 0, 1 and 2 are written, the last on the last clock
+    for _ in 0..3 {
+        simulator.clock(&mut clock);
+    }
+    assert_eq!(simulator.get_input_val(&Input::new("write_addr", 0)), 3);
+
+    let report = simulator.activity_report().unwrap();
+    let written: Vec<_> = report.coverage[0]
+        .points
+        .iter()
+        .filter(|(point, count)| point.ends_with("write") && *count > 0)
+        .collect();
+    assert_eq!(
+        written,
+        vec![
+            &("r0 write".to_string(), 1),
+            &("r1 write".to_string(), 1),
+            &("r2 write".to_string(), 1)
+        ]
+    );
+}
+
+#[test]
+fn test_reg_file_width_mismatch() {
+    // a 32 bit write address
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut {
+                id: "read_reg_1".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut {
+                id: "read_reg_2".to_string(),
+                bit_width: 5,
+            }),
+            Rc::new(ProbeOut::new("write_data")),
+            Rc::new(ProbeOut::new("write_addr")),
+            Rc::new(ProbeOut {
+                id: "write_enable".to_string(),
+                bit_width: 1,
+            }),
+            Rc::new(RegFile {
+                id: "reg_file".to_string(),
+                pos: (200.0, 150.0),
+                width: 100.0,
+                height: 150.0,
+
+                // ports
+                read_addr1: Input::new("read_reg_1", 0),
+                read_addr2: Input::new("read_reg_2", 0),
+                write_data: Input::new("write_data", 0),
+                write_addr: Input::new("write_addr", 0),
+                write_enable: Input::new("write_enable", 0),
+
+                // data
+                registers: vec![Cell::new(0); 32],
+                written: Cell::new(0),
+                saved: RefCell::new(None),
+                last_write: Cell::new(None),
+                reset: ResetMode::Reload,
+                delay: None,
+            }),
+        ],
+    };
+    let mut clock = 0;
+    assert_eq!(
+        Simulator::try_new(&cs, &mut clock).err(),
+        Some(SimulatorError::WidthMismatch {
+            component: "reg_file".to_string(),
+            port: "write_addr".to_string(),
+            output: "write_addr.out".to_string(),
+            bit_width: 32,
+            expected: 5,
+        })
+    );
+}
+
 // An example of a test that should panic (fail)
 // Useful to assert that illegal models and/or states does not pass unnoticed
 #[test]
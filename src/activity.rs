@@ -0,0 +1,363 @@
+use crate::common::{Components, Input, SignalValue, Simulator, SimulatorError};
+use crate::simulator::output_name;
+use crate::validate::Bounds;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::{fs::File, io::prelude::*, path::PathBuf};
+
+/// Activity counters of a simulation run, tracked from reset (see `Simulator::set_activity`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Activity {
+    // clock cycles tracked
+    pub cycles: usize,
+    // toggled bits of each signal, by index in `sim_state`
+    pub toggles: Vec<u64>,
+    // coverage points with the number of cycles hit (see `Component::coverage`),
+    // for each component in `ordered_components`
+    pub coverage: Vec<Vec<(String, u64)>>,
+}
+
+/// bits toggled from the old to the new value,
+/// a change to or from unknown (X) or high-impedance (Z) counts as one toggle
+pub fn toggles(old: SignalValue, new: SignalValue) -> u64 {
+    match (old, new) {
+        (SignalValue::Data(old), SignalValue::Data(new)) => (old ^ new).count_ones() as u64,
+        (old, new) => (old != new) as u64,
+    }
+}
+
+/// Toggle activity of a signal.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SignalActivity {
+    // output as `id.name`
+    pub signal: String,
+    pub bit_width: u32,
+    pub toggles: u64,
+}
+
+/// Coverage of a component.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Coverage {
+    pub id: String,
+    // coverage points with the number of cycles hit
+    pub points: Vec<(String, u64)>,
+}
+
+impl Coverage {
+    /// number of coverage points hit
+    pub fn hit(&self) -> usize {
+        self.points.iter().filter(|(_, count)| *count > 0).count()
+    }
+}
+
+/// Report of the toggle activity and coverage of a simulation run,
+/// as text (see `Display`) or JSON.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActivityReport {
+    pub cycles: usize,
+    pub signals: Vec<SignalActivity>,
+    // components with coverage points
+    pub coverage: Vec<Coverage>,
+}
+
+impl ActivityReport {
+    /// coverage points hit, and in total
+    pub fn coverage_points(&self) -> (usize, usize) {
+        self.coverage.iter().fold((0, 0), |(hit, total), c| {
+            (hit + c.hit(), total + c.points.len())
+        })
+    }
+}
+
+impl fmt::Display for ActivityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (hit, total) = self.coverage_points();
+        write!(
+            f,
+            "activity over {} cycles, {} of {} coverage points hit",
+            self.cycles, hit, total
+        )?;
+        for signal in &self.signals {
+            // toggle rate, the fraction of bits toggled per cycle
+            let rate = match self.cycles as u64 * signal.bit_width as u64 {
+                0 => 0.0,
+                bits => signal.toggles as f64 / bits as f64,
+            };
+            write!(
+                f,
+                "\n{} {} toggles ({:.2} per bit and cycle)",
+                signal.signal, signal.toggles, rate
+            )?;
+        }
+        for coverage in &self.coverage {
+            let points: Vec<String> = coverage
+                .points
+                .iter()
+                .map(|(point, count)| format!("{} {}", point, count))
+                .collect();
+            write!(
+                f,
+                "\n{} {} of {} hit: {}",
+                coverage.id,
+                coverage.hit(),
+                coverage.points.len(),
+                points.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Tracking of toggle activity, for estimating dynamic power, and of coverage, for finding
+/// the parts of a model never exercised. Counters are cleared on reset,
+/// and not rewound by un_clock.
+impl Simulator {
+    /// enable or disable activity tracking, counting from zero
+    pub fn set_activity(&mut self, enabled: bool) {
+        self.activity = enabled.then(|| Activity {
+            cycles: 0,
+            toggles: vec![0; self.sim_state.len()],
+            coverage: self
+                .ordered_components
+                .iter()
+                .map(|c| c.coverage().into_iter().map(|point| (point, 0)).collect())
+                .collect(),
+        });
+    }
+
+    // count the toggles from the previous state, and the coverage points hit, after a clock
+    // (components are taken from `ordered_components` on clock)
+    pub(crate) fn track_activity(&mut self, previous: &[SignalValue], components: &Components) {
+        let mut activity = match self.activity.take() {
+            Some(activity) => activity,
+            None => return,
+        };
+        activity.cycles += 1;
+        for (count, (old, new)) in activity
+            .toggles
+            .iter_mut()
+            .zip(previous.iter().zip(&self.sim_state))
+        {
+            *count += toggles(*old, *new);
+        }
+        for (i, component) in components.iter().enumerate() {
+            if activity.coverage[i].is_empty() {
+                continue;
+            }
+            self.scope = Some(i);
+            for point in component.covered(self) {
+                if let Some((_, count)) = activity.coverage[i].iter_mut().find(|(p, _)| *p == point)
+                {
+                    *count += 1;
+                }
+            }
+        }
+        self.scope = None;
+        self.activity = Some(activity);
+    }
+
+    /// report of the tracked activity, None if activity is not tracked
+    pub fn activity_report(&self) -> Option<ActivityReport> {
+        let activity = self.activity.as_ref()?;
+        let mut signals = vec![];
+        for (id, outputs) in self.component_ids.iter().zip(&self.output_ranges) {
+            for index in outputs.clone() {
+                signals.push(SignalActivity {
+                    signal: output_name(&self.id_ports, &Input::new(id, index - outputs.start)),
+                    bit_width: self.signal_widths[index],
+                    toggles: activity.toggles[index],
+                });
+            }
+        }
+        let coverage = self
+            .component_ids
+            .iter()
+            .zip(&activity.coverage)
+            .filter(|(_, points)| !points.is_empty())
+            .map(|(id, points)| Coverage {
+                id: id.clone(),
+                points: points.clone(),
+            })
+            .collect();
+        Some(ActivityReport {
+            cycles: activity.cycles,
+            signals,
+            coverage,
+        })
+    }
+
+    /// save the activity report to file, as JSON for files with `.json` extension,
+    /// otherwise as text (an empty report if activity is not tracked)
+    pub fn save_activity(&self, path: &PathBuf) -> Result<(), SimulatorError> {
+        let report = self.activity_report().unwrap_or_default();
+        let text = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::to_string_pretty(&report)
+                .map_err(|err| SimulatorError::Io(err.to_string()))?
+        } else {
+            format!("{}\n", report)
+        };
+        File::create(path)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))
+    }
+
+    /// position (center) and size of the shown components with outputs, with their
+    /// activity (toggled bits per bit and cycle) relative to the most active component,
+    /// for a heatmap, empty if activity is not tracked or no cycles are tracked
+    pub fn activity_heat(&self) -> Vec<(Bounds, f32)> {
+        let activity = match &self.activity {
+            Some(activity) if activity.cycles > 0 => activity,
+            _ => return vec![],
+        };
+        let shown: Vec<_> = self
+            .bounds
+            .iter()
+            .zip(&self.output_ranges)
+            .filter_map(|(bounds, outputs)| Some(((*bounds)?, outputs)))
+            .filter(|(_, outputs)| !outputs.is_empty())
+            .map(|(bounds, outputs)| {
+                let toggles: u64 = activity.toggles[outputs.clone()].iter().sum();
+                let bits: u64 = self.signal_widths[outputs.clone()]
+                    .iter()
+                    .map(|width| *width as u64)
+                    .sum();
+                (
+                    bounds,
+                    toggles as f32 / (bits * activity.cycles as u64) as f32,
+                )
+            })
+            .collect();
+        let max = shown.iter().map(|(_, heat)| *heat).fold(0.0, f32::max);
+        shown
+            .into_iter()
+            .map(|(bounds, heat)| (bounds, if max > 0.0 { heat / max } else { 0.0 }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::{counter, Mux, ProbeOut};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_toggles() {
+        assert_eq!(
+            toggles(SignalValue::Data(0b0101), SignalValue::Data(0b1001)),
+            2
+        );
+        assert_eq!(toggles(SignalValue::Data(1), SignalValue::Data(1)), 0);
+        assert_eq!(toggles(SignalValue::Unknown, SignalValue::Data(0xff)), 1);
+        assert_eq!(toggles(SignalValue::HighZ, SignalValue::HighZ), 0);
+    }
+
+    #[test]
+    fn test_activity() {
+        // one bit counter, selecting the inputs of a mux
+        let mut cs = counter(1);
+        cs.store.push(Rc::new(Mux {
+            id: "mux".to_string(),
+            pos: (0.0, 0.0),
+            select: Input::new("reg", 0),
+            m_in: vec![Input::new("c", 0), Input::new("c", 0), Input::new("reg", 0)],
+            bit_width: 1,
+            delay: None,
+        }));
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        assert_eq!(simulator.activity_report(), None);
+        assert_eq!(simulator.activity_heat(), vec![]);
+
+        simulator.set_activity(true);
+        for _ in 0..4 {
+            simulator.clock(&mut clock);
+        }
+        let report = simulator.activity_report().unwrap();
+        assert_eq!(report.cycles, 4);
+        // the counter, its sum and overflow toggle each cycle, the mux output is constant
+        assert_eq!(
+            report.to_string(),
+            "activity over 4 cycles, 2 of 3 coverage points hit\n\
+             reg.out 4 toggles (1.00 per bit and cycle)\n\
+             c.out 0 toggles (0.00 per bit and cycle)\n\
+             mux.out 0 toggles (0.00 per bit and cycle)\n\
+             add.sum 4 toggles (1.00 per bit and cycle)\n\
+             add.overflow 4 toggles (1.00 per bit and cycle)\n\
+             mux 2 of 3 hit: in0 2, in1 2, in2 0"
+        );
+        // relative to the most active component, in order of evaluation
+        assert_eq!(
+            simulator
+                .activity_heat()
+                .iter()
+                .map(|(_, heat)| *heat)
+                .collect::<Vec<_>>(),
+            vec![1.0, 0.0, 0.0, 1.0]
+        );
+
+        // counters are not rewound by un_clock, and cleared on reset
+        simulator.un_clock(&mut clock);
+        assert_eq!(simulator.activity_report().unwrap().cycles, 4);
+        simulator.reset(&mut clock);
+        assert_eq!(simulator.activity_report().unwrap(), {
+            let mut cleared = report.clone();
+            cleared.cycles = 0;
+            cleared.signals.iter_mut().for_each(|s| s.toggles = 0);
+            cleared.coverage[0].points.iter_mut().for_each(|p| p.1 = 0);
+            cleared
+        });
+
+        simulator.set_activity(false);
+        assert_eq!(simulator.activity_report(), None);
+    }
+
+    #[test]
+    fn test_activity_report() {
+        // a mux selecting a constant, or a two bit counter
+        let mut cs = counter(2);
+        cs.store.push(Rc::new(ProbeOut::new("sel")));
+        cs.store.push(Rc::new(Mux {
+            id: "mux".to_string(),
+            pos: (0.0, 0.0),
+            select: Input::new("sel", 0),
+            m_in: vec![Input::new("c", 0), Input::new("reg", 0)],
+            bit_width: 2,
+            delay: None,
+        }));
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        simulator.set_activity(true);
+        for _ in 0..4 {
+            simulator.clock(&mut clock);
+        }
+
+        // the counter toggles 1, 2, 1 and 2 bits (0 -> 1 -> 2 -> 3 -> 0),
+        // the mux never selects the counter
+        let report = simulator.activity_report().unwrap();
+        let toggles = |signal: &str| {
+            report
+                .signals
+                .iter()
+                .find(|s| s.signal == signal)
+                .unwrap()
+                .toggles
+        };
+        assert_eq!(toggles("reg.out"), 6);
+        assert_eq!(toggles("mux.out"), 0);
+        assert_eq!(report.coverage_points(), (1, 2));
+
+        // saved as JSON, and as text
+        let path = std::env::temp_dir().join("syncrim_test_activity.json");
+        simulator.save_activity(&path).unwrap();
+        let json = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ActivityReport>(&json).unwrap(),
+            report
+        );
+        let path = std::env::temp_dir().join("syncrim_test_activity.txt");
+        simulator.save_activity(&path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.ends_with("mux 1 of 2 hit: in0 4, in1 0\n"));
+    }
+}
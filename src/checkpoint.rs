@@ -0,0 +1,296 @@
+use crate::common::{Input, SignalValue, Simulator, SimulatorError};
+use crate::history::History;
+use crate::simulator::output_name;
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::{fs::File, io::prelude::*, path::PathBuf};
+
+/// Checkpoint of a running simulation.
+///
+/// Captures the clock cycle, the simulator state, the internal state of components
+/// (see `Component::snapshot`), the forced signals (see `Simulator::force`) and optionally
+/// the history, such that a simulation of the same model can be resumed from the checkpoint.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Checkpoint {
+    pub cycle: usize,
+    pub four_state: bool,
+    // output (as `id.name`) and bit width of each signal in `sim_state`,
+    // identifying the model the checkpoint was taken from
+    pub signals: Vec<(String, u32)>,
+    pub sim_state: Vec<SignalValue>,
+    // internal state by component id
+    pub components: BTreeMap<String, serde_json::Value>,
+    // forced signals by index in `sim_state`
+    #[serde(default)]
+    pub forced: BTreeMap<usize, SignalValue>,
+    // history, allows stepping back from the checkpoint
+    pub history: Option<History>,
+}
+
+impl Simulator {
+    /// checkpoint of the current simulation, optionally including the history
+    pub fn checkpoint(&self, with_history: bool) -> Checkpoint {
+        Checkpoint {
+            cycle: self.cycle,
+            four_state: self.four_state,
+            signals: self.signals(),
+            sim_state: self.sim_state.clone(),
+            components: self
+                .component_ids
+                .iter()
+                .zip(&self.ordered_components)
+                .filter_map(|(id, c)| c.snapshot().map(|state| (id.clone(), state)))
+                .collect(),
+            forced: self.forced.clone(),
+            history: if with_history {
+                Some(self.history.clone())
+            } else {
+                None
+            },
+        }
+    }
+
+    // output (as `id.name`) and bit width of each signal, by index in `sim_state`
+    fn signals(&self) -> Vec<(String, u32)> {
+        let mut signals = vec![(String::new(), 0); self.sim_state.len()];
+        for (id, outputs) in self.component_ids.iter().zip(&self.output_ranges) {
+            for index in outputs.clone() {
+                let name = output_name(&self.id_ports, &Input::new(id, index - outputs.start));
+                signals[index] = (name, self.signal_widths[index]);
+            }
+        }
+        signals
+    }
+
+    /// resume simulation from checkpoint, returns an error if the checkpoint
+    /// does not match the model
+    pub fn restore_checkpoint(
+        &mut self,
+        checkpoint: Checkpoint,
+        clock: &mut usize,
+    ) -> Result<(), SimulatorError> {
+        let signals = self.signals();
+        if checkpoint.signals.len() != signals.len() || checkpoint.sim_state.len() != signals.len()
+        {
+            return Err(SimulatorError::CheckpointMismatch(format!(
+                "{} signals, where the model has {}",
+                checkpoint.signals.len(),
+                signals.len()
+            )));
+        }
+        if let Some(((name, bit_width), (expected, expected_width))) = checkpoint
+            .signals
+            .iter()
+            .zip(&signals)
+            .find(|(signal, expected)| signal != expected)
+        {
+            return Err(SimulatorError::CheckpointMismatch(format!(
+                "signal {} of width {}, where the model has {} of width {}",
+                name, bit_width, expected, expected_width
+            )));
+        }
+        if let Some(index) = checkpoint
+            .forced
+            .keys()
+            .find(|i| **i >= self.sim_state.len())
+        {
+            return Err(SimulatorError::CheckpointMismatch(format!(
+                "forced signal {}, where the model has {} signals",
+                index,
+                self.sim_state.len()
+            )));
+        }
+        let mut components = vec![];
+        for (id, state) in checkpoint.components {
+            match self.component_ids.iter().position(|i| *i == id) {
+                Some(i) => components.push((i, state)),
+                None => {
+                    return Err(SimulatorError::CheckpointMismatch(format!(
+                        "component {} not found in the model",
+                        id
+                    )))
+                }
+            }
+        }
+
+        // the state of the components is kept, should any state not be restored
+        let previous: Vec<_> = components
+            .iter()
+            .map(|(i, _)| self.ordered_components[*i].snapshot())
+            .collect();
+        for (k, (i, state)) in components.iter().enumerate() {
+            if let Err(err) = self.ordered_components[*i].restore(state) {
+                for ((i, _), previous) in components.iter().zip(&previous).take(k) {
+                    if let Some(previous) = previous {
+                        self.ordered_components[*i].restore(previous).unwrap();
+                    }
+                }
+                return Err(err);
+            }
+        }
+        self.cycle = checkpoint.cycle;
+        self.four_state = checkpoint.four_state;
+        self.sim_state = checkpoint.sim_state;
+        self.forced.clear();
+        for (index, value) in checkpoint.forced {
+            self.set_value(index, value);
+            self.forced.insert(index, self.sim_state[index]);
+        }
+        self.settled = None;
+        match checkpoint.history {
+            Some(history) => self.history = history,
+            None => self.history.clear(self.cycle),
+        }
+        *clock = self.cycle;
+        Ok(())
+    }
+
+    /// save checkpoint to file, optionally including the history
+    pub fn save_checkpoint(
+        &self,
+        path: &PathBuf,
+        with_history: bool,
+    ) -> Result<(), SimulatorError> {
+        let json = serde_json::to_string(&self.checkpoint(with_history)).unwrap();
+        let mut file =
+            File::create(path).map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))?;
+        file.write_all(json.as_bytes())
+            .map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))
+    }
+
+    /// resume simulation from checkpoint file
+    pub fn load_checkpoint(
+        &mut self,
+        path: &PathBuf,
+        clock: &mut usize,
+    ) -> Result<(), SimulatorError> {
+        let mut file =
+            File::open(path).map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))?;
+        let mut json = String::new();
+        file.read_to_string(&mut json)
+            .map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))?;
+
+        let checkpoint = serde_json::from_str(&json).map_err(|err| SimulatorError::Parse {
+            line: err.line(),
+            col: err.column(),
+            msg: err.to_string(),
+        })?;
+        self.restore_checkpoint(checkpoint, clock)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::ComponentStore;
+    use crate::components::{counter, ProbeOut};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_checkpoint() {
+        let cs = counter(32);
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+
+        // counter
+        let reg = &Input::new("reg", 0);
+        for _ in 0..10 {
+            simulator.clock(&mut clock);
+        }
+        assert_eq!(clock, 11);
+        assert_eq!(simulator.get_input_val(reg), 10);
+
+        let path = std::env::temp_dir().join("syncrim_test_checkpoint.json");
+        simulator.save_checkpoint(&path, true).unwrap();
+        let path_no_history = std::env::temp_dir().join("syncrim_test_checkpoint_no_history.json");
+        simulator.save_checkpoint(&path_no_history, false).unwrap();
+
+        // resume in a new simulator
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        simulator.load_checkpoint(&path, &mut clock).unwrap();
+        assert_eq!(clock, 11);
+        assert_eq!(simulator.get_input_val(reg), 10);
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_input_val(reg), 11);
+
+        // step back past the checkpoint using the saved history
+        simulator.un_clock(&mut clock);
+        simulator.un_clock(&mut clock);
+        assert_eq!(clock, 10);
+        assert_eq!(simulator.get_input_val(reg), 9);
+
+        // without history, the checkpoint cannot be stepped back from
+        simulator
+            .load_checkpoint(&path_no_history, &mut clock)
+            .unwrap();
+        simulator.un_clock(&mut clock);
+        assert_eq!(clock, 11);
+        assert_eq!(simulator.get_input_val(reg), 10);
+        assert_eq!(
+            simulator.get_input_value_at(11, reg),
+            Some(SignalValue::Data(10))
+        );
+
+        // checkpoint of another model
+        let cs = ComponentStore {
+            store: vec![Rc::new(ProbeOut::new("po1"))],
+        };
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        assert!(matches!(
+            simulator.load_checkpoint(&path, &mut clock),
+            Err(SimulatorError::CheckpointMismatch(_))
+        ));
+
+        // checkpoint of another model with as many signals
+        let cs = ComponentStore {
+            store: ["po1", "po2", "po3", "po4"]
+                .iter()
+                .map(|id| Rc::new(ProbeOut::new(id)) as _)
+                .collect(),
+        };
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        assert_eq!(
+            simulator.load_checkpoint(&path, &mut clock),
+            Err(SimulatorError::CheckpointMismatch(
+                "signal c.out of width 32, where the model has po1.out of width 32".to_string()
+            ))
+        );
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(path_no_history).unwrap();
+    }
+
+    #[test]
+    fn test_force_checkpoint() {
+        let cs = counter(32);
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+
+        // counter, counting by 2 with the constant forced (the first sum was computed on reset)
+        let reg = &Input::new("reg", 0);
+        simulator.force("c", 0, 2);
+        for _ in 0..5 {
+            simulator.clock(&mut clock);
+        }
+        assert_eq!(simulator.get_input_val(reg), 9);
+
+        // forced signals are saved with the checkpoint
+        let json = serde_json::to_string(&simulator.checkpoint(false)).unwrap();
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        simulator
+            .restore_checkpoint(serde_json::from_str(&json).unwrap(), &mut clock)
+            .unwrap();
+        assert!(simulator.is_input_forced(&Input::new("c", 0)));
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_input_val(reg), 11);
+
+        // released, counting by 1
+        simulator.release("c", 0);
+        simulator.clock(&mut clock);
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_input_val(reg), 14);
+    }
+}
@@ -0,0 +1,582 @@
+use crate::common::{
+    fmt_value, resolve_signal, set_debug_output, ComponentStore, Input, SignalValue, Simulator,
+    SimulatorError,
+};
+use crate::migration::{self, MODEL_VERSION};
+use crate::simulator::output_name;
+use crate::stimulus::parse_value;
+use crate::testbench::Testbench;
+use crate::validate::Severity;
+use clap::{Parser, Subcommand};
+use std::{fs::File, io::prelude::*, path::PathBuf, process};
+
+/// SyncRim, simulation of synchronous circuits
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct Args {
+    /// Path to the model to load on startup
+    #[arg(short, long, global = true)]
+    pub model: Option<String>,
+
+    /// Command, opens the GUI if none is given
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Simulate the model without GUI
+    Run(RunArgs),
+    /// Run testbenches against the model, fails if any testbench fails
+    Test(TestArgs),
+    /// Upgrade the model to the current format version
+    Migrate(MigrateArgs),
+    /// Check the model for problems without simulating it, fails if any errors are found
+    Check,
+    /// Estimate the critical path and maximum clock frequency of the model
+    Timing(TimingArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RunArgs {
+    /// Number of cycles to clock
+    #[arg(short, long, default_value_t = 1)]
+    pub cycles: usize,
+
+    /// Signal to print, as `id.name` or `id.index` (or `id` for index 0), all outputs if none given
+    #[arg(short, long = "signal")]
+    pub signals: Vec<String>,
+
+    /// Print signals on every cycle, not only after the last
+    #[arg(short, long)]
+    pub trace: bool,
+
+    /// Simulate in four-state mode
+    #[arg(long)]
+    pub four_state: bool,
+
+    /// Write signals to file, instead of to standard output
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    /// Drive the simulation by stimulus file (CSV or JSON)
+    #[arg(long)]
+    pub stimulus: Option<String>,
+
+    /// Write the simulated cycles as a VCD waveform to file
+    #[arg(long)]
+    pub vcd: Option<String>,
+
+    /// Print the debug output of the simulation (to standard output), and fail if any
+    /// component reads an input not declared in its ports
+    #[arg(long)]
+    pub debug: bool,
+
+    /// Evaluate only the components depending on changed outputs
+    #[arg(long)]
+    pub event_driven: bool,
+
+    /// Force a signal to a value, as `id.name=value` or `id.index=value` (e.g., `mux.0=1`, or `reg=X`)
+    #[arg(long)]
+    pub force: Vec<String>,
+
+    /// Write the toggle activity and coverage of the run to file (JSON for `.json`, else text)
+    #[arg(long)]
+    pub activity: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct TestArgs {
+    /// Testbench files (CSV or JSON)
+    #[arg(required = true)]
+    pub testbenches: Vec<String>,
+
+    /// Simulate in four-state mode
+    #[arg(long)]
+    pub four_state: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct MigrateArgs {
+    /// Write the upgraded model to file, instead of overwriting the model
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct TimingArgs {
+    /// Number of paths to report, longest first
+    #[arg(short, long, default_value_t = 1)]
+    pub paths: usize,
+}
+
+/// command line entry point, shared by the simulator binaries,
+/// `default_model` is used if no model is given on the command line
+pub fn main(default_model: Option<&str>) {
+    let args = Args::parse();
+    let path = match args.model.as_deref().or(default_model) {
+        Some(model) => PathBuf::from(model),
+        None => {
+            eprintln!("No model given, use --model <MODEL>");
+            process::exit(2);
+        }
+    };
+
+    // the debug output of the simulation is shown in the GUI console, the commands print
+    // their results to standard output without it (unless `run --debug`)
+    if args.command.is_some() {
+        set_debug_output(matches!(&args.command, Some(Command::Run(run_args)) if run_args.debug));
+    }
+
+    match args.command {
+        Some(Command::Run(run_args)) => {
+            let result =
+                ComponentStore::try_load_file(&path).and_then(|cs| match &run_args.output {
+                    Some(output) => {
+                        let output = PathBuf::from(output);
+                        let mut file = File::create(&output)
+                            .map_err(|err| SimulatorError::Io(format!("{:?}: {}", output, err)))?;
+                        run(&cs, &run_args, &mut file)
+                    }
+                    None => run(&cs, &run_args, &mut std::io::stdout()),
+                });
+            if let Err(err) = result {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+        }
+        Some(Command::Test(test_args)) => {
+            let result = ComponentStore::try_load_file(&path)
+                .and_then(|cs| test(&cs, &test_args, &mut std::io::stdout()));
+            match result {
+                Ok(true) => {}
+                Ok(false) => process::exit(1),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    process::exit(1);
+                }
+            }
+        }
+        Some(Command::Migrate(migrate_args)) => {
+            let output = migrate_args
+                .output
+                .map_or_else(|| path.clone(), PathBuf::from);
+            match migrate(&path, &output) {
+                Ok(version) => println!(
+                    "{:?}: upgraded from version {} to {}",
+                    output, version, MODEL_VERSION
+                ),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    process::exit(1);
+                }
+            }
+        }
+        Some(Command::Check) => {
+            let result = ComponentStore::try_load_file(&path)
+                .and_then(|cs| check(&cs, &mut std::io::stdout()));
+            match result {
+                Ok(true) => {}
+                Ok(false) => process::exit(1),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    process::exit(1);
+                }
+            }
+        }
+        Some(Command::Timing(timing_args)) => {
+            let result = ComponentStore::try_load_file(&path)
+                .and_then(|cs| timing(&cs, &timing_args, &mut std::io::stdout()));
+            if let Err(err) = result {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+        }
+        None => gui(&path),
+    }
+}
+
+fn gui(path: &PathBuf) {
+    // start with an empty model on errors, reported in the GUI,
+    // the model can be re-opened when fixed
+    let (_cs, _error) = match ComponentStore::try_load_file(path) {
+        Ok(cs) => (cs, None),
+        Err(err) => {
+            eprintln!("{}", err);
+            (ComponentStore { store: vec![] }, Some(err))
+        }
+    };
+
+    #[cfg(feature = "gui-egui")]
+    crate::gui_egui::gui(&_cs, path, _error).ok();
+
+    #[cfg(feature = "gui-vizia")]
+    crate::gui_vizia::gui(&_cs, path, _error);
+
+    #[cfg(not(any(feature = "gui-vizia", feature = "gui-egui")))]
+    {
+        eprintln!("No GUI available in this build, use `run` to simulate without GUI");
+        process::exit(2);
+    }
+}
+
+/// signals referred to as `id.name` or `id.index` (all outputs if none), returns an error
+/// for signals not found in the simulated model
+pub fn signals(simulator: &Simulator, signals: &[String]) -> Result<Vec<Input>, SimulatorError> {
+    if signals.is_empty() {
+        let mut inputs = vec![];
+        for id in &simulator.component_ids {
+            for index in 0..simulator.id_nr_outputs[id] {
+                inputs.push(Input::new(id, index));
+            }
+        }
+        return Ok(inputs);
+    }
+    signals
+        .iter()
+        .map(|signal| resolve_signal(&simulator.id_ports, signal))
+        .collect()
+}
+
+/// forced signal (`id.name=value` or `id.index=value`), returns an error for signals not found
+/// in the simulated model and malformed values
+pub fn parse_force(
+    simulator: &Simulator,
+    force: &str,
+) -> Result<(Input, SignalValue), SimulatorError> {
+    let (signal, value) = force.split_once('=').unwrap_or((force, ""));
+    let input = signals(simulator, &[signal.to_string()])?.remove(0);
+    let value = parse_value(value).ok_or_else(|| SimulatorError::Parse {
+        line: 1,
+        col: signal.len() + 2,
+        msg: format!("invalid value {} forced on {}", value, signal),
+    })?;
+    Ok((input, value))
+}
+
+/// simulate the model for the given number of cycles, and write the selected signals
+/// (comma separated, one line per cycle)
+pub fn run(
+    cs: &ComponentStore,
+    args: &RunArgs,
+    out: &mut impl Write,
+) -> Result<(), SimulatorError> {
+    let mut clock = 0;
+    let mut simulator = Simulator::try_new(cs, &mut clock)?;
+    simulator.set_debug(args.debug);
+    simulator.set_event_driven(args.event_driven);
+    simulator.set_activity(args.activity.is_some());
+    if args.four_state {
+        simulator.set_four_state(true, &mut clock);
+    }
+    if let Some(stimulus) = &args.stimulus {
+        simulator.load_stimulus(&PathBuf::from(stimulus), &mut clock)?;
+    }
+    for force in &args.force {
+        let (input, value) = parse_force(&simulator, force)?;
+        simulator.force_value(&input.id, input.index, value);
+    }
+    let inputs = signals(&simulator, &args.signals)?;
+
+    let io_err = |err: std::io::Error| SimulatorError::Io(err.to_string());
+    // named as accepted by conditions, stimulus and testbenches (`id.name`)
+    let header: Vec<String> = inputs
+        .iter()
+        .map(|input| output_name(&simulator.id_ports, input))
+        .collect();
+    writeln!(out, "cycle,{}", header.join(",")).map_err(io_err)?;
+
+    for cycle in 0..=args.cycles {
+        if cycle > 0 {
+            simulator.clock(&mut clock);
+        }
+        if args.trace || cycle == args.cycles {
+            let values: Vec<String> = inputs
+                .iter()
+                .map(|input| {
+                    fmt_value(
+                        simulator.get_input_value(input),
+                        simulator.get_input_width(input),
+                    )
+                })
+                .collect();
+            writeln!(out, "{},{}", clock, values.join(",")).map_err(io_err)?;
+        }
+    }
+    if let Some(vcd) = &args.vcd {
+        simulator.save_vcd(&PathBuf::from(vcd))?;
+    }
+    if let Some(activity) = &args.activity {
+        simulator.save_activity(&PathBuf::from(activity))?;
+    }
+    match simulator.undeclared_inputs().into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// run each testbench on a fresh simulation of the model, reporting the first mismatching cycle
+/// of failed testbenches, returns true if all testbenches passed
+pub fn test(
+    cs: &ComponentStore,
+    args: &TestArgs,
+    out: &mut impl Write,
+) -> Result<bool, SimulatorError> {
+    let io_err = |err: std::io::Error| SimulatorError::Io(err.to_string());
+    let mut passed = 0;
+    for path in &args.testbenches {
+        let mut clock = 0;
+        let mut simulator = Simulator::try_new(cs, &mut clock)?;
+        let report = Testbench::load_file(&PathBuf::from(path)).and_then(|mut testbench| {
+            testbench.four_state |= args.four_state;
+            simulator.run_testbench(&testbench, &mut clock)
+        });
+        match report {
+            Ok(report) => match report.mismatch {
+                None => {
+                    passed += 1;
+                    writeln!(
+                        out,
+                        "{}: ok, {} checks in {} cycles",
+                        path, report.checks, report.cycles
+                    )
+                    .map_err(io_err)?;
+                }
+                Some(mismatch) => {
+                    writeln!(out, "{}: FAILED\n{}", path, mismatch).map_err(io_err)?
+                }
+            },
+            Err(err) => writeln!(out, "{}: FAILED\n{}", path, err).map_err(io_err)?,
+        }
+    }
+    writeln!(
+        out,
+        "{} of {} testbenches passed",
+        passed,
+        args.testbenches.len()
+    )
+    .map_err(io_err)?;
+    Ok(passed == args.testbenches.len())
+}
+
+/// report the critical path and maximum clock frequency of the model,
+/// followed by the longest paths with the delay of each component
+pub fn timing(
+    cs: &ComponentStore,
+    args: &TimingArgs,
+    out: &mut impl Write,
+) -> Result<(), SimulatorError> {
+    let io_err = |err: std::io::Error| SimulatorError::Io(err.to_string());
+    let mut clock = 0;
+    let timing = Simulator::try_new(cs, &mut clock)?.timing();
+    writeln!(out, "{}", timing).map_err(io_err)?;
+    for path in timing.paths.iter().take(args.paths) {
+        writeln!(out, "{}", path).map_err(io_err)?;
+    }
+    Ok(())
+}
+
+/// report the problems found in the model, one per line, returns true if no errors were found
+pub fn check(cs: &ComponentStore, out: &mut impl Write) -> Result<bool, SimulatorError> {
+    let io_err = |err: std::io::Error| SimulatorError::Io(err.to_string());
+    let problems = cs.validate();
+    for problem in &problems {
+        writeln!(out, "{}", problem).map_err(io_err)?;
+    }
+    let errors = problems
+        .iter()
+        .filter(|p| p.severity == Severity::Error)
+        .count();
+    writeln!(
+        out,
+        "{} errors, {} warnings",
+        errors,
+        problems.len() - errors
+    )
+    .map_err(io_err)?;
+    Ok(errors == 0)
+}
+
+/// upgrade the model to the current format version, saved to `output`,
+/// returns the version upgraded from
+pub fn migrate(path: &PathBuf, output: &PathBuf) -> Result<u32, SimulatorError> {
+    let mut file =
+        File::open(path).map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))?;
+    let mut json = String::new();
+    file.read_to_string(&mut json)
+        .map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))?;
+
+    let version = migration::model_version(&json)?;
+    ComponentStore::try_load(&json)?.try_save_file(output)?;
+    Ok(version)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "components")]
+    use crate::components::counter;
+
+    #[test]
+    fn test_args() {
+        let args = Args::try_parse_from([
+            "syncrim",
+            "run",
+            "--model",
+            "x.json",
+            "--cycles",
+            "10",
+            "-s",
+            "reg.0",
+            "-s",
+            "add",
+            "--activity",
+            "a.json",
+        ])
+        .unwrap();
+        assert_eq!(args.model.as_deref(), Some("x.json"));
+        match args.command {
+            Some(Command::Run(run_args)) => {
+                assert_eq!(run_args.cycles, 10);
+                assert_eq!(run_args.signals, vec!["reg.0", "add"]);
+                assert!(!run_args.trace);
+                assert_eq!(run_args.activity.as_deref(), Some("a.json"));
+            }
+            _ => panic!("expected run command"),
+        }
+
+        let args = Args::try_parse_from(["syncrim", "test", "a.csv", "b.json"]).unwrap();
+        match args.command {
+            Some(Command::Test(test_args)) => {
+                assert_eq!(test_args.testbenches, vec!["a.csv", "b.json"])
+            }
+            _ => panic!("expected test command"),
+        }
+        assert!(Args::try_parse_from(["syncrim", "test"]).is_err());
+
+        let args = Args::try_parse_from(["syncrim", "check", "-m", "x.json"]).unwrap();
+        assert!(matches!(args.command, Some(Command::Check)));
+
+        let args = Args::try_parse_from(["syncrim", "timing", "--paths", "3"]).unwrap();
+        match args.command {
+            Some(Command::Timing(timing_args)) => assert_eq!(timing_args.paths, 3),
+            _ => panic!("expected timing command"),
+        }
+
+        // GUI
+        let args = Args::try_parse_from(["syncrim", "--model", "x.json"]).unwrap();
+        assert!(args.command.is_none());
+    }
+
+    // run for 3 cycles, printing the signals
+    #[cfg(feature = "components")]
+    fn run_args(signals: &[&str]) -> RunArgs {
+        RunArgs {
+            cycles: 3,
+            signals: signals.iter().map(|signal| signal.to_string()).collect(),
+            trace: false,
+            four_state: false,
+            output: None,
+            stimulus: None,
+            vcd: None,
+            debug: true,
+            event_driven: false,
+            force: vec![],
+            activity: None,
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "components")]
+    fn test_run() {
+        let cs = counter(8);
+        let mut args = run_args(&["reg", "add.overflow"]);
+        args.trace = true;
+        let mut out = vec![];
+        run(&cs, &args, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "cycle,reg.out,add.overflow\n1,0x00,0x0\n2,0x01,0x0\n3,0x02,0x0\n4,0x03,0x0\n"
+        );
+
+        // the same in event-driven mode
+        args.event_driven = true;
+        let mut out = vec![];
+        run(&cs, &args, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "cycle,reg.out,add.overflow\n1,0x00,0x0\n2,0x01,0x0\n3,0x02,0x0\n4,0x03,0x0\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "components")]
+    fn test_run_signals() {
+        let cs = counter(8);
+        // all outputs, after the last cycle
+        let mut args = run_args(&[]);
+        args.four_state = true;
+        let mut out = vec![];
+        run(&cs, &args, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "cycle,reg.out,c.out,add.sum,add.overflow\n4,X,0x01,X,X\n"
+        );
+
+        let args = run_args(&["reg.1"]);
+        assert_eq!(
+            run(&cs, &args, &mut vec![]),
+            Err(SimulatorError::UnknownSignal("reg.1".to_string()))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "components")]
+    fn test_run_force() {
+        let cs = counter(8);
+        // counting by 2, with the constant forced (the first sum was computed on reset)
+        let mut args = run_args(&["reg"]);
+        args.force = vec!["c.out=2".to_string()];
+        let mut out = vec![];
+        run(&cs, &args, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "cycle,reg.out\n4,0x05\n");
+
+        args.force = vec!["c=4x".to_string()];
+        assert_eq!(
+            run(&cs, &args, &mut vec![]),
+            Err(SimulatorError::Parse {
+                line: 1,
+                col: 3,
+                msg: "invalid value 4x forced on c".to_string()
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "components")]
+    fn test_check() {
+        // the overflow of the adder is not used, and all components are placed at the origin
+        let mut out = vec![];
+        assert!(check(&counter(8), &mut out).unwrap());
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "warning: add: output overflow is not used\n\
+             warning: reg: overlaps c\n\
+             warning: add: overlaps c\n\
+             0 errors, 3 warnings\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "components")]
+    fn test_timing() {
+        // reg (0.5 ns) -> add (2 ns) -> reg
+        let mut out = vec![];
+        timing(&counter(8), &TimingArgs { paths: 1 }, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "critical path 2.5 ns, maximum clock frequency 400.0 MHz\n\
+             2.5 ns to reg.r_in\n  reg +0.5 ns = 0.5 ns\n  add +2.0 ns = 2.5 ns\n"
+        );
+    }
+}
@@ -1,38 +1,198 @@
+use crate::activity::Activity;
+use crate::history::History;
+use crate::observer::Observer;
+use crate::stimulus::Vectors;
+use crate::validate::{Bounds, Problem};
 use petgraph::Graph;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[cfg(feature = "gui-vizia")]
 use vizia::prelude::*;
 
-pub type Signal = u32;
-pub type SignedSignal = i32;
+// Signals are stored as 128 bit values, wide enough for 64 bit data paths,
+// and 128 bit cache lines, while narrow signals remain plain integers.
+// The actual bit width of each signal is given by its `Ports` declaration,
+// arithmetic on signals of up to 64 bits is done in 64 bit words (e.g., by `Add`).
+pub type Signal = u128;
+pub type SignedSignal = i128;
+
+// Debug output of the simulator and components (printed by `debug!`), on by default,
+// the command line tools print it only on request (`run --debug`)
+static DEBUG_OUTPUT: AtomicBool = AtomicBool::new(true);
+
+/// enable or disable the debug output of the simulator and components
+pub fn set_debug_output(enabled: bool) {
+    DEBUG_OUTPUT.store(enabled, Ordering::Relaxed);
+}
+
+/// true if the debug output of the simulator and components is enabled
+pub fn debug_output() -> bool {
+    DEBUG_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// print debug output of the simulator and components, if enabled (see `set_debug_output`)
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::common::debug_output() {
+            println!($($arg)*);
+        }
+    };
+}
+
+// Default bit width of signals
+pub fn default_bit_width() -> u32 {
+    32
+}
+
+/// mask for the given bit width (clamped to 1..=128 bits)
+pub fn mask(bit_width: u32) -> Signal {
+    Signal::MAX >> (Signal::BITS - bit_width.clamp(1, Signal::BITS))
+}
+
+/// Contents of a memory (e.g., `Mem`) after reset
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResetMode {
+    /// the contents the simulator was created with (e.g., a loaded program)
+    #[default]
+    Reload,
+    /// all zero
+    Clear,
+}
+
+// Value formatted as hexadecimal, zero padded to its bit width
+pub fn fmt_signal(value: Signal, bit_width: u32) -> String {
+    let digits = bit_width.max(1) as usize / 4 + !bit_width.is_multiple_of(4) as usize;
+    format!("{:#0w$x}", value, w = 2 + digits)
+}
+
+// Signal value formatted as hexadecimal, or as X/Z if unknown or high-impedance
+pub fn fmt_value(value: SignalValue, bit_width: u32) -> String {
+    match value {
+        SignalValue::Data(value) => fmt_signal(value, bit_width),
+        _ => value.to_string(),
+    }
+}
+
+/// The value of a signal.
+///
+/// In two-state mode (default) all signals hold data.
+/// In four-state mode signals start out unknown (X), until driven by a component,
+/// and may be high-impedance (Z). Components propagate X for unknown inputs.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignalValue {
+    Data(Signal),
+    Unknown,
+    HighZ,
+}
+
+impl SignalValue {
+    /// the data, or None if unknown or high-impedance
+    pub fn data(self) -> Option<Signal> {
+        match self {
+            SignalValue::Data(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl From<Signal> for SignalValue {
+    fn from(value: Signal) -> Self {
+        SignalValue::Data(value)
+    }
+}
+
+impl fmt::Display for SignalValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignalValue::Data(value) => write!(f, "{}", value),
+            SignalValue::Unknown => write!(f, "X"),
+            SignalValue::HighZ => write!(f, "Z"),
+        }
+    }
+}
 
 #[cfg(not(any(feature = "gui-vizia", feature = "gui-egui")))]
-type Components = Vec<Rc<dyn Component>>;
+pub type Components = Vec<Rc<dyn Component>>;
 
 #[cfg(feature = "gui-vizia")]
-type Components = Vec<Rc<dyn ViziaComponent>>;
+pub type Components = Vec<Rc<dyn ViziaComponent>>;
 
 #[cfg(feature = "gui-egui")]
-type Components = Vec<Rc<dyn EguiComponent>>;
+pub type Components = Vec<Rc<dyn EguiComponent>>;
 
 #[cfg_attr(feature = "gui-vizia", derive(Lens))]
 #[derive(Clone)]
 pub struct Simulator {
     pub id_start_index: IdStartIndex,
 
-    // Components stored in topological evaluation order
+    // Components stored in evaluation order,
+    // sequential components first, followed by combinatorial components in topological order
     pub ordered_components: Components,
-    pub sim_state: Vec<Signal>,
+    // Number of sequential components (at the start of `ordered_components`)
+    pub nr_sequential: usize,
+    pub sim_state: Vec<SignalValue>,
+    // Bit width of each signal in `sim_state`
+    pub signal_widths: Vec<u32>,
+    // Four-state mode, signals are unknown (X) until driven
+    pub four_state: bool,
     pub id_nr_outputs: IdNrOutputs,
-    pub history: Vec<Vec<Signal>>,
+    // Current clock cycle
+    pub cycle: usize,
+    pub history: History,
+    // Input vectors applied on each clock (see `Simulator::set_stimulus`)
+    pub stimulus: Vectors,
+    // Internal state of each component in `ordered_components` when the simulator was created,
+    // passed to `Component::reset`
+    pub(crate) initial_state: Vec<Option<serde_json::Value>>,
     pub component_ids: Vec<String>,
-    pub graph: Graph<String, ()>,
+    // Scope of each component in `ordered_components`, prefixed to the ids of the component
+    // and its inputs, empty at the top level (see `ComponentStore::flatten`)
+    pub scopes: Vec<String>,
+    // Position and size of each component in `ordered_components` shown at the top level,
+    // None for wires and the components of subcircuits, for highlighting (e.g., the critical
+    // path or the activity heatmap) without serializing the components each frame
+    pub bounds: Vec<Option<Bounds>>,
+    // Index of the component being evaluated, ids are resolved in the scope of the component
+    pub(crate) scope: Option<usize>,
+    // Ports of each component, for naming inputs and outputs
+    pub id_ports: IdPorts,
+    // Debug mode, reads of inputs not declared in the `Ports` of the component are reported
+    pub debug: bool,
+    // Undeclared inputs read in debug mode, each reported once (see `Simulator::set_debug`)
+    pub(crate) undeclared_inputs: RefCell<Vec<SimulatorError>>,
+    // Event-driven mode, only components depending on changed outputs are evaluated
+    pub event_driven: bool,
+    // State after the last propagation in event-driven mode,
+    // None if all components are to be evaluated on the next clock
+    pub(crate) settled: Option<Vec<SignalValue>>,
+    // Combinatorial components (index in `ordered_components`) reading the outputs
+    // of each component, from the edges of the dependency graph
+    pub(crate) fanout: Vec<Vec<usize>>,
+    // Combinatorial components (index in `ordered_components`) with clocked inputs
+    // (see `Ports::clocked_inputs`)
+    pub(crate) clocked: Vec<usize>,
+    // Range of the outputs of each component in `ordered_components`, in the state
+    pub(crate) output_ranges: Vec<std::ops::Range<usize>>,
+    // Forced signals by index in `sim_state`, overriding the outputs set by their components
+    // (see `Simulator::force`)
+    pub forced: BTreeMap<usize, SignalValue>,
+    // Observers called on clock, evaluation, reset and un_clock (see `Simulator::add_observer`)
+    pub(crate) observers: Vec<Rc<RefCell<dyn Observer>>>,
+    // Toggle activity and coverage, None if not tracked (see `Simulator::set_activity`)
+    pub activity: Option<Activity>,
+    // Number of component evaluations, for profiling
+    pub nr_evaluations: usize,
+    pub graph: Graph<String, Input>,
 }
 
-#[derive(Serialize, Deserialize)]
+// deserialized along with port name resolution (see `ComponentStore::try_load`)
+#[derive(Serialize)]
 pub struct ComponentStore {
     pub store: Components,
 }
@@ -47,6 +207,9 @@ pub type IdStartIndex = HashMap<String, usize>;
 
 pub type IdNrOutputs = HashMap<String, usize>;
 
+// a mapping (id -> ports), as declared by the component
+pub type IdPorts = HashMap<String, Ports>;
+
 // Common functionality for all components
 #[typetag::serde(tag = "type")]
 pub trait Component {
@@ -58,6 +221,74 @@ pub trait Component {
 
     /// evaluation function
     fn evaluate(&self, _simulator: &mut Simulator) {}
+
+    /// sample the clocked inputs (see `Ports::clocked_inputs`) on the clock edge, from the state
+    /// before the edge, updating the internal state of the component (e.g., writing a register)
+    fn clock_edge(&self, _simulator: &Simulator) {}
+
+    /// propagation delay in ns, from the inputs to the outputs (from the clock edge
+    /// for sequential components), used for timing analysis (see `Simulator::timing`)
+    fn delay(&self) -> f32 {
+        0.0
+    }
+
+    /// capture internal state (e.g., memory contents) not held in the simulator state,
+    /// None for components without internal state
+    fn snapshot(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// restore internal state previously captured by `snapshot`,
+    /// discarding changes not yet taken by `undo_log`, returns an error
+    /// (`SimulatorError::CheckpointMismatch`) if the state was not captured by such a component
+    fn restore(&self, _state: &serde_json::Value) -> Result<(), SimulatorError> {
+        Ok(())
+    }
+
+    /// changes to the internal state since the previous call, as needed to undo them
+    /// (e.g., the previous contents of written memory), None if unchanged,
+    /// taken after each clock and kept in history for un_clock
+    fn undo_log(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// undo the changes of a clock, previously taken by `undo_log`
+    fn undo(&self, _log: &serde_json::Value) {}
+
+    /// return to the power-on state on `Simulator::reset`, given the internal state the
+    /// component had when the simulator was created (see `snapshot`), outputs set here
+    /// are kept by sequential components over the reset clock
+    fn reset(&self, _simulator: &mut Simulator, _initial: Option<&serde_json::Value>) {}
+
+    /// coverage points of the component (e.g., the inputs selected by a `Mux`), reported
+    /// with the number of cycles they were hit when activity is tracked (see `Simulator::set_activity`)
+    fn coverage(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /// coverage points hit in the current cycle, given the state after the clock
+    fn covered(&self, _simulator: &Simulator) -> Vec<String> {
+        vec![]
+    }
+
+    /// components a hierarchical component (e.g., a subcircuit) is expanded into for simulation,
+    /// as (scope, components), where the scope (relative to the scope of the component)
+    /// is prefixed to the ids and inputs of the components, empty for other components
+    fn expand(&self) -> Result<Vec<(String, Components)>, SimulatorError> {
+        Ok(vec![])
+    }
+
+    /// the model instantiated by a hierarchical component (e.g., a subcircuit), loaded
+    /// standalone for the GUIs to drill into, None for other components
+    fn model(&self) -> Option<Result<ComponentStore, SimulatorError>> {
+        None
+    }
+
+    /// component specific problems, found before simulation (see `ComponentStore::validate`),
+    /// given the ports of all components
+    fn validate(&self, _id_ports: &IdPorts) -> Vec<Problem> {
+        vec![]
+    }
 }
 
 // Specific functionality for Vizia frontend
@@ -86,16 +317,65 @@ pub trait EguiComponent: Component {
 #[derive(Debug, Clone)]
 pub struct Ports {
     pub inputs: Vec<Input>,
+    // Maximum bit width accepted by each input (in the order of `inputs`)
+    pub input_widths: Vec<u32>,
+    // Name of each input (in the order of `inputs`), e.g., `a_in`
+    pub input_names: Vec<String>,
+    // Inputs of a combinatorial component sampled only on the clock edge (by index in `inputs`,
+    // see `Component::clock_edge`), e.g., the write port of a register file, the component
+    // does not depend on these during evaluation
+    pub clocked_inputs: Vec<usize>,
     pub out_type: OutputType,
     pub outputs: Vec<Output>,
+    // Bit width of each output (in the order of `outputs`)
+    pub output_widths: Vec<u32>,
+    // Name of each output (in the order of `outputs`), e.g., `sum`
+    pub output_names: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+// Port names, as declared in `Ports`
+pub fn port_names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+/// The output `index` of the component `id`.
+///
+/// In models the output is given either by index or by name, e.g.,
+/// `{ "id": "add", "index": 1 }` or `{ "id": "add", "index": "overflow" }`,
+/// names are resolved to indices when the model is loaded (see `ComponentStore::try_load`).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(try_from = "InputDef")]
 pub struct Input {
     pub id: String,
     pub index: usize,
 }
 
+// input as given in a model
+#[derive(Deserialize)]
+struct InputDef {
+    id: String,
+    index: PortIndex,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PortIndex {
+    Index(usize),
+    Name(String),
+}
+
+impl TryFrom<InputDef> for Input {
+    type Error = String;
+
+    fn try_from(def: InputDef) -> Result<Self, Self::Error> {
+        let index = match def.index {
+            PortIndex::Index(index) => index,
+            PortIndex::Name(name) => crate::component_store::resolve_port(&def.id, &name)?,
+        };
+        Ok(Input { id: def.id, index })
+    }
+}
+
 impl Input {
     pub fn new(id: &str, index: usize) -> Self {
         Input {
@@ -105,6 +385,26 @@ impl Input {
     }
 }
 
+/// Output referred to as `id.name` or `id.index` (or `id` for index 0), e.g., `add.overflow`
+/// or `add.1`, on the command line, in stimulus and testbench headers, and in conditions,
+/// returns an error if the output is not found among the ports of the model.
+pub fn resolve_signal(id_ports: &IdPorts, signal: &str) -> Result<Input, SimulatorError> {
+    let output = |id: &str, port: &str| {
+        let ports = id_ports.get(id)?;
+        let index = match port.parse::<usize>() {
+            Ok(index) => index,
+            Err(_) => ports.output_names.iter().position(|name| name == port)?,
+        };
+        (index < ports.outputs.len()).then(|| Input::new(id, index))
+    };
+    output(signal, "0")
+        .or_else(|| {
+            let (id, port) = signal.rsplit_once('.')?;
+            output(id, port)
+        })
+        .ok_or_else(|| SimulatorError::UnknownSignal(signal.to_string()))
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub enum OutputType {
     // Will be evaluated as a combinatorial function from inputs to outputs
@@ -120,3 +420,193 @@ pub enum Output {
     // Will be evaluated as a function
     Function,
 }
+
+// Errors detected when loading a model or building a simulator from it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimulatorError {
+    // The same component identifier is used more than once
+    DuplicateId(String),
+    // A component input (`port`) refers to an identifier not found in the model
+    UnknownSource {
+        component: String,
+        port: String,
+        input: Input,
+    },
+    // A component input (`port`) refers to an output index the source does not have
+    PortIndexOutOfRange {
+        component: String,
+        port: String,
+        input: Input,
+        nr_outputs: usize,
+    },
+    // A component input (`port`) is connected to an output wider than the input accepts
+    WidthMismatch {
+        component: String,
+        port: String,
+        // source output, as `id.name`
+        output: String,
+        bit_width: u32,
+        expected: u32,
+    },
+    // A component port (`port`) is declared with a bit width of 0, or wider than a `Signal`
+    InvalidWidth {
+        component: String,
+        port: String,
+        bit_width: u32,
+    },
+    // Combinatorial components depend on each other in one or more cycles
+    CombinationalLoop {
+        cycles: Vec<Cycle>,
+    },
+    // The model (checkpoint or stimulus) file could not be parsed
+    Parse {
+        line: usize,
+        col: usize,
+        msg: String,
+    },
+    // The model (checkpoint or stimulus) file could not be read
+    Io(String),
+    // The checkpoint was not taken from a simulation of the model
+    CheckpointMismatch(String),
+    // A signal condition is malformed, or refers to signals not found in the model
+    Condition {
+        col: usize,
+        msg: String,
+    },
+    // A signal (`id.index`) not found in the model
+    UnknownSignal(String),
+    // The model was saved in a format version newer than supported
+    UnsupportedVersion {
+        version: u32,
+        supported: u32,
+    },
+    // A subcircuit could not be expanded
+    Subcircuit {
+        component: String,
+        msg: String,
+    },
+    // A component read an input not declared in its `Ports` (detected in debug mode)
+    UndeclaredInput {
+        component: String,
+        input: Input,
+    },
+    // Breakpoints dropped as not valid in the model, (breakpoint, error) for each
+    InvalidBreakpoints(Vec<(String, String)>),
+}
+
+impl fmt::Display for SimulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulatorError::DuplicateId(id) => {
+                write!(f, "Component identifier {} is defined twice", id)
+            }
+            SimulatorError::UnknownSource {
+                component,
+                port,
+                input,
+            } => write!(
+                f,
+                "Component {}.{} reads {} at index {}, but there is no component {}",
+                component, port, input.id, input.index, input.id
+            ),
+            SimulatorError::PortIndexOutOfRange {
+                component,
+                port,
+                input,
+                nr_outputs,
+            } => write!(
+                f,
+                "Component {}.{} reads {} at index {}, where {} has only {} outputs.",
+                component, port, input.id, input.index, input.id, nr_outputs
+            ),
+            SimulatorError::WidthMismatch {
+                component,
+                port,
+                output,
+                bit_width,
+                expected,
+            } => write!(
+                f,
+                "Component {}.{} reads {} of width {}, where at most {} bits are accepted.",
+                component, port, output, bit_width, expected
+            ),
+            SimulatorError::InvalidWidth {
+                component,
+                port,
+                bit_width,
+            } => write!(
+                f,
+                "Component {}.{} has bit width {}, where 1 to {} bits are supported.",
+                component,
+                port,
+                bit_width,
+                Signal::BITS
+            ),
+            SimulatorError::CombinationalLoop { cycles } => {
+                write!(f, "Combinatorial loop detected")?;
+                for cycle in cycles {
+                    write!(f, "\n  {}", cycle)?;
+                }
+                Ok(())
+            }
+            SimulatorError::Parse { line, col, msg } => {
+                write!(f, "Parse error at line {}, column {}: {}", line, col, msg)
+            }
+            SimulatorError::Io(msg) => write!(f, "IO error: {}", msg),
+            SimulatorError::CheckpointMismatch(msg) => {
+                write!(f, "Checkpoint does not match the model: {}", msg)
+            }
+            SimulatorError::Condition { col, msg } => {
+                write!(f, "Condition error at column {}: {}", col, msg)
+            }
+            SimulatorError::UnknownSignal(signal) => {
+                write!(f, "Signal {} not found in the model", signal)
+            }
+            SimulatorError::UnsupportedVersion { version, supported } => write!(
+                f,
+                "Model format version {} is newer than the supported version {}",
+                version, supported
+            ),
+            SimulatorError::Subcircuit { component, msg } => {
+                write!(f, "Subcircuit {}: {}", component, msg)
+            }
+            SimulatorError::UndeclaredInput { component, input } => write!(
+                f,
+                "Component {} reads {} at index {}, which is not declared as an input",
+                component, input.id, input.index
+            ),
+            SimulatorError::InvalidBreakpoints(breakpoints) => {
+                write!(f, "Breakpoints removed, as not valid in the model")?;
+                for (breakpoint, err) in breakpoints {
+                    write!(f, "\n  {}: {}", breakpoint, err)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimulatorError {}
+
+// A combinatorial loop (a strongly connected component of the dependency graph)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    // component ids, in the order found by following the dependencies
+    pub ids: Vec<String>,
+    // (component id, input) for each dependency between components in the loop
+    pub inputs: Vec<(String, Input)>,
+    // (input, output) names of each dependency, as `id.name`
+    pub names: Vec<(String, String)>,
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.ids.join(" -> "))?;
+        let inputs: Vec<String> = self
+            .names
+            .iter()
+            .map(|(input, output)| format!("{} reads {}", input, output))
+            .collect();
+        write!(f, " ({})", inputs.join(", "))
+    }
+}
@@ -1,29 +1,401 @@
-use crate::common::ComponentStore;
+use crate::common::{ComponentStore, Components, IdPorts, Input, SimulatorError};
+use crate::debug;
+use crate::migration::{self, MODEL_VERSION};
+use crate::simulator;
+use petgraph::Graph;
+use serde::{Deserialize, Deserializer, Serialize};
 
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::{fs::File, io::prelude::*, path::PathBuf};
 
+// Maximum depth of nested subcircuits, bounds the expansion of recursive subcircuits
+pub(crate) const MAX_DEPTH: usize = 32;
+
+// Resolution of port names in inputs (`Input`), done in two passes over the model.
+// The first pass collects the output names of the components of each model
+// (the model and any embedded models), the second resolves the names.
+// Models are numbered in the order they are deserialized, the same for both passes.
+#[derive(Default)]
+struct PortResolver {
+    // first pass, names are not yet known
+    collect: bool,
+    // any names found in the first pass
+    named: bool,
+    // output names of the components, by model
+    models: Vec<HashMap<String, Vec<String>>>,
+    // number of models deserialized
+    next: usize,
+    // models being deserialized (innermost last)
+    stack: Vec<usize>,
+}
+
+thread_local! {
+    static RESOLVER: RefCell<Option<PortResolver>> = const { RefCell::new(None) };
+    // directory of the model file being loaded, paths in the model are relative to it
+    static MODEL_DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+// directory of the model file being loaded, None if not loaded from file
+pub(crate) fn model_dir() -> Option<PathBuf> {
+    MODEL_DIR.with(|dir| dir.borrow().clone())
+}
+
+// load with paths relative to `dir`, the directory of the outer model is restored when done
+pub(crate) fn with_model_dir<T>(dir: Option<PathBuf>, load: impl FnOnce() -> T) -> T {
+    let outer = MODEL_DIR.with(|model_dir| model_dir.replace(dir));
+    let result = load();
+    MODEL_DIR.with(|model_dir| model_dir.replace(outer));
+    result
+}
+
+// output index of the port `name` of the component `id`, in the model being loaded
+pub(crate) fn resolve_port(id: &str, name: &str) -> Result<usize, String> {
+    RESOLVER.with(|resolver| match &mut *resolver.borrow_mut() {
+        None => Err(format!(
+            "port {}.{} given by name outside of a model",
+            id, name
+        )),
+        Some(resolver) if resolver.collect => {
+            resolver.named = true;
+            Ok(0)
+        }
+        Some(resolver) => resolver
+            .stack
+            .last()
+            .and_then(|model| resolver.models[*model].get(id))
+            .and_then(|names| names.iter().position(|n| n == name))
+            .ok_or_else(|| format!("unknown port {}.{}", id, name)),
+    })
+}
+
+impl<'de> Deserialize<'de> for ComponentStore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Store {
+            store: Components,
+        }
+
+        let model = RESOLVER.with(|resolver| {
+            resolver.borrow_mut().as_mut().map(|resolver| {
+                let model = resolver.next;
+                resolver.next += 1;
+                if resolver.collect {
+                    resolver.models.push(HashMap::new());
+                }
+                resolver.stack.push(model);
+                model
+            })
+        });
+        let store = Store::deserialize(deserializer)?.store;
+        if let Some(model) = model {
+            let collect = RESOLVER.with(|resolver| {
+                let mut resolver = resolver.borrow_mut();
+                let resolver = resolver.as_mut().unwrap();
+                resolver.stack.pop();
+                resolver.collect
+            });
+            if collect {
+                // not borrowed while collecting, as the ports of a subcircuit are
+                // resolved from its model, which is loaded in turn
+                let names = store
+                    .iter()
+                    .map(|c| {
+                        let (id, ports) = c.get_id_ports();
+                        (id, ports.output_names)
+                    })
+                    .collect();
+                RESOLVER.with(|resolver| {
+                    resolver.borrow_mut().as_mut().unwrap().models[model] = names;
+                });
+            }
+        }
+        Ok(ComponentStore { store })
+    }
+}
+
+fn parse(json: &str) -> Result<ComponentStore, SimulatorError> {
+    serde_json::from_str(json).map_err(|err| SimulatorError::Parse {
+        line: err.line(),
+        col: err.column(),
+        msg: err.to_string(),
+    })
+}
+
+// model as saved, tagged by format version
+#[derive(Serialize)]
+struct VersionedStore<'a> {
+    version: u32,
+    #[serde(flatten)]
+    model: &'a ComponentStore,
+}
+
 impl ComponentStore {
     pub fn load(json: &str) -> Self {
-        serde_json::from_str(json).unwrap()
+        ComponentStore::try_load(json).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// load model, models saved in an older format version are migrated,
+    /// and outputs given by name are resolved to indices
+    pub fn try_load(json: &str) -> Result<Self, SimulatorError> {
+        let json = migration::migrate(json)?;
+
+        // models may be loaded while loading a model, the resolver is restored when done
+        let collect = PortResolver {
+            collect: true,
+            ..Default::default()
+        };
+        let outer = RESOLVER.with(|resolver| resolver.replace(Some(collect)));
+        let mut result = parse(&json);
+        let named = RESOLVER.with(|resolver| {
+            let mut resolver = resolver.borrow_mut();
+            let resolver = resolver.as_mut().unwrap();
+            resolver.collect = false;
+            resolver.next = 0;
+            resolver.named
+        });
+        // second pass, only needed if any output is given by name
+        if result.is_ok() && named {
+            result = parse(&json);
+        }
+        RESOLVER.with(|resolver| resolver.replace(outer));
+        result
     }
 
     pub fn load_file(path: &PathBuf) -> Self {
-        let mut file = File::open(path).unwrap();
+        ComponentStore::try_load_file(path).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_load_file(path: &PathBuf) -> Result<Self, SimulatorError> {
+        let mut file =
+            File::open(path).map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))?;
         let mut json = String::new();
-        file.read_to_string(&mut json).unwrap();
+        file.read_to_string(&mut json)
+            .map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))?;
+
+        let dir = path.parent().map(|dir| dir.to_path_buf());
+        with_model_dir(dir, || ComponentStore::try_load(&json))
+    }
 
-        ComponentStore::load(&json)
+    /// model as JSON, tagged by the current format version
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&VersionedStore {
+            version: MODEL_VERSION,
+            model: self,
+        })
+        .unwrap()
     }
 
     pub fn save_file(&self, path: &PathBuf) {
-        let json = serde_json::to_string(self).unwrap();
-        println!("json: {}", json);
-        println!("path {:?}", path);
-        let mut file = File::create(path).unwrap();
-        file.write_all(json.as_bytes()).unwrap();
+        debug!("json: {}", self.to_json());
+        debug!("path {:?}", path);
+        self.try_save_file(path)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    pub fn try_save_file(&self, path: &PathBuf) -> Result<(), SimulatorError> {
+        let mut file =
+            File::create(path).map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))?;
+        file.write_all(self.to_json().as_bytes())
+            .map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))
+    }
+
+    /// components of the model with hierarchical components (subcircuits) expanded,
+    /// as the scope of each component (see `Component::expand`) and the components
+    pub fn flatten(&self) -> Result<(Vec<String>, Components), SimulatorError> {
+        let mut scopes = vec![];
+        let mut components = vec![];
+        flatten(&self.store, "", 0, &mut scopes, &mut components)?;
+        Ok((scopes, components))
+    }
+
+    /// dependency graph, with a node for each component (id),
+    /// and an edge for each input of a combinatorial component
+    pub fn dependency_graph(&self) -> Graph<String, Input> {
+        let id_ports: Vec<_> = self.store.iter().map(|c| c.get_id_ports()).collect();
+        simulator::dependency_graph(&id_ports)
+    }
+
+    /// ports of the components by id
+    pub fn id_ports(&self) -> IdPorts {
+        self.store.iter().map(|c| c.get_id_ports()).collect()
+    }
+
+    /// save dependency graph as `dot` file with `.gv` extension,
+    /// combinatorial loops (if any) are coloured red, and the critical path blue
+    pub fn save_dot(&self, path: &PathBuf) {
+        let critical = self
+            .timing()
+            .map(|timing| timing.critical_ids())
+            .unwrap_or_default();
+        simulator::save_dot(&self.dependency_graph(), &self.id_ports(), &critical, path);
     }
 
     pub fn to_(&self) {
         self.store.iter().for_each(|c| c.to_());
     }
 }
+
+// expand components in scope, hierarchical components are followed by the components
+// they are expanded into
+fn flatten(
+    store: &Components,
+    scope: &str,
+    depth: usize,
+    scopes: &mut Vec<String>,
+    components: &mut Components,
+) -> Result<(), SimulatorError> {
+    for c in store {
+        let expanded = c.expand()?;
+        if !expanded.is_empty() && depth == MAX_DEPTH {
+            return Err(SimulatorError::Subcircuit {
+                component: format!("{}{}", scope, c.get_id_ports().0),
+                msg: format!("nested more than {} levels deep", MAX_DEPTH),
+            });
+        }
+        scopes.push(scope.to_string());
+        components.push(c.clone());
+        for (inner_scope, inner) in expanded {
+            flatten(
+                &inner,
+                &format!("{}{}", scope, inner_scope),
+                depth + 1,
+                scopes,
+                components,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_try_load_parse_error() {
+        let json = "{\n  \"store\": [\n    {\"type\": \"NoSuchComponent\"}\n  ]\n}";
+        match ComponentStore::try_load(json) {
+            Err(SimulatorError::Parse { line, .. }) => assert_eq!(line, 3),
+            _ => panic!("expected parse error"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "components")]
+    fn test_load_wide_constant() {
+        use crate::common::{Signal, Simulator};
+        use crate::components::Constant;
+        use std::rc::Rc;
+
+        let cs = ComponentStore {
+            store: vec![Rc::new(Constant {
+                id: "c".to_string(),
+                pos: (0.0, 0.0),
+                value: Signal::MAX - 1,
+                bit_width: 128,
+            })],
+        };
+        let json = serde_json::to_string(&cs).unwrap();
+        let cs = ComponentStore::try_load(&json).unwrap();
+        let mut clock = 0;
+        let simulator = Simulator::new(&cs, &mut clock);
+        assert_eq!(
+            simulator.get_input_val(&Input::new("c", 0)),
+            Signal::MAX - 1
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "components")]
+    fn test_optional_fields() {
+        use crate::components::Register;
+        use std::rc::Rc;
+
+        // optional fields not given are left out, rather than saved as null
+        let register = |reset_value, delay| ComponentStore {
+            store: vec![Rc::new(Register {
+                id: "reg".to_string(),
+                pos: (0.0, 0.0),
+                r_in: Input::new("reg", 0),
+                bit_width: 8,
+                reset_value,
+                delay,
+            })],
+        };
+        let json = serde_json::to_string(&register(None, None)).unwrap();
+        assert!(!json.contains("reset_value") && !json.contains("delay"));
+        assert_eq!(ComponentStore::try_load(&json).unwrap().store.len(), 1);
+
+        let json = serde_json::to_string(&register(Some(1), Some(0.25))).unwrap();
+        assert!(json.contains("\"reset_value\":1") && json.contains("\"delay\":0.25"));
+    }
+
+    #[test]
+    #[cfg(feature = "components")]
+    fn test_version() {
+        use crate::components::ProbeOut;
+        use crate::migration::MODEL_VERSION;
+        use std::rc::Rc;
+
+        let cs = ComponentStore {
+            store: vec![Rc::new(ProbeOut::new("po"))],
+        };
+        let json = cs.to_json();
+        assert!(json.starts_with(&format!("{{\"version\":{},", MODEL_VERSION)));
+        assert_eq!(ComponentStore::try_load(&json).unwrap().store.len(), 1);
+
+        // models saved before versioning
+        let json = serde_json::to_string(&cs).unwrap();
+        assert_eq!(ComponentStore::try_load(&json).unwrap().store.len(), 1);
+
+        let json = json.replacen('{', "{\"version\":1000,", 1);
+        assert!(matches!(
+            ComponentStore::try_load(&json),
+            Err(SimulatorError::UnsupportedVersion { version: 1000, .. })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "components")]
+    fn test_named_ports() {
+        let json = r#"{"store": [
+            {"type": "ProbeOut", "id": "po1"},
+            {"type": "Add", "id": "add", "pos": [0, 0],
+             "a_in": {"id": "po1", "index": "out"}, "b_in": {"id": "po1", "index": 0}},
+            {"type": "Probe", "id": "p", "pos": [0, 0], "input": {"id": "add", "index": "overflow"}},
+            {"type": "Subcircuit", "id": "sub", "pos": [0, 0], "width": 80, "height": 80,
+             "model": {"store": [
+                {"type": "ProbeOut", "id": "a"},
+                {"type": "Register", "id": "reg", "pos": [0, 0], "r_in": {"id": "a", "index": "out"}}
+             ]},
+             "inputs": [["a", {"id": "add", "index": "sum"}]],
+             "outputs": ["reg.out"]},
+            {"type": "Probe", "id": "q", "pos": [0, 0], "input": {"id": "sub", "index": "reg.out"}}
+        ]}"#;
+        let cs = ComponentStore::try_load(json).unwrap();
+        let id_ports = cs.id_ports();
+        assert_eq!(id_ports["p"].inputs, vec![Input::new("add", 1)]);
+        assert_eq!(id_ports["add"].inputs, vec![Input::new("po1", 0); 2]);
+        assert_eq!(id_ports["q"].inputs, vec![Input::new("sub", 0)]);
+
+        // names are resolved within the model they are given in
+        let json = json.replace(r#""index": "sum""#, r#""index": "out""#);
+        match ComponentStore::try_load(&json) {
+            Err(SimulatorError::Parse { line, msg, .. }) => {
+                assert_eq!(line, 11);
+                assert!(msg.starts_with("unknown port add.out"), "{}", msg);
+            }
+            result => panic!("unexpected {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_try_load_file_missing() {
+        let path = PathBuf::from("no_such_model.json");
+        assert!(matches!(
+            ComponentStore::try_load_file(&path),
+            Err(SimulatorError::Io(_))
+        ));
+    }
+}
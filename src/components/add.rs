@@ -1,4 +1,8 @@
-use crate::common::{Component, Input, Output, OutputType, Ports, Signal, SignedSignal, Simulator};
+use crate::common::{
+    default_bit_width, port_names, Component, Input, Output, OutputType, Ports, Signal,
+    SignalValue, SignedSignal, Simulator,
+};
+use crate::debug;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -7,12 +11,34 @@ pub struct Add {
     pub pos: (f32, f32),
     pub a_in: Input,
     pub b_in: Input,
+    #[serde(default = "default_bit_width")]
+    pub bit_width: u32,
+    // propagation delay in ns, if not given 2 ns (see `Component::delay`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<f32>,
+}
+
+// signed addition at the given bit width, the sum and if it overflows,
+// the operands are shifted to the top bits, such that the sum overflows the machine word
+// exactly when it overflows the bit width, narrow signals (the common case) are added
+// as 64 bit words, and only wider signals as 128 bit words
+fn add(a: Signal, b: Signal, bit_width: u32) -> (Signal, bool) {
+    if bit_width <= i64::BITS {
+        let shift = i64::BITS - bit_width;
+        let (sum, overflow) = ((a as i64) << shift).overflowing_add((b as i64) << shift);
+        ((sum >> shift) as Signal, overflow)
+    } else {
+        let shift = SignedSignal::BITS - bit_width;
+        let (sum, overflow) =
+            ((a as SignedSignal) << shift).overflowing_add((b as SignedSignal) << shift);
+        ((sum >> shift) as Signal, overflow)
+    }
 }
 
 #[typetag::serde]
 impl Component for Add {
     fn to_(&self) {
-        println!("Add");
+        debug!("Add");
     }
 
     fn get_id_ports(&self) -> (String, Ports) {
@@ -20,29 +46,73 @@ impl Component for Add {
             self.id.clone(),
             Ports {
                 inputs: vec![self.a_in.clone(), self.b_in.clone()],
+                input_widths: vec![self.bit_width; 2],
+                input_names: port_names(&["a_in", "b_in"]),
+                clocked_inputs: vec![],
                 out_type: OutputType::Combinatorial,
                 outputs: vec![Output::Function; 2],
+                output_widths: vec![self.bit_width, 1],
+                output_names: port_names(&["sum", "overflow"]),
             },
         )
     }
 
+    fn delay(&self) -> f32 {
+        self.delay.unwrap_or(2.0)
+    }
+
     // propagate addition to output
     fn evaluate(&self, simulator: &mut Simulator) {
         // get input values
-        let a_in = simulator.get_input_val(&self.a_in);
-        let b_in = simulator.get_input_val(&self.b_in);
+        let (a_in, b_in) = match (
+            simulator.get_input_value(&self.a_in),
+            simulator.get_input_value(&self.b_in),
+        ) {
+            (SignalValue::Data(a_in), SignalValue::Data(b_in)) => (a_in, b_in),
+            _ => {
+                // unknown operand, sum and overflow are unknown
+                simulator.set_id_index_value(&self.id, 0, SignalValue::Unknown);
+                simulator.set_id_index_value(&self.id, 1, SignalValue::Unknown);
+                return;
+            }
+        };
 
-        // compute addition (notice will panic on overflow)
-        let (value, overflow) =
-            SignedSignal::overflowing_add(a_in as SignedSignal, b_in as SignedSignal);
+        // compute signed addition at the given bit width
+        let (value, overflow) = add(a_in, b_in, self.bit_width);
 
-        println!(
+        debug!(
             "eval Add a_in {}, b_in {}, value = {}, overflow = {}",
             a_in, b_in, value, overflow
         );
 
-        // set output
-        simulator.set_id_index(&self.id, 0, value as Signal);
+        // set output (masked to bit width by the simulator)
+        simulator.set_id_index(&self.id, 0, value);
         simulator.set_id_index(&self.id, 1, Signal::from(overflow));
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::mask;
+
+    #[test]
+    fn test_add_narrow_wide() {
+        // at the widths of the narrow path (up to 64 bits) and of the wide path
+        for bit_width in [2, 8, 63, 64, 65, 127, 128] {
+            // sum masked to the width (as by the simulator)
+            let add = |a, b| {
+                let (sum, overflow) = add(a, b, bit_width);
+                (sum & mask(bit_width), overflow)
+            };
+            // the largest positive value, the smallest negative value and -1
+            let max = mask(bit_width) >> 1;
+            let min = max + 1;
+            let minus_one = mask(bit_width);
+            assert_eq!(add(max, 0), (max, false));
+            assert_eq!(add(max, 1), (min, true));
+            assert_eq!(add(min, min), (0, true));
+            assert_eq!(add(minus_one, minus_one), (minus_one - 1, false));
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use crate::common::{Component, Output, OutputType, Ports, Signal, Simulator};
+use crate::common::{
+    default_bit_width, port_names, Component, Output, OutputType, Ports, Signal, Simulator,
+};
+use crate::debug;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -6,12 +9,14 @@ pub struct Constant {
     pub id: String,
     pub pos: (f32, f32),
     pub value: Signal, // perhaps vector here ... not sure
+    #[serde(default = "default_bit_width")]
+    pub bit_width: u32,
 }
 
 #[typetag::serde]
 impl Component for Constant {
     fn to_(&self) {
-        println!("constant {:?}", self.value);
+        debug!("constant {:?}", self.value);
     }
 
     fn get_id_ports(&self) -> (String, Ports) {
@@ -20,9 +25,14 @@ impl Component for Constant {
             Ports {
                 // Constants do not take any inputs
                 inputs: vec![],
+                input_widths: vec![],
+                input_names: vec![],
+                clocked_inputs: vec![],
                 out_type: OutputType::Combinatorial,
                 // Single output value
                 outputs: vec![Output::Constant(self.value)],
+                output_widths: vec![self.bit_width],
+                output_names: port_names(&["out"]),
             },
         )
     }
@@ -1,4 +1,8 @@
-use crate::common::{Component, Input, Output, OutputType, Ports, Signal, Simulator};
+use crate::common::{
+    port_names, Component, Input, Output, OutputType, Ports, ResetMode, Signal, SignalValue,
+    Simulator, SimulatorError,
+};
+use crate::debug;
 use num_enum::IntoPrimitive;
 use num_enum::TryFromPrimitive;
 use serde::{Deserialize, Serialize};
@@ -22,31 +26,57 @@ pub struct Mem {
     pub sign_extend: Input,
     pub size: Input,
 
-    // memory
+    // memory, rewound on un_clock (see `undo_log`/`undo`)
     pub memory: Memory,
-    // later history... tbd
+    // memory after reset, if not given reloaded as the simulator was created
+    #[serde(default)]
+    pub reset: ResetMode,
+
+    // propagation delay in ns, if not given 10 ns (see `Component::delay`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<f32>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Memory {
     bytes: RefCell<HashMap<usize, u8>>,
-}
-
-impl Default for Memory {
-    fn default() -> Self {
-        Self::new()
-    }
+    // (addr, previous byte) of the writes since the log was last taken, in order
+    #[serde(skip)]
+    log: RefCell<Vec<(usize, Option<u8>)>>,
 }
 
 impl Memory {
     pub fn new() -> Self {
-        Memory {
-            bytes: RefCell::new(HashMap::new()),
-        }
+        Self::default()
+    }
+
+    // set or forget (None) a byte, logging the previous byte
+    fn set(&self, addr: usize, byte: Option<u8>) {
+        let previous = match byte {
+            Some(byte) => self.bytes.borrow_mut().insert(addr, byte),
+            None => self.bytes.borrow_mut().remove(&addr),
+        };
+        self.log.borrow_mut().push((addr, previous));
     }
 
     fn align(&self, addr: usize, size: usize) -> Signal {
-        (addr % size != 0) as Signal
+        !addr.is_multiple_of(size) as Signal
+    }
+
+    // all bytes in range have been written
+    fn initialized(&self, addr: usize, size: usize) -> bool {
+        (0..size).all(|i| self.bytes.borrow().contains_key(&(addr + i)))
+    }
+
+    // forget the bytes in range, such that they read as unknown (X) in four-state mode
+    fn invalidate(&self, addr: usize, size: usize) {
+        (0..size).for_each(|i| self.set(addr + i, None));
+    }
+
+    // forget all bytes
+    fn invalidate_all(&self) {
+        let addrs: Vec<usize> = self.bytes.borrow().keys().copied().collect();
+        addrs.into_iter().for_each(|addr| self.set(addr, None));
     }
 
     fn read(&self, addr: usize, size: usize, sign_extend: bool, big_endian: bool) -> Signal {
@@ -56,7 +86,7 @@ impl Memory {
 
         let data = data.as_slice();
 
-        println!("{:x?}", data);
+        debug!("{:x?}", data);
         match size {
             1 => {
                 if sign_extend {
@@ -68,37 +98,38 @@ impl Memory {
             2 => {
                 if sign_extend {
                     if big_endian {
-                        println!("read signed half word be");
+                        debug!("read signed half word be");
                         let i_16 = i16::from_be_bytes(data.try_into().unwrap());
-                        println!("i_16 {:x?}", i_16);
+                        debug!("i_16 {:x?}", i_16);
                         let i_32 = i_16 as i32;
-                        println!("i_32 {:x?}", i_32);
+                        debug!("i_32 {:x?}", i_32);
                         i_32 as Signal
                     } else {
-                        println!("read signed half word le");
+                        debug!("read signed half word le");
                         let i_16 = i16::from_le_bytes(data.try_into().unwrap());
-                        println!("i_16 {:x?}", i_16);
+                        debug!("i_16 {:x?}", i_16);
                         let i_32 = i_16 as i32;
-                        println!("i_32 {:x?}", i_32);
+                        debug!("i_32 {:x?}", i_32);
                         i_32 as Signal
                     }
                 } else if big_endian {
-                    println!("read unsigned half word be");
+                    debug!("read unsigned half word be");
                     let u_16 = u16::from_be_bytes(data.try_into().unwrap());
-                    println!("u_16 {:x?}", u_16);
+                    debug!("u_16 {:x?}", u_16);
                     let u_32 = u_16 as u32;
-                    println!("u_32 {:x?}", u_32);
+                    debug!("u_32 {:x?}", u_32);
                     u_32 as Signal
                 } else {
-                    println!("read unsigned half word le");
+                    debug!("read unsigned half word le");
                     let u_16 = u16::from_le_bytes(data.try_into().unwrap());
-                    println!("u_16 {:x?}", u_16);
+                    debug!("u_16 {:x?}", u_16);
                     let u_32 = u_16 as u32;
-                    println!("u_32 {:x?}", u_32);
+                    debug!("u_32 {:x?}", u_32);
                     u_32 as Signal
                 }
             }
-            4 => {
+            // word, other sizes are rejected by `evaluate`
+            _ => {
                 if sign_extend {
                     if big_endian {
                         i32::from_be_bytes(data.try_into().unwrap()) as Signal
@@ -111,60 +142,58 @@ impl Memory {
                     u32::from_le_bytes(data.try_into().unwrap()) as Signal
                 }
             }
-            _ => panic!("illegal sized memory operation"),
         }
     }
 
     fn write(&self, addr: usize, size: usize, big_endian: bool, data: Signal) {
         match size {
             1 => {
-                println!("write byte");
-                self.bytes.borrow_mut().insert(addr, data as u8);
+                debug!("write byte");
+                self.set(addr, Some(data as u8));
             }
             2 => {
                 if big_endian {
-                    println!("write half word be");
+                    debug!("write half word be");
                     (data as u16)
                         .to_be_bytes()
                         .iter()
                         .enumerate()
                         .for_each(|(i, bytes)| {
-                            self.bytes.borrow_mut().insert(addr + i, *bytes);
+                            self.set(addr + i, Some(*bytes));
                         })
                 } else {
-                    println!("write half word le");
+                    debug!("write half word le");
                     (data as u16)
                         .to_le_bytes()
                         .iter()
                         .enumerate()
                         .for_each(|(i, bytes)| {
-                            self.bytes.borrow_mut().insert(addr + i, *bytes);
+                            self.set(addr + i, Some(*bytes));
                         })
                 }
             }
-
-            4 => {
+            // word, other sizes are rejected by `evaluate`
+            _ => {
                 if big_endian {
-                    println!("write word be");
-                    data.to_be_bytes()
+                    debug!("write word be");
+                    (data as u32)
+                        .to_be_bytes()
                         .iter()
                         .enumerate()
                         .for_each(|(i, bytes)| {
-                            self.bytes.borrow_mut().insert(addr + i, *bytes);
+                            self.set(addr + i, Some(*bytes));
                         })
                 } else {
-                    println!("write word le");
-                    data.to_le_bytes()
+                    debug!("write word le");
+                    (data as u32)
+                        .to_le_bytes()
                         .iter()
                         .enumerate()
                         .for_each(|(i, bytes)| {
-                            self.bytes.borrow_mut().insert(addr + i, *bytes);
+                            self.set(addr + i, Some(*bytes));
                         })
                 }
             }
-            _ => {
-                panic!("illegal sized memory operation, size = {}", size)
-            }
         };
     }
 }
@@ -177,54 +206,187 @@ pub enum MemCtrl {
     Write,
 }
 
+impl MemCtrl {
+    // control given by the signal, None if not a valid control
+    fn from_signal(ctrl: Signal) -> Option<Self> {
+        u8::try_from(ctrl)
+            .ok()
+            .and_then(|ctrl| MemCtrl::try_from(ctrl).ok())
+    }
+}
+
 #[typetag::serde()]
 impl Component for Mem {
     fn to_(&self) {
-        println!("Mem");
+        debug!("Mem");
     }
 
     fn get_id_ports(&self) -> (String, Ports) {
         (
             self.id.clone(),
             Ports {
-                inputs: vec![self.data.clone(), self.addr.clone(), self.ctrl.clone()],
+                inputs: vec![
+                    self.data.clone(),
+                    self.addr.clone(),
+                    self.ctrl.clone(),
+                    self.sign_extend.clone(),
+                    self.size.clone(),
+                ],
+                input_widths: vec![Signal::BITS; 5],
+                input_names: port_names(&["data", "addr", "ctrl", "sign_extend", "size"]),
+                clocked_inputs: vec![],
                 out_type: OutputType::Combinatorial,
-                // out 0 data
-                // out 1 alignment error
                 outputs: vec![Output::Function; 2],
+                output_widths: vec![32, 1],
+                output_names: port_names(&["data", "err"]),
             },
         )
     }
 
+    fn delay(&self) -> f32 {
+        self.delay.unwrap_or(10.0)
+    }
+
     fn evaluate(&self, simulator: &mut Simulator) {
-        let data = simulator.get_input_val(&self.data);
-        let addr = simulator.get_input_val(&self.addr) as usize;
-        let ctrl = MemCtrl::try_from(simulator.get_input_val(&self.ctrl) as u8).unwrap();
-        let size = simulator.get_input_val(&self.size) as usize;
-        let sign_extend = simulator.get_input_val(&self.sign_extend) != 0;
+        let data = simulator.get_input_value(&self.data);
+        let addr = simulator.get_input_value(&self.addr);
+        let ctrl = simulator.get_input_value(&self.ctrl);
+        let size = simulator.get_input_value(&self.size);
+        let sign_extend = simulator.get_input_value(&self.sign_extend);
+
+        let (addr, ctrl, size) = match (addr, ctrl, size) {
+            (SignalValue::Data(addr), SignalValue::Data(ctrl), SignalValue::Data(size)) => {
+                match (MemCtrl::from_signal(ctrl), size) {
+                    (Some(MemCtrl::None), _) => {
+                        debug!("no read/write");
+                        return;
+                    }
+                    (Some(ctrl), 1 | 2 | 4) => (addr as usize, ctrl, size as usize),
+                    _ => {
+                        // illegal control or size, the data is unknown and the error is set
+                        // (memory is left unchanged)
+                        simulator.set_id_index_value(&self.id, 0, SignalValue::Unknown);
+                        simulator.set_id_index(&self.id, 1, true as Signal);
+                        return;
+                    }
+                }
+            }
+            (_, SignalValue::Data(ctrl), _) if ctrl == MemCtrl::None as Signal => {
+                debug!("no read/write");
+                return;
+            }
+            (addr, SignalValue::Data(ctrl), _) if ctrl == MemCtrl::Write as Signal => {
+                // write of unknown address or size, the memory possibly written reads as unknown
+                match addr {
+                    SignalValue::Data(addr) => self.memory.invalidate(addr as usize, 4),
+                    _ => self.memory.invalidate_all(),
+                }
+                simulator.set_id_index_value(&self.id, 1, SignalValue::Unknown);
+                return;
+            }
+            _ => {
+                // unknown control, or read of unknown address or size, outputs are unknown
+                // (memory is left unchanged)
+                simulator.set_id_index_value(&self.id, 0, SignalValue::Unknown);
+                simulator.set_id_index_value(&self.id, 1, SignalValue::Unknown);
+                return;
+            }
+        };
 
         match ctrl {
             MemCtrl::Read => {
-                println!("read addr {:?} size {:?}", addr, size);
-                let value = self.memory.read(addr, size, sign_extend, self.big_endian);
-                simulator.set_id_index(&self.id, 0, value);
+                debug!("read addr {:?} size {:?}", addr, size);
+                let value = match sign_extend {
+                    SignalValue::Data(sign_extend)
+                        if !simulator.four_state || self.memory.initialized(addr, size) =>
+                    {
+                        SignalValue::Data(self.memory.read(
+                            addr,
+                            size,
+                            sign_extend != 0,
+                            self.big_endian,
+                        ))
+                    }
+                    _ => SignalValue::Unknown,
+                };
+                simulator.set_id_index_value(&self.id, 0, value);
                 let value = self.memory.align(addr, size);
-                println!("align {}", value);
+                debug!("align {}", value);
                 simulator.set_id_index(&self.id, 1, value); // align
             }
             MemCtrl::Write => {
-                println!("write addr {:?} size {:?}", addr, size);
-                self.memory.write(addr, size, self.big_endian, data);
+                debug!("write addr {:?} size {:?}", addr, size);
+                match data {
+                    SignalValue::Data(data) => self.memory.write(addr, size, self.big_endian, data),
+                    _ => self.memory.invalidate(addr, size),
+                }
                 let value = self.memory.align(addr, size);
-                println!("align {}", value);
+                debug!("align {}", value);
                 simulator.set_id_index(&self.id, 1, value); // align
             }
             MemCtrl::None => {
-                println!("no read/write");
+                debug!("no read/write");
             }
         }
 
-        println!("memory {:?}", self.memory);
+        debug!("memory {:?}", self.memory);
+    }
+
+    // reads and writes
+    fn coverage(&self) -> Vec<String> {
+        vec!["read".to_string(), "write".to_string()]
+    }
+
+    fn covered(&self, simulator: &Simulator) -> Vec<String> {
+        match simulator.get_input_value(&self.ctrl) {
+            SignalValue::Data(ctrl) => match MemCtrl::from_signal(ctrl) {
+                Some(MemCtrl::Read) => vec!["read".to_string()],
+                Some(MemCtrl::Write) => vec!["write".to_string()],
+                _ => vec![],
+            },
+            _ => vec![],
+        }
+    }
+
+    fn snapshot(&self) -> Option<serde_json::Value> {
+        Some(serde_json::to_value(&self.memory).unwrap())
+    }
+
+    fn restore(&self, state: &serde_json::Value) -> Result<(), SimulatorError> {
+        let memory: Memory = serde_json::from_value(state.clone())
+            .map_err(|err| SimulatorError::CheckpointMismatch(format!("{}: {}", self.id, err)))?;
+        self.memory.bytes.replace(memory.bytes.into_inner());
+        self.memory.log.borrow_mut().clear();
+        Ok(())
+    }
+
+    // the bytes written by the clock, rather than the whole memory
+    fn undo_log(&self) -> Option<serde_json::Value> {
+        let log = self.memory.log.take();
+        (!log.is_empty()).then(|| serde_json::to_value(log).unwrap())
+    }
+
+    fn undo(&self, log: &serde_json::Value) {
+        let log: Vec<(usize, Option<u8>)> = serde_json::from_value(log.clone()).unwrap();
+        let mut bytes = self.memory.bytes.borrow_mut();
+        for (addr, byte) in log.into_iter().rev() {
+            match byte {
+                Some(byte) => bytes.insert(addr, byte),
+                None => bytes.remove(&addr),
+            };
+        }
+    }
+
+    // reload the memory image the simulator was created with, or clear the memory
+    fn reset(&self, _simulator: &mut Simulator, initial: Option<&serde_json::Value>) {
+        match (self.reset, initial) {
+            // the state captured on creation
+            (ResetMode::Reload, Some(initial)) => self.restore(initial).unwrap(),
+            _ => {
+                self.memory.bytes.borrow_mut().clear();
+                self.memory.log.borrow_mut().clear();
+            }
+        }
     }
 }
 
@@ -261,10 +423,10 @@ mod test {
                     sign_extend: Input::new("sign_extend", 0),
 
                     // memory
-                    memory: Memory {
-                        bytes: RefCell::new(HashMap::new()),
-                    },
+                    memory: Memory::new(),
                     // later history... tbd
+                    reset: ResetMode::Reload,
+                    delay: None,
                 }),
             ],
         };
@@ -429,10 +591,10 @@ mod test {
                     sign_extend: Input::new("sign_extend", 0),
 
                     // memory
-                    memory: Memory {
-                        bytes: RefCell::new(HashMap::new()),
-                    },
+                    memory: Memory::new(),
                     // later history... tbd
+                    reset: ResetMode::Reload,
+                    delay: None,
                 }),
             ],
         };
@@ -527,4 +689,527 @@ mod test {
         assert_eq!(clock, 9);
         assert_eq!(simulator.get_input_val(out), 0x12 as Signal);
     }
+
+    #[test]
+    fn test_mem_four_state() {
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(ProbeOut::new("data")),
+                Rc::new(ProbeOut::new("addr")),
+                Rc::new(ProbeOut::new("ctrl")),
+                Rc::new(ProbeOut::new("size")),
+                Rc::new(ProbeOut::new("sign_extend")),
+                Rc::new(Mem {
+                    id: "mem".to_string(),
+                    pos: (0.0, 0.0),
+                    width: 0.0,
+                    height: 0.0,
+
+                    // configuration
+                    big_endian: true, // i.e., big endian
+
+                    // ports
+                    data: Input::new("data", 0),
+                    addr: Input::new("addr", 0),
+                    ctrl: Input::new("ctrl", 0),
+                    size: Input::new("size", 0),
+                    sign_extend: Input::new("sign_extend", 0),
+
+                    // memory
+                    memory: Memory::new(),
+                    reset: ResetMode::Reload,
+                    delay: None,
+                }),
+            ],
+        };
+
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        simulator.set_four_state(true, &mut clock);
+
+        // outputs
+        let out = &Input::new("mem", 0);
+        let err = &Input::new("mem", 1);
+
+        // unknown control
+        assert_eq!(simulator.get_input_value(out), SignalValue::Unknown);
+        assert_eq!(simulator.get_input_value(err), SignalValue::Unknown);
+
+        // read of memory never written
+        simulator.set_id_index("addr", 0, 4);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Read as Signal);
+        simulator.set_id_index("size", 0, 4);
+        simulator.set_id_index("sign_extend", 0, false as Signal);
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_input_value(out), SignalValue::Unknown);
+        assert_eq!(simulator.get_input_value(err), SignalValue::Data(0));
+
+        // write 42 to addr 4, and read it back
+        simulator.set_id_index("data", 0, 42);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Write as Signal);
+        simulator.clock(&mut clock);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Read as Signal);
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_input_value(out), SignalValue::Data(42));
+
+        // write unknown data, reads back as unknown
+        simulator.set_id_index_value("data", 0, SignalValue::Unknown);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Write as Signal);
+        simulator.clock(&mut clock);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Read as Signal);
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_input_value(out), SignalValue::Unknown);
+
+        // write of unknown size, the bytes up to a word from the address read as unknown
+        for addr in [4, 8] {
+            simulator.set_id_index("data", 0, 42);
+            simulator.set_id_index("addr", 0, addr);
+            simulator.set_id_index("size", 0, 4);
+            simulator.set_id_index("ctrl", 0, MemCtrl::Write as Signal);
+            simulator.clock(&mut clock);
+        }
+        simulator.set_id_index("addr", 0, 6);
+        simulator.set_id_index_value("size", 0, SignalValue::Unknown);
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_input_value(err), SignalValue::Unknown);
+        simulator.set_id_index("size", 0, 1);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Read as Signal);
+        for (addr, value) in [
+            (5, SignalValue::Data(0)),
+            (6, SignalValue::Unknown),
+            (9, SignalValue::Unknown),
+            (10, SignalValue::Data(0)),
+        ] {
+            simulator.set_id_index("addr", 0, addr);
+            simulator.clock(&mut clock);
+            assert_eq!(simulator.get_input_value(out), value);
+        }
+
+        // write of unknown address, all memory reads as unknown
+        simulator.set_id_index_value("addr", 0, SignalValue::Unknown);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Write as Signal);
+        simulator.clock(&mut clock);
+        simulator.set_id_index("addr", 0, 5);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Read as Signal);
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_input_value(out), SignalValue::Unknown);
+
+        // undone by un_clock
+        simulator.un_clock(&mut clock);
+        simulator.un_clock(&mut clock);
+        simulator.set_id_index("addr", 0, 5);
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_input_value(out), SignalValue::Data(0));
+    }
+
+    #[test]
+    fn test_mem_un_clock() {
+        let mem = Rc::new(Mem {
+            id: "mem".to_string(),
+            pos: (0.0, 0.0),
+            width: 0.0,
+            height: 0.0,
+
+            // configuration
+            big_endian: true, // i.e., big endian
+
+            // ports
+            data: Input::new("data", 0),
+            addr: Input::new("addr", 0),
+            ctrl: Input::new("ctrl", 0),
+            size: Input::new("size", 0),
+            sign_extend: Input::new("sign_extend", 0),
+
+            // memory
+            memory: Memory::new(),
+            reset: ResetMode::Reload,
+            delay: None,
+        });
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(ProbeOut::new("data")),
+                Rc::new(ProbeOut::new("addr")),
+                Rc::new(ProbeOut::new("ctrl")),
+                Rc::new(ProbeOut::new("size")),
+                Rc::new(ProbeOut::new("sign_extend")),
+                mem.clone(),
+            ],
+        };
+
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+
+        // write 42 to addr 4
+        simulator.set_id_index("data", 0, 42);
+        simulator.set_id_index("addr", 0, 4);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Write as Signal);
+        simulator.set_id_index("size", 0, 1);
+        simulator.clock(&mut clock);
+        assert_eq!(clock, 2);
+        assert_eq!(mem.memory.bytes.borrow().get(&4), Some(&42));
+
+        // overwrite with 43
+        simulator.set_id_index("data", 0, 43);
+        simulator.clock(&mut clock);
+        assert_eq!(mem.memory.bytes.borrow().get(&4), Some(&43));
+
+        // step back, restores the memory of the previous clock
+        simulator.un_clock(&mut clock);
+        assert_eq!(clock, 2);
+        assert_eq!(mem.memory.bytes.borrow().get(&4), Some(&42));
+
+        simulator.un_clock(&mut clock);
+        assert_eq!(clock, 1);
+        assert_eq!(mem.memory.bytes.borrow().get(&4), None);
+    }
+
+    #[test]
+    fn test_mem_illegal() {
+        let mem = Rc::new(Mem {
+            id: "mem".to_string(),
+            pos: (0.0, 0.0),
+            width: 0.0,
+            height: 0.0,
+
+            // configuration
+            big_endian: true, // i.e., big endian
+
+            // ports
+            data: Input::new("data", 0),
+            addr: Input::new("addr", 0),
+            ctrl: Input::new("ctrl", 0),
+            size: Input::new("size", 0),
+            sign_extend: Input::new("sign_extend", 0),
+
+            // memory
+            memory: Memory::new(),
+            reset: ResetMode::Reload,
+            delay: None,
+        });
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(ProbeOut::new("data")),
+                Rc::new(ProbeOut::new("addr")),
+                Rc::new(ProbeOut::new("ctrl")),
+                Rc::new(ProbeOut::new("size")),
+                Rc::new(ProbeOut::new("sign_extend")),
+                mem.clone(),
+            ],
+        };
+
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+
+        // outputs
+        let out = &Input::new("mem", 0);
+        let err = &Input::new("mem", 1);
+
+        // illegal control (256 is not truncated to none), the data is unknown and err is set
+        for ctrl in [3, 256] {
+            simulator.set_id_index("ctrl", 0, ctrl);
+            simulator.set_id_index("size", 0, 1);
+            simulator.clock(&mut clock);
+            assert_eq!(simulator.get_input_value(out), SignalValue::Unknown);
+            assert_eq!(simulator.get_input_val(err), true as Signal);
+        }
+
+        // illegal size, nothing is written
+        simulator.set_id_index("data", 0, 42);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Write as Signal);
+        simulator.set_id_index("size", 0, 3);
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_input_val(err), true as Signal);
+        assert!(mem.memory.bytes.borrow().is_empty());
+
+        simulator.set_id_index("ctrl", 0, MemCtrl::Read as Signal);
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_input_value(out), SignalValue::Unknown);
+        assert_eq!(simulator.get_input_val(err), true as Signal);
+    }
+
+    #[test]
+    fn test_mem_undo_log() {
+        // memory image of 1 KiB
+        let mem = Rc::new(Mem {
+            id: "mem".to_string(),
+            pos: (0.0, 0.0),
+            width: 0.0,
+            height: 0.0,
+
+            // configuration
+            big_endian: true, // i.e., big endian
+
+            // ports
+            data: Input::new("data", 0),
+            addr: Input::new("addr", 0),
+            ctrl: Input::new("ctrl", 0),
+            size: Input::new("size", 0),
+            sign_extend: Input::new("sign_extend", 0),
+
+            // memory
+            memory: Memory {
+                bytes: RefCell::new((0..1024).map(|addr| (addr, addr as u8)).collect()),
+                ..Memory::default()
+            },
+            reset: ResetMode::Reload,
+            delay: None,
+        });
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(ProbeOut::new("data")),
+                Rc::new(ProbeOut::new("addr")),
+                Rc::new(ProbeOut::new("ctrl")),
+                Rc::new(ProbeOut::new("size")),
+                Rc::new(ProbeOut::new("sign_extend")),
+                mem.clone(),
+            ],
+        };
+
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+
+        // a read leaves no undo log in history
+        simulator.set_id_index("ctrl", 0, MemCtrl::Read as Signal);
+        simulator.set_id_index("size", 0, 1);
+        simulator.clock(&mut clock);
+        assert!(simulator
+            .history
+            .pop(&simulator.sim_state)
+            .unwrap()
+            .components
+            .is_empty());
+
+        // a write keeps the previous bytes written, not the memory image
+        simulator.set_id_index("data", 0, 0x4243);
+        simulator.set_id_index("addr", 0, 4);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Write as Signal);
+        simulator.set_id_index("size", 0, 2);
+        simulator.clock(&mut clock);
+        let entry = simulator.history.pop(&simulator.sim_state).unwrap();
+        assert_eq!(entry.components.len(), 1);
+        assert_eq!(entry.components[0].1, serde_json::json!([[4, 4], [5, 5]]));
+
+        mem.undo(&entry.components[0].1);
+        assert_eq!(mem.memory.bytes.borrow().get(&4), Some(&4));
+        assert_eq!(mem.memory.bytes.borrow().get(&5), Some(&5));
+        assert_eq!(mem.memory.bytes.borrow().len(), 1024);
+    }
+
+    #[test]
+    fn test_mem_reset() {
+        // memory image 42 at addr 0, reloaded or cleared on reset
+        for (reset, image) in [(ResetMode::Reload, Some(&42)), (ResetMode::Clear, None)] {
+            let mem = Rc::new(Mem {
+                id: "mem".to_string(),
+                pos: (0.0, 0.0),
+                width: 0.0,
+                height: 0.0,
+
+                // configuration
+                big_endian: true, // i.e., big endian
+
+                // ports
+                data: Input::new("data", 0),
+                addr: Input::new("addr", 0),
+                ctrl: Input::new("ctrl", 0),
+                size: Input::new("size", 0),
+                sign_extend: Input::new("sign_extend", 0),
+
+                // memory
+                memory: Memory {
+                    bytes: RefCell::new(HashMap::from([(0, 42)])),
+                    ..Memory::default()
+                },
+                reset,
+                delay: None,
+            });
+            let cs = ComponentStore {
+                store: vec![
+                    Rc::new(ProbeOut::new("data")),
+                    Rc::new(ProbeOut::new("addr")),
+                    Rc::new(ProbeOut::new("ctrl")),
+                    Rc::new(ProbeOut::new("size")),
+                    Rc::new(ProbeOut::new("sign_extend")),
+                    mem.clone(),
+                ],
+            };
+
+            let mut clock = 0;
+            let mut simulator = Simulator::new(&cs, &mut clock);
+            // the image is loaded on start (the reset clock on creation included)
+            assert_eq!(mem.memory.bytes.borrow().get(&0), image);
+
+            // overwrite the image with 43, and write 1 to addr 4
+            simulator.set_id_index("data", 0, 43);
+            simulator.set_id_index("ctrl", 0, MemCtrl::Write as Signal);
+            simulator.set_id_index("size", 0, 1);
+            simulator.clock(&mut clock);
+            simulator.set_id_index("data", 0, 1);
+            simulator.set_id_index("addr", 0, 4);
+            simulator.clock(&mut clock);
+            assert_eq!(mem.memory.bytes.borrow().get(&0), Some(&43));
+            assert_eq!(mem.memory.bytes.borrow().get(&4), Some(&1));
+
+            // reset reloads the image, or clears the memory
+            simulator.reset(&mut clock);
+            assert_eq!(clock, 1);
+            assert_eq!(mem.memory.bytes.borrow().get(&0), image);
+            assert_eq!(mem.memory.bytes.borrow().get(&4), None);
+        }
+    }
+
+    #[test]
+    fn test_mem_restore() {
+        let mem = Mem {
+            id: "mem".to_string(),
+            pos: (0.0, 0.0),
+            width: 0.0,
+            height: 0.0,
+            big_endian: true,
+            data: Input::new("data", 0),
+            addr: Input::new("addr", 0),
+            ctrl: Input::new("ctrl", 0),
+            size: Input::new("size", 0),
+            sign_extend: Input::new("sign_extend", 0),
+            memory: Memory::default(),
+            reset: ResetMode::Reload,
+            delay: None,
+        };
+        mem.restore(&serde_json::json!({"bytes": {"4": 1}}))
+            .unwrap();
+        assert_eq!(mem.memory.bytes.borrow().get(&4), Some(&1));
+
+        // state not captured from a memory is not restored
+        assert!(matches!(
+            mem.restore(&serde_json::json!([4, 1])),
+            Err(SimulatorError::CheckpointMismatch(msg)) if msg.starts_with("mem: ")
+        ));
+        assert_eq!(mem.memory.bytes.borrow().get(&4), Some(&1));
+    }
+
+    #[test]
+    fn test_mem_coverage() {
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(ProbeOut::new("data")),
+                Rc::new(ProbeOut::new("addr")),
+                Rc::new(ProbeOut::new("ctrl")),
+                Rc::new(ProbeOut::new("size")),
+                Rc::new(ProbeOut::new("sign_extend")),
+                Rc::new(Mem {
+                    id: "mem".to_string(),
+                    pos: (0.0, 0.0),
+                    width: 0.0,
+                    height: 0.0,
+                    big_endian: true,
+                    data: Input::new("data", 0),
+                    addr: Input::new("addr", 0),
+                    ctrl: Input::new("ctrl", 0),
+                    size: Input::new("size", 0),
+                    sign_extend: Input::new("sign_extend", 0),
+                    memory: Memory::new(),
+                    reset: ResetMode::Reload,
+                    delay: None,
+                }),
+            ],
+        };
+
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        simulator.set_activity(true);
+
+        // two writes and a read, cycles without read or write are not counted
+        simulator.set_id_index("size", 0, 4);
+        simulator.clock(&mut clock);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Write as Signal);
+        simulator.clock(&mut clock);
+        simulator.clock(&mut clock);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Read as Signal);
+        simulator.clock(&mut clock);
+        let report = simulator.activity_report().unwrap();
+        assert_eq!(
+            report.coverage[0].points,
+            vec![("read".to_string(), 1), ("write".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn test_mem_dependencies() {
+        use crate::components::Add;
+
+        // size and sign_extend driven by combinatorial components, declared after the memory
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(Mem {
+                    id: "mem".to_string(),
+                    pos: (0.0, 0.0),
+                    width: 0.0,
+                    height: 0.0,
+                    big_endian: true,
+                    data: Input::new("data", 0),
+                    addr: Input::new("addr", 0),
+                    ctrl: Input::new("ctrl", 0),
+                    size: Input::new("size", 0),
+                    sign_extend: Input::new("sign_extend", 0),
+                    memory: Memory::new(),
+                    reset: ResetMode::Reload,
+                    delay: None,
+                }),
+                Rc::new(Add {
+                    id: "size".to_string(),
+                    pos: (0.0, 0.0),
+                    a_in: Input::new("s", 0),
+                    b_in: Input::new("zero", 0),
+                    bit_width: 32,
+                    delay: None,
+                }),
+                Rc::new(Add {
+                    id: "sign_extend".to_string(),
+                    pos: (0.0, 0.0),
+                    a_in: Input::new("se", 0),
+                    b_in: Input::new("zero", 0),
+                    bit_width: 32,
+                    delay: None,
+                }),
+                Rc::new(ProbeOut::new("data")),
+                Rc::new(ProbeOut::new("addr")),
+                Rc::new(ProbeOut::new("ctrl")),
+                Rc::new(ProbeOut::new("s")),
+                Rc::new(ProbeOut::new("se")),
+                Rc::new(ProbeOut::new("zero")),
+            ],
+        };
+
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        simulator.set_debug(true);
+
+        // the memory depends on size and sign_extend, and is evaluated after them
+        let order = &simulator.component_ids;
+        let position = |id: &str| order.iter().position(|i| i == id).unwrap();
+        assert!(position("size") < position("mem"));
+        assert!(position("sign_extend") < position("mem"));
+
+        // write word to addr 4
+        simulator.set_id_index("data", 0, 0x1234_8765);
+        simulator.set_id_index("addr", 0, 4);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Write as Signal);
+        simulator.set_id_index("s", 0, 4);
+        simulator.clock(&mut clock);
+
+        // read sign extended half-word from addr 6, size and sign_extend change in the same cycle
+        simulator.set_id_index("addr", 0, 6);
+        simulator.set_id_index("ctrl", 0, MemCtrl::Read as Signal);
+        simulator.set_id_index("s", 0, 2);
+        simulator.set_id_index("se", 0, true as Signal);
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_input_val(&Input::new("mem", 0)), 0xffff_8765);
+        assert_eq!(
+            simulator.get_input_val(&Input::new("mem", 1)),
+            false as Signal
+        );
+
+        // all inputs read are declared
+        assert_eq!(simulator.undeclared_inputs(), vec![]);
+    }
 }
@@ -6,6 +6,7 @@ mod probe;
 mod probe_out;
 mod register;
 mod sext;
+mod subcircuit;
 mod wire;
 
 pub use add::*;
@@ -16,4 +17,39 @@ pub use probe::*;
 pub use probe_out::*;
 pub use register::*;
 pub use sext::*;
+pub use subcircuit::*;
 pub use wire::*;
+
+// counter, counting by the constant c (1), all components placed at the origin, for unit tests
+#[cfg(test)]
+pub(crate) fn counter(bit_width: u32) -> crate::common::ComponentStore {
+    use crate::common::Input;
+    use std::rc::Rc;
+
+    crate::common::ComponentStore {
+        store: vec![
+            Rc::new(Constant {
+                id: "c".to_string(),
+                pos: (0.0, 0.0),
+                value: 1,
+                bit_width,
+            }),
+            Rc::new(Register {
+                id: "reg".to_string(),
+                pos: (0.0, 0.0),
+                r_in: Input::new("add", 0),
+                bit_width,
+                reset_value: None,
+                delay: None,
+            }),
+            Rc::new(Add {
+                id: "add".to_string(),
+                pos: (0.0, 0.0),
+                a_in: Input::new("reg", 0),
+                b_in: Input::new("c", 0),
+                bit_width,
+                delay: None,
+            }),
+        ],
+    }
+}
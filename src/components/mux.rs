@@ -1,4 +1,9 @@
-use crate::common::{Component, Input, Output, OutputType, Ports, Simulator};
+use crate::common::{
+    default_bit_width, port_names, Component, IdPorts, Input, Output, OutputType, Ports, Signal,
+    SignalValue, Simulator,
+};
+use crate::debug;
+use crate::validate::Problem;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -7,12 +12,26 @@ pub struct Mux {
     pub pos: (f32, f32),
     pub select: Input,
     pub m_in: Vec<Input>,
+    #[serde(default = "default_bit_width")]
+    pub bit_width: u32,
+    // propagation delay in ns, if not given 0.5 ns (see `Component::delay`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<f32>,
+}
+
+impl Mux {
+    // input selected by the select value, None if the value selects no input
+    fn selected(&self, select: Signal) -> Option<&Input> {
+        usize::try_from(select)
+            .ok()
+            .and_then(|select| self.m_in.get(select))
+    }
 }
 
 #[typetag::serde]
 impl Component for Mux {
     fn to_(&self) {
-        println!("mux");
+        debug!("mux");
     }
 
     fn get_id_ports(&self) -> (String, Ports) {
@@ -20,24 +39,112 @@ impl Component for Mux {
         let mut m = self.m_in.clone();
         inputs.append(&mut m);
 
+        // select may be of any width, inputs should fit the output
+        let mut input_widths = vec![Signal::BITS];
+        input_widths.append(&mut vec![self.bit_width; self.m_in.len()]);
+
+        let mut input_names = vec!["select".to_string()];
+        input_names.extend((0..self.m_in.len()).map(|i| format!("in{}", i)));
+
         (
             self.id.clone(),
             Ports {
                 inputs,
+                input_widths,
+                input_names,
+                clocked_inputs: vec![],
                 out_type: OutputType::Combinatorial,
                 outputs: vec![Output::Function],
+                output_widths: vec![self.bit_width],
+                output_names: port_names(&["out"]),
             },
         )
     }
 
+    fn delay(&self) -> f32 {
+        self.delay.unwrap_or(0.5)
+    }
+
     // propagate selected input value to output
     fn evaluate(&self, simulator: &mut Simulator) {
         // get input value
-        let select = simulator.get_input_val(&self.select) as usize;
-        println!("select {}", select);
-        let value = simulator.get_input_val(&self.m_in[select]);
+        let value = match simulator.get_input_value(&self.select) {
+            SignalValue::Data(select) => {
+                debug!("select {}", select);
+                match self.selected(select) {
+                    Some(input) => match simulator.get_input_value(input) {
+                        // a high-impedance input is read as unknown
+                        SignalValue::HighZ => SignalValue::Unknown,
+                        value => value,
+                    },
+                    // a select of no input, output is unknown (0 in two-state mode)
+                    None if simulator.four_state => SignalValue::Unknown,
+                    None => SignalValue::Data(0),
+                }
+            }
+            // unknown select, output is unknown
+            _ => SignalValue::Unknown,
+        };
 
         // set output
-        simulator.set_id_index(&self.id, 0, value);
+        simulator.set_id_index_value(&self.id, 0, value);
+    }
+
+    // the inputs selected
+    fn coverage(&self) -> Vec<String> {
+        (0..self.m_in.len()).map(|i| format!("in{}", i)).collect()
+    }
+
+    fn covered(&self, simulator: &Simulator) -> Vec<String> {
+        match simulator.get_input_value(&self.select) {
+            SignalValue::Data(select) if self.selected(select).is_some() => {
+                vec![format!("in{}", select)]
+            }
+            _ => vec![],
+        }
+    }
+
+    // the number of inputs should match the values of the select signal,
+    // if the select is of the width needed for the inputs
+    fn validate(&self, id_ports: &IdPorts) -> Vec<Problem> {
+        if self.m_in.is_empty() {
+            return vec![Problem::error(&self.id, "no inputs".to_string())];
+        }
+        let select_width = match id_ports
+            .get(&self.select.id)
+            .and_then(|ports| ports.output_widths.get(self.select.index))
+        {
+            Some(select_width) => *select_width,
+            None => return vec![],
+        };
+        // number of values of the select signal, saturated for wide signals
+        let values = 1usize.checked_shl(select_width).unwrap_or(usize::MAX);
+        // width of the select needed to address the inputs
+        let needed = (usize::BITS - (self.m_in.len() - 1).leading_zeros()).max(1);
+        if values < self.m_in.len() {
+            vec![Problem::warning(
+                &self.id,
+                format!(
+                    "{} inputs, where the select signal of width {} selects at most {}",
+                    self.m_in.len(),
+                    select_width,
+                    values
+                ),
+            )]
+        } else if values > self.m_in.len() && select_width == needed {
+            // a select of the width needed for the inputs, where a value selects no input
+            // (a wider select, e.g., of the default width, is not expected to be exhausted)
+            vec![Problem::warning(
+                &self.id,
+                format!(
+                    "{} inputs, where the select signal of width {} may select up to {}",
+                    self.m_in.len(),
+                    select_width,
+                    values
+                ),
+            )]
+        } else {
+            vec![]
+        }
     }
 }
@@ -1,4 +1,5 @@
-use crate::common::{Component, Input, OutputType, Ports};
+use crate::common::{port_names, Component, Input, OutputType, Ports, Signal};
+use crate::debug;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -11,7 +12,7 @@ pub struct Probe {
 #[typetag::serde]
 impl Component for Probe {
     fn to_(&self) {
-        println!("Probe");
+        debug!("Probe");
     }
 
     fn get_id_ports(&self) -> (String, Ports) {
@@ -20,9 +21,14 @@ impl Component for Probe {
             Ports {
                 // Probes take one input
                 inputs: vec![self.input.clone()],
+                input_widths: vec![Signal::BITS],
+                input_names: port_names(&["input"]),
+                clocked_inputs: vec![],
                 out_type: OutputType::Combinatorial,
                 // No output value
                 outputs: vec![],
+                output_widths: vec![],
+                output_names: vec![],
             },
         )
     }
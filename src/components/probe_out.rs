@@ -1,15 +1,18 @@
-use crate::common::{Component, Output, OutputType, Ports};
+use crate::common::{default_bit_width, port_names, Component, Output, OutputType, Ports};
+use crate::debug;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
 pub struct ProbeOut {
     pub id: String,
+    #[serde(default = "default_bit_width")]
+    pub bit_width: u32,
 }
 
 #[typetag::serde]
 impl Component for ProbeOut {
     fn to_(&self) {
-        println!("ProbeOut");
+        debug!("ProbeOut");
     }
 
     fn get_id_ports(&self) -> (String, Ports) {
@@ -18,9 +21,14 @@ impl Component for ProbeOut {
             Ports {
                 // Constants do not take any inputs
                 inputs: vec![],
+                input_widths: vec![],
+                input_names: vec![],
+                clocked_inputs: vec![],
                 out_type: OutputType::Combinatorial,
                 // Single output value
                 outputs: vec![Output::Function],
+                output_widths: vec![self.bit_width],
+                output_names: port_names(&["out"]),
             },
         )
     }
@@ -28,6 +36,9 @@ impl Component for ProbeOut {
 
 impl ProbeOut {
     pub fn new(id: &str) -> Self {
-        ProbeOut { id: id.to_string() }
+        ProbeOut {
+            id: id.to_string(),
+            bit_width: default_bit_width(),
+        }
     }
 }
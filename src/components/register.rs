@@ -1,4 +1,9 @@
-use crate::common::{Component, Input, Output, OutputType, Ports, Simulator};
+use crate::common::{
+    default_bit_width, fmt_signal, mask, port_names, Component, IdPorts, Input, Output, OutputType,
+    Ports, Signal, SignalValue, Simulator,
+};
+use crate::debug;
+use crate::validate::Problem;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -6,12 +11,20 @@ pub struct Register {
     pub id: String,
     pub pos: (f32, f32),
     pub r_in: Input,
+    #[serde(default = "default_bit_width")]
+    pub bit_width: u32,
+    // output on reset, if not given 0 (unknown in four-state mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reset_value: Option<Signal>,
+    // clock-to-output delay in ns, if not given 0.5 ns (see `Component::delay`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<f32>,
 }
 
 #[typetag::serde]
 impl Component for Register {
     fn to_(&self) {
-        println!("register");
+        debug!("register");
     }
 
     fn get_id_ports(&self) -> (String, Ports) {
@@ -20,18 +33,51 @@ impl Component for Register {
             Ports {
                 // Vector of inputs
                 inputs: vec![self.r_in.clone()],
+                input_widths: vec![self.bit_width],
+                input_names: port_names(&["r_in"]),
+                clocked_inputs: vec![],
                 out_type: OutputType::Sequential,
                 outputs: vec![Output::Function],
+                output_widths: vec![self.bit_width],
+                output_names: port_names(&["out"]),
             },
         )
     }
 
+    fn delay(&self) -> f32 {
+        self.delay.unwrap_or(0.5)
+    }
+
     // propagate input value to output
     fn evaluate(&self, simulator: &mut Simulator) {
-        // get input value
-        let value = simulator.get_input_val(&self.r_in);
+        // get input value, unknown (X) and high-impedance (Z) values are stored as is
+        let value = simulator.get_input_value(&self.r_in);
         // set output
-        simulator.set_id_index(&self.id, 0, value);
-        println!("eval: register id {} in {}", self.id, value);
+        simulator.set_id_index_value(&self.id, 0, value);
+        debug!("eval: register id {} in {}", self.id, value);
+    }
+
+    // the output is set to the reset value (if any), masked to the bit width
+    fn reset(&self, simulator: &mut Simulator, _initial: Option<&serde_json::Value>) {
+        if let Some(value) = self.reset_value {
+            let value = value & mask(self.bit_width);
+            simulator.set_id_index_value(&self.id, 0, SignalValue::Data(value));
+        }
+    }
+
+    // the reset value should fit the bit width
+    fn validate(&self, _id_ports: &IdPorts) -> Vec<Problem> {
+        match self.reset_value {
+            Some(value) if value & !mask(self.bit_width) != 0 => vec![Problem::warning(
+                &self.id,
+                format!(
+                    "reset_value {:#x} does not fit in {} bits, it is masked to {}",
+                    value,
+                    self.bit_width,
+                    fmt_signal(value & mask(self.bit_width), self.bit_width)
+                ),
+            )],
+            _ => vec![],
+        }
     }
 }
@@ -1,5 +1,10 @@
 // use std::fmt::Alignment;
-use crate::common::{Component, Input, Output, OutputType, Ports, Signal, Simulator};
+use crate::common::{
+    mask, port_names, Component, IdPorts, Input, Output, OutputType, Ports, Signal, SignalValue,
+    Simulator,
+};
+use crate::debug;
+use crate::validate::Problem;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -9,12 +14,15 @@ pub struct Sext {
     pub sext_in: Input,
     pub in_size: u8,
     pub out_size: u8,
+    // propagation delay in ns, if not given 0.2 ns (see `Component::delay`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<f32>,
 }
 
 #[typetag::serde]
 impl Component for Sext {
     fn to_(&self) {
-        println!("Sign Extension");
+        debug!("Sign Extension");
     }
 
     fn get_id_ports(&self) -> (String, Ports) {
@@ -22,37 +30,63 @@ impl Component for Sext {
             self.id.clone(),
             Ports {
                 inputs: vec![self.sext_in.clone()],
+                // the input may be of any width, only the low in_size bits are extended
+                input_widths: vec![Signal::BITS],
+                input_names: port_names(&["sext_in"]),
+                clocked_inputs: vec![],
                 out_type: OutputType::Combinatorial,
                 outputs: vec![Output::Function],
+                output_widths: vec![self.out_size as u32],
+                output_names: port_names(&["out"]),
             },
         )
     }
 
+    fn delay(&self) -> f32 {
+        self.delay.unwrap_or(0.2)
+    }
+
     // propagate sign extension to output
     // TODO: always extend to Signal size? (it should not matter and should be slightly cheaper)
     fn evaluate(&self, simulator: &mut Simulator) {
         // get input values
-        let mut value = simulator.get_input_val(&self.sext_in);
-        let max_size: Signal = 1 << self.in_size as Signal;
-        assert!(
-            value < max_size,
-            "SXT input ({}) greater than allowed input size ({})",
-            value,
-            max_size
-        );
-
-        if (value & 1 << (self.in_size - 1)) != 0 {
-            value |= (1 << self.out_size as Signal) - (1 << self.in_size as Signal)
+        let mut value = match simulator.get_input_value(&self.sext_in) {
+            SignalValue::Data(value) => value,
+            _ => {
+                // unknown input, output is unknown
+                simulator.set_id_index_value(&self.id, 0, SignalValue::Unknown);
+                return;
+            }
+        };
+        // masks built by shifting right, in_size and out_size may be up to Signal::BITS,
+        // bits of the input above in_size are ignored
+        let in_mask = mask(self.in_size as u32);
+        value &= in_mask;
+
+        // the sign bit is the top bit of the input mask
+        if value & !(in_mask >> 1) != 0 {
+            // set bits in_size..out_size
+            value |= mask(self.out_size as u32) & !in_mask
         }
 
-        println!(
-            "{}, {}, {}",
-            value,
-            1 << (self.out_size as Signal),
-            1 << (self.in_size as Signal)
-        );
+        debug!("{}, {}, {}", value, self.out_size, self.in_size);
 
         // set output
         simulator.set_id_index(&self.id, 0, value);
     }
+
+    // the output should be at least as wide as the input
+    fn validate(&self, _id_ports: &IdPorts) -> Vec<Problem> {
+        if self.in_size > self.out_size {
+            vec![Problem::error(
+                &self.id,
+                format!(
+                    "in_size {} is larger than out_size {}",
+                    self.in_size, self.out_size
+                ),
+            )]
+        } else {
+            vec![]
+        }
+    }
 }
@@ -0,0 +1,286 @@
+use crate::common::{
+    default_bit_width, port_names, Component, ComponentStore, Components, Input, Output,
+    OutputType, Ports, Simulator, SimulatorError,
+};
+use crate::component_store;
+use crate::debug;
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::{Cell, RefCell},
+    path::PathBuf,
+    rc::Rc,
+};
+
+/// A subcircuit, instantiating another model as a component.
+///
+/// The model is either referenced by `path` (relative to the model file the subcircuit
+/// is loaded from) or embedded as `model`. Its inputs are
+/// components of the model without inputs (e.g., `ProbeOut`), driven from the signals
+/// given by `inputs`, as (inner id, outer signal). The outputs of the subcircuit are
+/// the inner signals given by `outputs`, as `id.port` (the output of the inner component
+/// by name or index, or `id` for its first output), the outputs are named the same.
+///
+/// For simulation the subcircuit is flattened, the inner components are given
+/// the ids `<id>/<inner id>`, and the inputs are replaced by `SubcircuitInput` components.
+#[derive(Serialize, Deserialize)]
+pub struct Subcircuit {
+    pub id: String,
+    pub pos: (f32, f32),
+    pub width: f32,
+    pub height: f32,
+    // model file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    // embedded model, used if no path is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<ComponentStore>,
+    // (inner input component, outer signal)
+    pub inputs: Vec<(String, Input)>,
+    // inner signals exposed as outputs
+    pub outputs: Vec<String>,
+    // (inner signal, bit width) of each output, resolved from the model when first needed
+    #[serde(skip)]
+    exposed: RefCell<Option<Result<Exposed, SimulatorError>>>,
+    // directory of the model file the subcircuit was loaded from, `path` is relative to it
+    #[serde(skip, default = "component_store::model_dir")]
+    dir: Option<PathBuf>,
+}
+
+// (inner signal, bit width) of each output
+type Exposed = Vec<(Input, u32)>;
+
+thread_local! {
+    // nesting depth of the subcircuits being resolved, bounded as a model may refer to itself
+    static RESOLVING: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Input of an expanded subcircuit, passing an outer signal to the inner components.
+#[derive(Serialize, Deserialize)]
+pub struct SubcircuitInput {
+    pub id: String,
+    pub input: Input,
+    #[serde(default = "default_bit_width")]
+    pub bit_width: u32,
+}
+
+impl Subcircuit {
+    pub fn new(
+        id: &str,
+        pos: (f32, f32),
+        model: ComponentStore,
+        inputs: Vec<(&str, Input)>,
+        outputs: Vec<&str>,
+    ) -> Self {
+        Subcircuit {
+            id: id.to_string(),
+            pos,
+            width: 80.0,
+            height: 80.0,
+            path: None,
+            model: Some(model),
+            inputs: inputs
+                .into_iter()
+                .map(|(id, input)| (id.to_string(), input))
+                .collect(),
+            outputs: outputs.iter().map(|o| o.to_string()).collect(),
+            exposed: RefCell::new(None),
+            dir: None,
+        }
+    }
+
+    // inner signal, as seen from the scope of the subcircuit
+    fn inner(&self, input: &Input) -> Input {
+        Input::new(&format!("{}/{}", self.id, input.id), input.index)
+    }
+
+    fn error(&self, msg: String) -> SimulatorError {
+        SimulatorError::Subcircuit {
+            component: self.id.clone(),
+            msg,
+        }
+    }
+
+    /// the model file, relative to the directory of the model the subcircuit was loaded from,
+    /// None for embedded models
+    pub fn model_path(&self) -> Option<PathBuf> {
+        let path = PathBuf::from(self.path.as_ref()?);
+        match &self.dir {
+            Some(dir) if path.is_relative() => Some(dir.join(path)),
+            _ => Some(path),
+        }
+    }
+
+    /// (inner signal, bit width) of each output, the model is loaded when first needed
+    pub fn exposed(&self) -> Result<Exposed, SimulatorError> {
+        if self.exposed.borrow().is_none() {
+            let depth = RESOLVING.with(|depth| depth.replace(depth.get() + 1));
+            let exposed = if depth == component_store::MAX_DEPTH {
+                Err(self.error(format!(
+                    "nested more than {} levels deep",
+                    component_store::MAX_DEPTH
+                )))
+            } else {
+                self.load().and_then(|cs| self.resolve(&cs.store))
+            };
+            RESOLVING.with(|resolving| resolving.set(depth));
+            *self.exposed.borrow_mut() = Some(exposed);
+        }
+        self.exposed.borrow().clone().unwrap()
+    }
+
+    // the exposed signals of the model, outputs given by name or index
+    fn resolve(&self, store: &Components) -> Result<Exposed, SimulatorError> {
+        self.outputs
+            .iter()
+            .map(|o| {
+                let (id, port) = o.rsplit_once('.').unwrap_or((o, "0"));
+                store
+                    .iter()
+                    .map(|c| c.get_id_ports())
+                    .find(|(i, _)| i == id)
+                    .and_then(|(_, ports)| {
+                        let index = match port.parse::<usize>() {
+                            Ok(index) => index,
+                            Err(_) => ports.output_names.iter().position(|name| name == port)?,
+                        };
+                        Some((Input::new(id, index), *ports.output_widths.get(index)?))
+                    })
+                    .ok_or_else(|| self.error(format!("output {} not found", o)))
+            })
+            .collect()
+    }
+
+    /// the inner model, a fresh instance such that subcircuits do not share state
+    pub fn load(&self) -> Result<ComponentStore, SimulatorError> {
+        match (self.model_path(), &self.model) {
+            (Some(path), _) => ComponentStore::try_load_file(&path),
+            // round trip, at the current format version (as embedded models are migrated
+            // along with the model they are embedded in), paths in the embedded model
+            // are relative to the model file it is embedded in
+            (None, Some(model)) => component_store::with_model_dir(self.dir.clone(), || {
+                ComponentStore::try_load(&model.to_json())
+            }),
+            (None, None) => Err(self.error("no model given".to_string())),
+        }
+        .map_err(|err| match err {
+            SimulatorError::Subcircuit { .. } => err,
+            err => self.error(err.to_string()),
+        })
+    }
+}
+
+#[typetag::serde]
+impl Component for Subcircuit {
+    fn to_(&self) {
+        debug!("Subcircuit");
+    }
+
+    fn get_id_ports(&self) -> (String, Ports) {
+        let Ok(exposed) = self.exposed() else {
+            // the model could not be loaded, or the outputs not found (reported by `expand`)
+            return (
+                self.id.clone(),
+                Ports {
+                    inputs: vec![],
+                    input_widths: vec![],
+                    input_names: vec![],
+                    clocked_inputs: vec![],
+                    out_type: OutputType::Combinatorial,
+                    outputs: vec![Output::Function; self.outputs.len()],
+                    output_widths: vec![default_bit_width(); self.outputs.len()],
+                    output_names: self.outputs.clone(),
+                },
+            );
+        };
+        let (inputs, widths): (Vec<_>, Vec<_>) = exposed
+            .into_iter()
+            .map(|(o, bit_width)| (self.inner(&o), bit_width))
+            .unzip();
+        (
+            self.id.clone(),
+            Ports {
+                inputs,
+                input_widths: widths.clone(),
+                input_names: self.outputs.clone(),
+                clocked_inputs: vec![],
+                out_type: OutputType::Combinatorial,
+                outputs: vec![Output::Function; self.outputs.len()],
+                output_widths: widths,
+                output_names: self.outputs.clone(),
+            },
+        )
+    }
+
+    // pass the exposed inner signals to the outputs
+    fn evaluate(&self, simulator: &mut Simulator) {
+        let exposed = self.exposed().unwrap_or_default();
+        for (index, (o, _)) in exposed.iter().enumerate() {
+            let value = simulator.get_input_value(&self.inner(o));
+            simulator.set_id_index_value(&self.id, index, value);
+        }
+    }
+
+    fn expand(&self) -> Result<Vec<(String, Components)>, SimulatorError> {
+        let mut store = self.load()?.store;
+
+        // replace inner input components by inputs driven from the outer signals
+        let mut inputs: Components = vec![];
+        for (name, input) in &self.inputs {
+            let position = store
+                .iter()
+                .position(|c| c.get_id_ports().0 == *name)
+                .ok_or_else(|| self.error(format!("input {} not found", name)))?;
+            let ports = store.remove(position).get_id_ports().1;
+            inputs.push(Rc::new(SubcircuitInput {
+                id: format!("{}/{}", self.id, name),
+                input: input.clone(),
+                bit_width: ports
+                    .output_widths
+                    .first()
+                    .copied()
+                    .unwrap_or_else(default_bit_width),
+            }));
+        }
+
+        // the exposed signals are resolved from the model (see `exposed`)
+        self.exposed()?;
+
+        Ok(vec![
+            (String::new(), inputs),
+            (format!("{}/", self.id), store),
+        ])
+    }
+
+    fn model(&self) -> Option<Result<ComponentStore, SimulatorError>> {
+        Some(self.load())
+    }
+}
+
+#[typetag::serde]
+impl Component for SubcircuitInput {
+    fn to_(&self) {
+        debug!("SubcircuitInput");
+    }
+
+    fn get_id_ports(&self) -> (String, Ports) {
+        (
+            self.id.clone(),
+            Ports {
+                inputs: vec![self.input.clone()],
+                input_widths: vec![self.bit_width],
+                input_names: port_names(&["in"]),
+                clocked_inputs: vec![],
+                out_type: OutputType::Combinatorial,
+                outputs: vec![Output::Function],
+                output_widths: vec![self.bit_width],
+                output_names: port_names(&["out"]),
+            },
+        )
+    }
+
+    // pass the outer signal to the inner components
+    fn evaluate(&self, simulator: &mut Simulator) {
+        let value = simulator.get_input_value(&self.input);
+        simulator.set_id_index_value(&self.id, 0, value);
+    }
+}
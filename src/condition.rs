@@ -0,0 +1,465 @@
+use crate::common::{resolve_signal, Signal, Simulator, SimulatorError};
+
+/// Maximum number of cycles to run until a breakpoint is hit (used by the GUIs)
+pub const RUN_UNTIL_MAX_CYCLES: usize = 100_000;
+
+/// Condition over signals, e.g., `reg.0 == 0x40 && mem.err != 0`.
+///
+/// Signals are referred to as `id.name` or `id.index` (or just `id` for index 0), where the id
+/// of a component inside a subcircuit is given by its path (e.g., `alu/add.sum`), constants are
+/// given in decimal, hexadecimal (`0x`) or binary (`0b`). Supported operators are
+/// `==`, `!=`, `<`, `<=`, `>`, `>=`, `&&`, `||` and `!`, with parentheses for grouping.
+///
+/// In four-state mode, comparisons involving unknown (X) or high-impedance (Z) signals
+/// are unknown, and an unknown condition does not hold.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Condition {
+    expr: Expr,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Expr {
+    Const(Signal),
+    // signal as named (see `resolve_signal`), and its column in the source
+    Signal(String, usize),
+    Not(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Number(Signal),
+    Ident(String),
+    Dot,
+    Not,
+    Op(Op),
+    LParen,
+    RParen,
+}
+
+impl Condition {
+    /// parse condition, returns an error if the condition is malformed
+    pub fn parse(source: &str) -> Result<Self, SimulatorError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+            end: source.chars().count() + 1,
+        };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(Condition { expr }),
+            Some((_, col)) => Err(error(*col, "unexpected token")),
+        }
+    }
+
+    /// condition that holds if any of the conditions hold
+    pub fn any(conditions: Vec<Condition>) -> Self {
+        let expr = conditions
+            .into_iter()
+            .map(|c| c.expr)
+            .reduce(|a, b| Expr::Binary(Op::Or, Box::new(a), Box::new(b)))
+            .unwrap_or(Expr::Const(0));
+        Condition { expr }
+    }
+
+    /// check that all signals referred to exist in the simulated model
+    pub fn check(&self, simulator: &Simulator) -> Result<(), SimulatorError> {
+        self.expr.check(simulator)
+    }
+
+    /// evaluate condition on the current state
+    pub fn eval(&self, simulator: &Simulator) -> bool {
+        matches!(self.expr.eval(simulator), Some(value) if value != 0)
+    }
+}
+
+impl Expr {
+    fn check(&self, simulator: &Simulator) -> Result<(), SimulatorError> {
+        match self {
+            Expr::Const(_) => Ok(()),
+            Expr::Signal(signal, col) => match resolve_signal(&simulator.id_ports, signal) {
+                Ok(_) => Ok(()),
+                Err(err) => Err(error(*col, &err.to_string())),
+            },
+            Expr::Not(expr) => expr.check(simulator),
+            Expr::Binary(_, lhs, rhs) => {
+                lhs.check(simulator)?;
+                rhs.check(simulator)
+            }
+        }
+    }
+
+    // value of expression, None if unknown
+    fn eval(&self, simulator: &Simulator) -> Option<Signal> {
+        match self {
+            Expr::Const(value) => Some(*value),
+            Expr::Signal(signal, _) => resolve_signal(&simulator.id_ports, signal)
+                .ok()
+                .and_then(|input| simulator.get_input_value(&input).data()),
+            Expr::Not(expr) => expr.eval(simulator).map(|value| (value == 0) as Signal),
+            Expr::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval(simulator), rhs.eval(simulator));
+                match op {
+                    // false if either side is false (even if the other is unknown)
+                    Op::And => match (lhs, rhs) {
+                        (Some(0), _) | (_, Some(0)) => Some(0),
+                        (Some(_), Some(_)) => Some(1),
+                        _ => None,
+                    },
+                    // true if either side is true (even if the other is unknown)
+                    Op::Or => match (lhs, rhs) {
+                        (Some(lhs), _) if lhs != 0 => Some(1),
+                        (_, Some(rhs)) if rhs != 0 => Some(1),
+                        (Some(_), Some(_)) => Some(0),
+                        _ => None,
+                    },
+                    _ => {
+                        let (lhs, rhs) = (lhs?, rhs?);
+                        let value = match op {
+                            Op::Eq => lhs == rhs,
+                            Op::Ne => lhs != rhs,
+                            Op::Lt => lhs < rhs,
+                            Op::Le => lhs <= rhs,
+                            Op::Gt => lhs > rhs,
+                            _ => lhs >= rhs,
+                        };
+                        Some(value as Signal)
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn error(col: usize, msg: &str) -> SimulatorError {
+    SimulatorError::Condition {
+        col,
+        msg: msg.to_string(),
+    }
+}
+
+// tokens, with their (1 based) column in the source
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, SimulatorError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let col = i + 1;
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let (token, len) = match (c, next) {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('=', Some('=')) => (Token::Op(Op::Eq), 2),
+            ('!', Some('=')) => (Token::Op(Op::Ne), 2),
+            ('<', Some('=')) => (Token::Op(Op::Le), 2),
+            ('>', Some('=')) => (Token::Op(Op::Ge), 2),
+            ('&', Some('&')) => (Token::Op(Op::And), 2),
+            ('|', Some('|')) => (Token::Op(Op::Or), 2),
+            ('<', _) => (Token::Op(Op::Lt), 1),
+            ('>', _) => (Token::Op(Op::Gt), 1),
+            ('!', _) => (Token::Not, 1),
+            ('.', _) => (Token::Dot, 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            _ if c.is_ascii_alphanumeric() || c == '_' => {
+                // identifiers may hold a subcircuit path (`/`)
+                let len = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || **c == '_' || **c == '/')
+                    .count();
+                let word: String = chars[i..i + len].iter().collect();
+                if c.is_ascii_digit() {
+                    (Token::Number(number(&word, col)?), len)
+                } else {
+                    (Token::Ident(word), len)
+                }
+            }
+            _ => return Err(error(col, &format!("unexpected character '{}'", c))),
+        };
+        tokens.push((token, col));
+        i += len;
+    }
+    Ok(tokens)
+}
+
+fn number(word: &str, col: usize) -> Result<Signal, SimulatorError> {
+    let word = word.replace('_', "");
+    let value = if let Some(hex) = word.strip_prefix("0x") {
+        Signal::from_str_radix(hex, 16)
+    } else if let Some(bin) = word.strip_prefix("0b") {
+        Signal::from_str_radix(bin, 2)
+    } else {
+        word.parse()
+    };
+    value.map_err(|_| error(col, &format!("malformed number {}", word)))
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    // column past the end of the source
+    end: usize,
+}
+
+// recursive descent, with operator precedence (lowest first) `||`, `&&`, comparisons, `!`
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn col(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(_, col)| *col)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr, SimulatorError> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Op(Op::Or)) {
+            self.next();
+            expr = Expr::Binary(Op::Or, Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, SimulatorError> {
+        let mut expr = self.compare()?;
+        while self.peek() == Some(&Token::Op(Op::And)) {
+            self.next();
+            expr = Expr::Binary(Op::And, Box::new(expr), Box::new(self.compare()?));
+        }
+        Ok(expr)
+    }
+
+    fn compare(&mut self) -> Result<Expr, SimulatorError> {
+        let expr = self.unary()?;
+        match self.peek() {
+            Some(Token::Op(op)) if *op != Op::And && *op != Op::Or => {
+                let op = *op;
+                self.next();
+                Ok(Expr::Binary(op, Box::new(expr), Box::new(self.unary()?)))
+            }
+            _ => Ok(expr),
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, SimulatorError> {
+        let col = self.col();
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Number(value)) => Ok(Expr::Const(value)),
+            Some(Token::Ident(id)) => {
+                if self.peek() != Some(&Token::Dot) {
+                    return Ok(Expr::Signal(id, col));
+                }
+                self.next();
+                let port_col = self.col();
+                match self.next() {
+                    Some(Token::Number(index)) => {
+                        Ok(Expr::Signal(format!("{}.{}", id, index), col))
+                    }
+                    Some(Token::Ident(name)) => Ok(Expr::Signal(format!("{}.{}", id, name), col)),
+                    _ => Err(error(port_col, "expected output name or index")),
+                }
+            }
+            Some(Token::LParen) => {
+                let expr = self.or()?;
+                let col = self.col();
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err(error(col, "expected ')'")),
+                }
+            }
+            Some(_) => Err(error(col, "expected signal, number or '('")),
+            None => Err(error(col, "unexpected end of condition")),
+        }
+    }
+}
+
+impl Simulator {
+    /// clock until the condition holds, at most `max_cycles` times,
+    /// returns true if the condition holds
+    pub fn run_until(
+        &mut self,
+        condition: &Condition,
+        max_cycles: usize,
+        clock: &mut usize,
+    ) -> bool {
+        for _ in 0..max_cycles {
+            self.clock(clock);
+            if condition.eval(self) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// check the breakpoints (conditions) against the simulated model, e.g., after opening
+    /// another model, breakpoints that do not parse or refer to signals not found in the model
+    /// are removed, and reported in the error
+    pub fn check_breakpoints(&self, breakpoints: &mut Vec<String>) -> Result<(), SimulatorError> {
+        let mut invalid = vec![];
+        breakpoints.retain(|breakpoint| {
+            match Condition::parse(breakpoint).and_then(|c| c.check(self)) {
+                Ok(()) => true,
+                Err(err) => {
+                    invalid.push((breakpoint.clone(), err.to_string()));
+                    false
+                }
+            }
+        });
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(SimulatorError::InvalidBreakpoints(invalid))
+        }
+    }
+
+    /// step back until the condition holds, or the history is exhausted,
+    /// returns true if the condition holds
+    pub fn rewind_until(&mut self, condition: &Condition, clock: &mut usize) -> bool {
+        loop {
+            let cycle = self.cycle;
+            self.un_clock(clock);
+            if self.cycle == cycle {
+                return false;
+            }
+            if condition.eval(self) {
+                return true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::Input;
+    use crate::components::counter;
+
+    fn col(source: &str) -> usize {
+        match Condition::parse(source) {
+            Err(SimulatorError::Condition { col, .. }) => col,
+            other => panic!("expected condition error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let c = Condition::parse("reg.0 == 0x40 && mem.err != 0").unwrap();
+        assert_eq!(
+            c.expr,
+            Expr::Binary(
+                Op::And,
+                Box::new(Expr::Binary(
+                    Op::Eq,
+                    Box::new(Expr::Signal("reg.0".to_string(), 1)),
+                    Box::new(Expr::Const(0x40))
+                )),
+                Box::new(Expr::Binary(
+                    Op::Ne,
+                    Box::new(Expr::Signal("mem.err".to_string(), 18)),
+                    Box::new(Expr::Const(0))
+                ))
+            )
+        );
+        assert_eq!(
+            Condition::parse("!(pc >= 0b1_0000)").unwrap(),
+            Condition {
+                expr: Expr::Not(Box::new(Expr::Binary(
+                    Op::Ge,
+                    Box::new(Expr::Signal("pc".to_string(), 3)),
+                    Box::new(Expr::Const(16))
+                )))
+            }
+        );
+        assert_eq!(
+            Condition::parse("alu/add.0").unwrap().expr,
+            Expr::Signal("alu/add.0".to_string(), 1)
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(col("reg.0 == "), 10);
+        assert_eq!(col("reg. == 1"), 6);
+        assert_eq!(col("reg.0 = 1"), 7);
+        assert_eq!(col("(reg.0 == 1"), 12);
+        assert_eq!(col("reg.0 == 0xfg"), 10);
+        assert_eq!(col("reg.0 1"), 7);
+        assert_eq!(col("1/2"), 1);
+    }
+
+    #[test]
+    fn test_run_until() {
+        let cs = counter(32);
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+
+        // counter
+        let reg = &Input::new("reg", 0);
+
+        let condition = Condition::parse("reg.0 == 0x40 && add.sum != 0").unwrap();
+        condition.check(&simulator).unwrap();
+        assert!(simulator.run_until(&condition, 1000, &mut clock));
+        assert_eq!(simulator.get_input_val(reg), 0x40);
+        assert_eq!(clock, 0x41);
+
+        // not reached within the given number of cycles
+        let condition = Condition::parse("reg >= 100").unwrap();
+        assert!(!simulator.run_until(&condition, 10, &mut clock));
+        assert_eq!(simulator.get_input_val(reg), 0x4a);
+
+        let condition = Condition::parse("reg == 0x20 || reg == 0x30").unwrap();
+        assert!(simulator.rewind_until(&condition, &mut clock));
+        assert_eq!(simulator.get_input_val(reg), 0x30);
+        assert!(simulator.rewind_until(&condition, &mut clock));
+        assert_eq!(simulator.get_input_val(reg), 0x20);
+
+        // history exhausted
+        assert!(!simulator.rewind_until(&condition, &mut clock));
+        assert_eq!(clock, 1);
+
+        // unknown signals
+        let condition = Condition::parse("reg.1 == 0 || regs == 0").unwrap();
+        assert!(matches!(
+            condition.check(&simulator),
+            Err(SimulatorError::Condition { col: 1, .. })
+        ));
+
+        // breakpoints not valid in the model are removed, and reported
+        let mut breakpoints = vec![
+            "reg == 3".to_string(),
+            "regs == 0".to_string(),
+            "reg ==".to_string(),
+        ];
+        let err = simulator.check_breakpoints(&mut breakpoints).unwrap_err();
+        assert_eq!(breakpoints, vec!["reg == 3".to_string()]);
+        assert_eq!(
+            err.to_string(),
+            "Breakpoints removed, as not valid in the model\n  \
+             regs == 0: Condition error at column 1: Signal regs not found in the model\n  \
+             reg ==: Condition error at column 7: unexpected end of condition"
+        );
+        assert_eq!(simulator.check_breakpoints(&mut breakpoints), Ok(()));
+    }
+}
@@ -0,0 +1,170 @@
+use crate::common::{fmt_value, Input, Signal, SignalValue, Simulator};
+use std::ops::Range;
+
+/// Forcing signals, for debugging.
+///
+/// A forced signal holds its forced value until released, overriding the output set by
+/// its component on each evaluation (and by stimulus). Forced signals are kept over
+/// reset and un_clock, and saved in checkpoints.
+impl Simulator {
+    // index of output `index` of component `id`
+    fn forced_index(&self, id: &str, index: usize) -> usize {
+        let nr_out = *self.id_nr_outputs.get(id).unwrap();
+        if index < nr_out {
+            self.id_start_index[id] + index
+        } else {
+            panic!(
+                "Attempt to force {} at index {}, where {} has only {} outputs.",
+                id, index, id, nr_out
+            )
+        }
+    }
+
+    /// force output by id and offset (index) to value, until released
+    pub fn force(&mut self, id: &str, index: usize, value: Signal) {
+        self.force_value(id, index, SignalValue::Data(value));
+    }
+
+    /// force output by id and offset (index) to four-state value, until released,
+    /// data is masked to the bit width of the signal
+    pub fn force_value(&mut self, id: &str, index: usize, value: SignalValue) {
+        let index = self.forced_index(id, index);
+        self.set_value(index, value);
+        self.forced.insert(index, self.sim_state[index]);
+    }
+
+    /// release forced output by id and offset (index), the output keeps the forced value
+    /// until its component is evaluated on the next clock
+    pub fn release(&mut self, id: &str, index: usize) {
+        let index = self.forced_index(id, index);
+        self.forced.remove(&index);
+        // the component may not depend on changed outputs in event-driven mode
+        self.settled = None;
+    }
+
+    /// release all forced outputs
+    pub fn release_all(&mut self) {
+        self.forced.clear();
+        self.settled = None;
+    }
+
+    /// signal by index is forced
+    pub fn is_forced(&self, index: usize) -> bool {
+        self.forced.contains_key(&index)
+    }
+
+    /// input is forced
+    pub fn is_input_forced(&self, input: &Input) -> bool {
+        self.is_forced(self.get_id_start_index(&input.id) + input.index)
+    }
+
+    /// value by index formatted for display (see `fmt_value`), marked if forced
+    pub fn display_value(&self, index: usize) -> String {
+        let value = fmt_value(self.get_value(index), self.get_width(index));
+        if self.is_forced(index) {
+            format!("{} (forced)", value)
+        } else {
+            value
+        }
+    }
+
+    /// input value formatted for display, marked if forced
+    pub fn display_input_value(&self, input: &Input) -> String {
+        self.display_value(self.get_id_start_index(&input.id) + input.index)
+    }
+
+    // set the forced values in range of the state, after evaluation
+    pub(crate) fn apply_forced(&mut self, range: Range<usize>) {
+        for (index, value) in self.forced.range(range) {
+            self.sim_state[*index] = *value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::{ComponentStore, Input, SignalValue, Simulator};
+    use crate::components::{counter, Add, ProbeOut, Register};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_force() {
+        // an 8 bit adder of po1 and po2, into a register
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(ProbeOut {
+                    id: "po1".to_string(),
+                    bit_width: 8,
+                }),
+                Rc::new(ProbeOut {
+                    id: "po2".to_string(),
+                    bit_width: 8,
+                }),
+                Rc::new(Add {
+                    id: "add".to_string(),
+                    pos: (0.0, 0.0),
+                    a_in: Input::new("po1", 0),
+                    b_in: Input::new("po2", 0),
+                    bit_width: 8,
+                    delay: None,
+                }),
+                Rc::new(Register {
+                    id: "reg".to_string(),
+                    pos: (0.0, 0.0),
+                    r_in: Input::new("add", 0),
+                    bit_width: 8,
+                    reset_value: None,
+                    delay: None,
+                }),
+            ],
+        };
+        for event_driven in [false, true] {
+            let mut clock = 0;
+            let mut simulator = Simulator::new(&cs, &mut clock);
+            simulator.set_event_driven(event_driven);
+            let sum = &Input::new("add", 0);
+            let reg = &Input::new("reg", 0);
+
+            // forced outputs override the evaluated sum from when forced, masked to 8 bits
+            simulator.set_id_index("po1", 0, 1);
+            simulator.set_id_index("po2", 0, 2);
+            simulator.force("add", 0, 0x1ff);
+            assert!(simulator.is_input_forced(sum));
+            assert!(!simulator.is_input_forced(reg));
+            assert_eq!(simulator.get_input_val(sum), 0xff);
+            simulator.clock(&mut clock);
+            assert_eq!(simulator.display_input_value(sum), "0xff (forced)");
+            assert_eq!(simulator.display_input_value(reg), "0xff");
+
+            // sequential outputs are forced after the clock edge, also to unknown (X)
+            simulator.force_value("reg", 0, SignalValue::Unknown);
+            simulator.clock(&mut clock);
+            assert_eq!(simulator.get_input_value(reg), SignalValue::Unknown);
+
+            // forced outputs are kept over reset
+            simulator.reset(&mut clock);
+            assert_eq!(simulator.get_input_val(sum), 0xff);
+            assert_eq!(simulator.get_input_value(reg), SignalValue::Unknown);
+
+            // released outputs are evaluated on the next clock
+            simulator.set_id_index("po1", 0, 1);
+            simulator.set_id_index("po2", 0, 2);
+            simulator.release("add", 0);
+            simulator.release_all();
+            assert!(!simulator.is_input_forced(sum));
+            simulator.clock(&mut clock);
+            assert_eq!(simulator.get_input_val(sum), 3);
+            simulator.clock(&mut clock);
+            assert_eq!(simulator.get_input_val(reg), 3);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempt to force add at index 2")]
+    fn test_force_out_of_range() {
+        let cs = counter(8);
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        simulator.force("add", 2, 0);
+    }
+}
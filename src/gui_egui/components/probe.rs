@@ -1,4 +1,4 @@
-use crate::common::{EguiComponent, Simulator};
+use crate::common::{EguiComponent, SignalValue, Simulator};
 use crate::components::Probe;
 use egui::{Align2, Area, Color32, Order, Rect, RichText};
 
@@ -16,7 +16,8 @@ impl EguiComponent for Probe {
         offset.x += self.pos.0 * scale;
         offset.y += self.pos.1 * scale;
         let input = self.input.clone();
-        let value = simulator.get_input_val(&input);
+        let value = simulator.get_input_value(&input);
+        let forced = simulator.is_input_forced(&input);
         Area::new(self.id.to_string())
             .order(Order::Middle)
             .current_pos(offset.to_pos2())
@@ -25,12 +26,19 @@ impl EguiComponent for Probe {
             .pivot(Align2::CENTER_CENTER)
             .show(ui.ctx(), |ui| {
                 ui.set_clip_rect(clip_rect);
+                // unknown (X) and high-impedance (Z) values are shown in red,
+                // forced values (see `Simulator::force`) in yellow
+                let background_color = match value {
+                    _ if forced => Color32::LIGHT_YELLOW,
+                    SignalValue::Data(_) => Color32::LIGHT_BLUE,
+                    _ => Color32::LIGHT_RED,
+                };
                 ui.label(
-                    RichText::new(format!("{:?}", value))
+                    RichText::new(format!("{}", value))
                         .size(scale * 12f32)
-                        .background_color(Color32::LIGHT_BLUE),
+                        .background_color(background_color),
                 )
-                .on_hover_text(format!("{:#x}", value));
+                .on_hover_text(simulator.display_input_value(&input));
             });
     }
 }
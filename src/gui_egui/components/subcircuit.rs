@@ -0,0 +1,50 @@
+use crate::gui_egui::helper::offset_helper;
+use crate::{
+    common::{EguiComponent, Simulator},
+    components::{Subcircuit, SubcircuitInput},
+};
+
+#[typetag::serde]
+impl EguiComponent for Subcircuit {
+    fn render(
+        &self,
+        ui: &mut egui::Ui,
+        _simulator: Simulator,
+        offset: egui::Vec2,
+        scale: f32,
+        _clip_rect: egui::Rect,
+    ) {
+        let oh: fn((f32, f32), f32, egui::Vec2) -> egui::Pos2 = offset_helper;
+        let mut offset = offset;
+        offset.x += self.pos.0 * scale;
+        offset.y += self.pos.1 * scale;
+        let s = scale;
+        let o = offset;
+        let (w, h) = (self.width / 2f32, self.height / 2f32);
+        // the box
+        ui.painter().add(egui::Shape::closed_line(
+            vec![
+                oh((-w, -h), s, o),
+                oh((w, -h), s, o),
+                oh((w, h), s, o),
+                oh((-w, h), s, o),
+            ],
+            egui::Stroke {
+                width: scale,
+                color: egui::Color32::BLACK,
+            },
+        ));
+        // the name of the instance
+        ui.painter().text(
+            oh((0f32, 0f32), s, o),
+            egui::Align2::CENTER_CENTER,
+            &self.id,
+            egui::FontId::monospace(12f32 * scale),
+            egui::Color32::BLACK,
+        );
+    }
+}
+
+// inputs of expanded subcircuits are not shown
+#[typetag::serde]
+impl EguiComponent for SubcircuitInput {}
@@ -1,5 +1,7 @@
-use crate::common::{ComponentStore, Simulator};
+use crate::common::{ComponentStore, Signal, Simulator, SimulatorError};
 use crate::gui_egui::{keymap, keymap::Shortcuts, menu::Menu};
+use crate::timing::Timing;
+use crate::validate::{bounds, Bounds, Problem};
 use eframe::egui;
 use std::path::PathBuf;
 
@@ -7,7 +9,7 @@ pub struct Gui {
     pub simulator: Simulator,
     pub path: PathBuf,
     // History, acts like a stack
-    pub history: Vec<Vec<u32>>,
+    pub history: Vec<Vec<Signal>>,
     pub scale: f32,
     pub clock: usize,
     // When the ui elements change size
@@ -17,14 +19,52 @@ pub struct Gui {
     pub clip_rect: egui::Rect,
     pub shortcuts: Shortcuts,
     pub pause: bool,
+    // Error to be reported to the user
+    pub error: Option<SimulatorError>,
+    // Problems found in the model, shown on request
+    pub problems: Vec<Problem>,
+    pub show_problems: bool,
+    // Timing analysis of the model, shown on request, the critical path is highlighted
+    pub timing: Timing,
+    pub critical: Vec<Bounds>,
+    pub show_timing: bool,
+    // Breakpoint condition being edited
+    pub breakpoint: String,
+    pub breakpoints: Vec<String>,
+    // Models the opened subcircuits were opened from, outermost first
+    pub outer: Vec<OuterModel>,
 }
 
-pub fn gui(cs: &ComponentStore, path: &PathBuf) -> Result<(), eframe::Error> {
+// Model shown before opening a subcircuit, returned to on closing it
+pub struct OuterModel {
+    pub id: String,
+    pub clock: usize,
+    pub simulator: Simulator,
+    pub problems: Vec<Problem>,
+    pub timing: Timing,
+    pub critical: Vec<Bounds>,
+}
+
+/// Open the GUI on the model, the error (if any) of loading the model is reported on start.
+pub fn gui(
+    cs: &ComponentStore,
+    path: &PathBuf,
+    error: Option<SimulatorError>,
+) -> Result<(), eframe::Error> {
     let mut clock = 0;
-    let simulator = Simulator::new(cs, &mut clock);
+    let (simulator, error) = match Simulator::try_new(cs, &mut clock) {
+        Ok(simulator) => (simulator, error),
+        // start with an empty model, and report the error
+        Err(err) => (
+            Simulator::new(&ComponentStore { store: vec![] }, &mut clock),
+            Some(err),
+        ),
+    };
+    let timing = simulator.timing();
+    let critical = simulator.critical_bounds();
     let options = eframe::NativeOptions::default();
     let path = path.to_owned();
-    simulator.save_dot(&path);
+    cs.save_dot(&path);
     let gui = Gui {
         clock,
         path,
@@ -37,6 +77,15 @@ pub fn gui(cs: &ComponentStore, path: &PathBuf) -> Result<(), eframe::Error> {
         clip_rect: egui::Rect::NOTHING,
         shortcuts: Shortcuts::new(),
         pause: true,
+        error,
+        problems: cs.validate(),
+        show_problems: false,
+        timing,
+        critical,
+        show_timing: false,
+        breakpoint: String::new(),
+        breakpoints: vec![],
+        outer: vec![],
     };
     eframe::run_native("SyncRim", options, Box::new(|_cc| Box::new(gui)))
 }
@@ -72,6 +121,9 @@ impl eframe::App for Gui {
             self.side_panel(ctx);
             self.draw_area(ctx, frame);
         }
+        self.error_window(ctx);
+        self.problems_window(ctx);
+        self.timing_window(ctx);
     }
 }
 
@@ -104,7 +156,16 @@ impl Gui {
         let central_panel = egui::CentralPanel::default().frame(frame).show(ctx, |ui| {
             ui.set_clip_rect(self.clip_rect);
             // Don't draw over the rest of the ui
-            for c in &self.simulator.ordered_components {
+            // the components of expanded subcircuits are not shown
+            for (c, scope) in self
+                .simulator
+                .ordered_components
+                .iter()
+                .zip(&self.simulator.scopes)
+            {
+                if !scope.is_empty() {
+                    continue;
+                }
                 c.render(
                     ui,
                     self.simulator.clone(),
@@ -113,11 +174,67 @@ impl Gui {
                     self.clip_rect,
                 );
             }
+            // activity heatmap, more active components in deeper red
+            for (((x, y), (width, height)), heat) in self.simulator.activity_heat() {
+                let center =
+                    (self.offset + self.pan).to_pos2() + egui::Vec2::new(x, y) * self.scale;
+                ui.painter().rect_filled(
+                    egui::Rect::from_center_size(
+                        center,
+                        egui::Vec2::new(width, height) * self.scale,
+                    ),
+                    0.0,
+                    egui::Color32::from_rgba_unmultiplied(255, 0, 0, (heat * 128.0) as u8),
+                );
+            }
+            // highlight the critical path
+            for ((x, y), (width, height)) in &self.critical {
+                let center = (self.offset + self.pan).to_pos2()
+                    + egui::Vec2::new(*x, *y) * self.scale;
+                ui.painter().rect_stroke(
+                    egui::Rect::from_center_size(
+                        center,
+                        egui::Vec2::new(*width, *height) * self.scale,
+                    ),
+                    0.0,
+                    egui::Stroke {
+                        width: 2.0 * self.scale,
+                        color: egui::Color32::BLUE,
+                    },
+                );
+            }
         });
-        let cpr = central_panel.response.interact(egui::Sense::drag());
+        let cpr = central_panel
+            .response
+            .interact(egui::Sense::click_and_drag());
         if cpr.dragged_by(egui::PointerButton::Middle) {
             self.pan += cpr.drag_delta();
         }
+        // drill into the subcircuit double clicked
+        if cpr.double_clicked() {
+            if let Some(pos) = cpr.interact_pointer_pos() {
+                let pos = (pos - self.offset - self.pan).to_vec2() / self.scale;
+                let (x, y) = (pos.x, pos.y);
+                let id = self
+                    .simulator
+                    .ordered_components
+                    .iter()
+                    .zip(&self.simulator.scopes)
+                    .filter(|(c, scope)| scope.is_empty() && c.model().is_some())
+                    .find(|(c, _)| {
+                        serde_json::to_value(&***c)
+                            .ok()
+                            .and_then(|value| bounds(&value))
+                            .is_some_and(|((cx, cy), (width, height))| {
+                                (x - cx).abs() <= width / 2.0 && (y - cy).abs() <= height / 2.0
+                            })
+                    })
+                    .map(|(c, _)| c.get_id_ports().0);
+                if let Some(id) = id {
+                    keymap::file_open_subcircuit_fn(self, &id);
+                }
+            }
+        }
         if central_panel.response.hovered() {
             ctx.input_mut(|i| {
                 if i.scroll_delta.y > 0f32 {
@@ -140,6 +257,49 @@ impl Gui {
         });
     }
 
+    fn error_window(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        if let Some(error) = &self.error {
+            egui::Window::new("Error")
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.label(error.to_string());
+                });
+        }
+        if !open {
+            self.error = None;
+        }
+    }
+
+    fn problems_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Problems")
+            .open(&mut self.show_problems)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(format!("{} problems found", self.problems.len()));
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for problem in &self.problems {
+                        ui.label(problem.to_string());
+                    }
+                });
+            });
+    }
+
+    fn timing_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Timing")
+            .open(&mut self.show_timing)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(self.timing.to_string());
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for path in &self.timing.paths {
+                        ui.label(path.to_string());
+                    }
+                });
+            });
+    }
+
     fn top_bar(&mut self, ctx: &egui::Context) {
         egui::TopBottomPanel::top("topBar").show(ctx, |ui| Menu::new(ui, self));
     }
@@ -1,3 +1,5 @@
+use crate::common::Simulator;
+use crate::condition::{Condition, RUN_UNTIL_MAX_CYCLES};
 use egui::{Key, KeyboardShortcut, Modifiers};
 
 #[derive(Copy, Clone)]
@@ -196,6 +198,103 @@ pub fn file_save_fn(_gui: &mut crate::gui_egui::gui::Gui) {}
 pub fn file_save_as_fn(_gui: &mut crate::gui_egui::gui::Gui) {}
 pub fn file_preferences_fn(_gui: &mut crate::gui_egui::gui::Gui) {}
 pub fn file_quit_fn(_gui: &mut crate::gui_egui::gui::Gui) {}
+pub fn file_save_checkpoint_fn(gui: &mut crate::gui_egui::gui::Gui) {
+    if let Some(path) = rfd::FileDialog::new()
+        .add_filter("json", &["json"])
+        .save_file()
+    {
+        if let Err(err) = gui.simulator.save_checkpoint(&path, true) {
+            gui.error = Some(err);
+        }
+    }
+}
+pub fn file_load_checkpoint_fn(gui: &mut crate::gui_egui::gui::Gui) {
+    if let Some(path) = rfd::FileDialog::new()
+        .add_filter("json", &["json"])
+        .pick_file()
+    {
+        if let Err(err) = gui.simulator.load_checkpoint(&path, &mut gui.clock) {
+            gui.error = Some(err);
+        }
+        gui.pause = true;
+    }
+}
+pub fn file_export_waveform_fn(gui: &mut crate::gui_egui::gui::Gui) {
+    if let Some(path) = rfd::FileDialog::new()
+        .add_filter("vcd", &["vcd"])
+        .save_file()
+    {
+        if let Err(err) = gui.simulator.save_vcd(&path) {
+            gui.error = Some(err);
+        }
+    }
+}
+pub fn file_export_activity_fn(gui: &mut crate::gui_egui::gui::Gui) {
+    if let Some(path) = rfd::FileDialog::new()
+        .add_filter("activity", &["txt", "json"])
+        .save_file()
+    {
+        if let Err(err) = gui.simulator.save_activity(&path) {
+            gui.error = Some(err);
+        }
+    }
+}
+pub fn file_load_stimulus_fn(gui: &mut crate::gui_egui::gui::Gui) {
+    if let Some(path) = rfd::FileDialog::new()
+        .add_filter("stimulus", &["csv", "json"])
+        .pick_file()
+    {
+        if let Err(err) = gui.simulator.load_stimulus(&path, &mut gui.clock) {
+            gui.error = Some(err);
+        }
+        gui.pause = true;
+    }
+}
+// drill into the subcircuit, simulated standalone, the current model is kept to return to
+pub fn file_open_subcircuit_fn(gui: &mut crate::gui_egui::gui::Gui, id: &str) {
+    let model = gui
+        .simulator
+        .component_ids
+        .iter()
+        .position(|i| i == id)
+        .and_then(|i| gui.simulator.ordered_components[i].model());
+    let mut clock = 0;
+    match model
+        .map(|cs| cs.and_then(|cs| Ok((cs.validate(), Simulator::try_new(&cs, &mut clock)?))))
+    {
+        Some(Ok((problems, mut simulator))) => {
+            if gui.simulator.four_state {
+                simulator.set_four_state(true, &mut clock);
+            }
+            let outer = crate::gui_egui::gui::OuterModel {
+                id: id.to_string(),
+                clock: std::mem::replace(&mut gui.clock, clock),
+                timing: std::mem::replace(&mut gui.timing, simulator.timing()),
+                critical: std::mem::replace(&mut gui.critical, simulator.critical_bounds()),
+                simulator: std::mem::replace(&mut gui.simulator, simulator),
+                problems: std::mem::replace(&mut gui.problems, problems),
+            };
+            gui.outer.push(outer);
+            check_breakpoints(gui);
+            gui.pause = true;
+        }
+        // keep the current model, and report the error
+        Some(Err(err)) => gui.error = Some(err),
+        None => {}
+    }
+}
+// return to the model the subcircuit was opened from
+pub fn file_close_subcircuit_fn(gui: &mut crate::gui_egui::gui::Gui) {
+    if let Some(outer) = gui.outer.pop() {
+        gui.clock = outer.clock;
+        gui.simulator = outer.simulator;
+        gui.problems = outer.problems;
+        gui.timing = outer.timing;
+        gui.critical = outer.critical;
+        check_breakpoints(gui);
+        gui.pause = true;
+    }
+}
 pub fn edit_cut_fn(_gui: &mut crate::gui_egui::gui::Gui) {}
 pub fn edit_copy_fn(_gui: &mut crate::gui_egui::gui::Gui) {}
 pub fn edit_paste_fn(_gui: &mut crate::gui_egui::gui::Gui) {}
@@ -238,3 +337,42 @@ pub fn control_step_forward(gui: &mut crate::gui_egui::gui::Gui) {
 pub fn control_step_back(gui: &mut crate::gui_egui::gui::Gui) {
     gui.simulator.un_clock(&mut gui.clock);
 }
+pub fn control_run_until(gui: &mut crate::gui_egui::gui::Gui) {
+    if let Some(condition) = breakpoint_condition(gui) {
+        gui.simulator
+            .run_until(&condition, RUN_UNTIL_MAX_CYCLES, &mut gui.clock);
+    }
+    gui.pause = true;
+}
+pub fn control_rewind_until(gui: &mut crate::gui_egui::gui::Gui) {
+    if let Some(condition) = breakpoint_condition(gui) {
+        gui.simulator.rewind_until(&condition, &mut gui.clock);
+    }
+    gui.pause = true;
+}
+pub fn breakpoint_add(gui: &mut crate::gui_egui::gui::Gui) {
+    match Condition::parse(&gui.breakpoint).and_then(|c| c.check(&gui.simulator)) {
+        Ok(()) => gui.breakpoints.push(std::mem::take(&mut gui.breakpoint)),
+        Err(err) => gui.error = Some(err),
+    }
+}
+// any of the breakpoints, None if there are no breakpoints
+fn breakpoint_condition(gui: &crate::gui_egui::gui::Gui) -> Option<Condition> {
+    if gui.breakpoints.is_empty() {
+        None
+    } else {
+        // the breakpoints parsed as added (and checked as the model changes)
+        Some(Condition::any(
+            gui.breakpoints
+                .iter()
+                .map(|b| Condition::parse(b).unwrap())
+                .collect(),
+        ))
+    }
+}
+// remove the breakpoints not valid in the current model, and report them
+fn check_breakpoints(gui: &mut crate::gui_egui::gui::Gui) {
+    if let Err(err) = gui.simulator.check_breakpoints(&mut gui.breakpoints) {
+        gui.error = Some(err);
+    }
+}
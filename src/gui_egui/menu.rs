@@ -29,6 +29,25 @@ impl Menu {
                     // Save As here
                 }
                 ui.separator();
+                if ui.button("Save Checkpoint").clicked() {
+                    crate::gui_egui::keymap::file_save_checkpoint_fn(gui);
+                }
+                if ui.button("Load Checkpoint").clicked() {
+                    crate::gui_egui::keymap::file_load_checkpoint_fn(gui);
+                }
+                if ui.button("Export Waveform").clicked() {
+                    crate::gui_egui::keymap::file_export_waveform_fn(gui);
+                }
+                if ui.button("Export Activity").clicked() {
+                    crate::gui_egui::keymap::file_export_activity_fn(gui);
+                }
+                if ui.button("Load Stimulus").clicked() {
+                    crate::gui_egui::keymap::file_load_stimulus_fn(gui);
+                }
+                if ui.button("Close Subcircuit").clicked() {
+                    crate::gui_egui::keymap::file_close_subcircuit_fn(gui);
+                }
+                ui.separator();
                 if btn(ui, "Preferences", gui.shortcuts.file_preferences).clicked() {
                     // Preferences here
                 }
@@ -82,6 +101,23 @@ impl Menu {
                         gui.scale = 2f32;
                     }
                 });
+                ui.separator();
+                let mut four_state = gui.simulator.four_state;
+                if ui.checkbox(&mut four_state, "Four-State (X/Z)").changed() {
+                    gui.simulator.set_four_state(four_state, &mut gui.clock);
+                    gui.pause = true;
+                }
+                // tracked from zero, shown as a heatmap from the next clock
+                let mut activity = gui.simulator.activity.is_some();
+                if ui.checkbox(&mut activity, "Activity Heatmap").changed() {
+                    gui.simulator.set_activity(activity);
+                }
+                if ui.button("Problems").clicked() {
+                    gui.show_problems = true;
+                }
+                if ui.button("Timing").clicked() {
+                    gui.show_timing = true;
+                }
             });
 
             ui.menu_button("Help", |ui| {
@@ -109,7 +145,39 @@ impl Menu {
             if ui.button("⏸").clicked() {
                 crate::gui_egui::keymap::control_pause(gui);
             }
+            if ui
+                .button("⏪")
+                .on_hover_text("UnClock until breakpoint")
+                .clicked()
+            {
+                crate::gui_egui::keymap::control_rewind_until(gui);
+            }
+            if ui
+                .button("⏩")
+                .on_hover_text("Clock until breakpoint")
+                .clicked()
+            {
+                crate::gui_egui::keymap::control_run_until(gui);
+            }
             ui.label(format!("Clock #{}", gui.clock));
+            ui.separator();
+
+            // breakpoint condition, e.g., `reg.0 == 0x40`, added on enter
+            let response = ui
+                .text_edit_singleline(&mut gui.breakpoint)
+                .on_hover_text("Add breakpoint condition (Enter)");
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                crate::gui_egui::keymap::breakpoint_add(gui);
+            }
+            let mut remove = None;
+            for (index, breakpoint) in gui.breakpoints.iter().enumerate() {
+                if ui.button(format!("{} ✖", breakpoint)).clicked() {
+                    remove = Some(index);
+                }
+            }
+            if let Some(index) = remove {
+                gui.breakpoints.remove(index);
+            }
         });
     }
 }
@@ -1,5 +1,5 @@
 use crate::{
-    common::{Component, ViziaComponent},
+    common::{Component, SignalValue, ViziaComponent},
     components::Probe,
     gui_vizia::{popup::NewPopup, tooltip::new_component_tooltip, GuiData},
 };
@@ -18,10 +18,21 @@ impl ViziaComponent for Probe {
             let input = self.input.clone();
 
             Binding::new(cx, crate::gui_vizia::GuiData::clock, move |cx, _| {
-                Label::new(cx, {
-                    let simulator = GuiData::simulator.get(cx);
-                    &format!(" {:?}", simulator.get_input_val(&input))
-                })
+                let simulator = GuiData::simulator.get(cx);
+                let value = simulator.get_input_value(&input);
+                let forced = simulator.is_input_forced(&input);
+                // unknown (X) and high-impedance (Z) values are shown in red,
+                // forced values (see `Simulator::force`) are marked and shown in yellow
+                let background_color = match value {
+                    _ if forced => Color::lightyellow(),
+                    SignalValue::Data(_) => Color::transparent(),
+                    _ => Color::lightcoral(),
+                };
+                Label::new(
+                    cx,
+                    &format!(" {}{}", value, if forced { " (forced)" } else { "" }),
+                )
+                .background_color(background_color)
                 .hoverable(false);
             });
             NewPopup::new(cx, self.get_id_ports()).position_type(PositionType::SelfDirected);
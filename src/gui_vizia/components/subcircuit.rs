@@ -0,0 +1,86 @@
+use crate::{
+    common::{Component, ViziaComponent},
+    components::{Subcircuit, SubcircuitInput},
+    gui_vizia::{popup::NewPopup, tooltip::new_component_tooltip, GuiEvent},
+};
+
+use vizia::{
+    prelude::*,
+    vg::{Paint, Path},
+};
+
+#[typetag::serde]
+impl ViziaComponent for Subcircuit {
+    // create view
+    fn view(&self, cx: &mut Context) {
+        println!("---- Create Subcircuit View");
+
+        View::build(
+            SubcircuitView {
+                id: self.id.clone(),
+            },
+            cx,
+            |cx| {
+                Label::new(cx, &self.id)
+                    .hoverable(false)
+                    .left(Pixels(10.0))
+                    .top(Pixels(10.0));
+                NewPopup::new(cx, self.get_id_ports()).position_type(PositionType::SelfDirected);
+            },
+        )
+        .position_type(PositionType::SelfDirected)
+        .left(Pixels(self.pos.0 - self.width / 2.0))
+        .top(Pixels(self.pos.1 - self.height / 2.0))
+        .width(Pixels(self.width))
+        .height(Pixels(self.height))
+        .on_press(|ex| ex.emit(PopupEvent::Switch))
+        .tooltip(|cx| new_component_tooltip(cx, self));
+    }
+}
+
+// inputs of expanded subcircuits are not shown
+#[typetag::serde]
+impl ViziaComponent for SubcircuitInput {}
+
+struct SubcircuitView {
+    // opened on double click, on top of the model shown
+    id: String,
+}
+
+impl View for SubcircuitView {
+    fn element(&self) -> Option<&'static str> {
+        Some("Subcircuit")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, _meta| {
+            if let WindowEvent::MouseDoubleClick(MouseButton::Left) = window_event {
+                cx.emit(GuiEvent::OpenSubcircuit(self.id.clone()));
+            }
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext<'_>, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let mut path = Path::new();
+        let mut paint = Paint::color(vizia::vg::Color::rgbf(0.0, 0.0, 0.0));
+        paint.set_line_width(cx.logical_to_physical(1.0));
+
+        let left = bounds.left();
+        let right = bounds.right();
+        let top = bounds.top();
+        let bottom = bounds.bottom();
+
+        // draw box, with a double border marking the hierarchy
+        for inset in [0.5, 3.5] {
+            path.move_to(left + inset, top + inset);
+            path.line_to(right - inset, top + inset);
+            path.line_to(right - inset, bottom - inset);
+            path.line_to(left + inset, bottom - inset);
+            path.line_to(left + inset, top + inset);
+        }
+
+        canvas.stroke_path(&path, &paint);
+    }
+}
@@ -1,5 +1,7 @@
-use crate::common::{ComponentStore, Simulator};
+use crate::common::{ComponentStore, Simulator, SimulatorError};
+use crate::condition::{Condition, RUN_UNTIL_MAX_CYCLES};
 use crate::gui_vizia::{grid::Grid, menu::Menu, transport::Transport, keymap::init_keymap};
+use crate::validate::Bounds;
 use rfd::FileDialog;
 use std::path::PathBuf;
 use vizia::prelude::*;
@@ -12,10 +14,38 @@ pub struct GuiData {
     pub pause: bool,
     pub is_saved: bool,
     pub show_about: bool,
+    pub show_error: bool,
+    pub error: String,
+    pub show_problems: bool,
+    // problems found in the model, by `ComponentStore::validate`
+    pub problems: Vec<String>,
+    pub show_timing: bool,
+    // timing analysis of the model, by `Simulator::timing`
+    pub timing: Vec<String>,
+    // position and size of the components on the critical path, highlighted
+    pub critical: Vec<Bounds>,
     pub selected_id: usize,
+    // breakpoint condition being edited
+    pub breakpoint: String,
+    pub breakpoints: Vec<String>,
+    // models the opened subcircuits were opened from, outermost first
+    pub outer: Vec<OuterModel>,
+    // ids of the opened subcircuits, as `id/id`, empty for the model itself
+    pub subcircuit: String,
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+// model shown before opening a subcircuit, returned to on `GuiEvent::CloseSubcircuit`
+#[derive(Clone)]
+pub struct OuterModel {
+    pub id: String,
+    pub clock: usize,
+    pub simulator: Simulator,
+    pub problems: Vec<String>,
+    pub timing: Vec<String>,
+    pub critical: Vec<Bounds>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub(crate) enum GuiEvent {
     Open,
     ReOpen,
@@ -28,6 +58,26 @@ pub(crate) enum GuiEvent {
     Preferences,
     ShowAbout,
     HideAbout,
+    HideError,
+    ShowProblems,
+    HideProblems,
+    ShowTiming,
+    HideTiming,
+    ActivityToggle,
+    FourStateToggle,
+    SaveCheckpoint,
+    LoadCheckpoint,
+    ExportWaveform,
+    ExportActivity,
+    LoadStimulus,
+    EditBreakpoint(String),
+    AddBreakpoint(String),
+    RemoveBreakpoint(usize),
+    RunUntil,
+    RewindUntil,
+    // the subcircuit (by id) drilled into
+    OpenSubcircuit(String),
+    CloseSubcircuit,
     // SelectComponent(usize),
 }
 
@@ -51,11 +101,13 @@ impl Model for GuiData {
                 let files = FileDialog::new().add_filter("json", &["json"]).pick_file();
                 println!("files {:?}", files);
                 if let Some(path_buf) = files {
-                    self.path = path_buf;
-                    self.open();
+                    self.open(path_buf);
                 }
             }
-            GuiEvent::ReOpen => self.open(),
+            GuiEvent::ReOpen => self.open(self.path.clone()),
+            // drill into the subcircuit, simulated standalone on top of the current model
+            GuiEvent::OpenSubcircuit(id) => self.open_subcircuit(id),
+            GuiEvent::CloseSubcircuit => self.close_subcircuit(),
             GuiEvent::Clock => self.simulator.clock(&mut self.clock),
             GuiEvent::UnClock => self.simulator.un_clock(&mut self.clock),
             GuiEvent::Reset => {
@@ -68,29 +120,252 @@ impl Model for GuiData {
             GuiEvent::Preferences => println!("Preferences"),
             GuiEvent::ShowAbout => self.show_about = true,
             GuiEvent::HideAbout => self.show_about = false,
+            GuiEvent::HideError => self.show_error = false,
+            GuiEvent::ShowProblems => self.show_problems = true,
+            GuiEvent::HideProblems => self.show_problems = false,
+            GuiEvent::ShowTiming => self.show_timing = true,
+            GuiEvent::HideTiming => self.show_timing = false,
+            GuiEvent::SaveCheckpoint => {
+                let file = FileDialog::new().add_filter("json", &["json"]).save_file();
+                println!("file {:?}", file);
+                if let Some(path_buf) = file {
+                    if let Err(err) = self.simulator.save_checkpoint(&path_buf, true) {
+                        self.error = err.to_string();
+                        self.show_error = true;
+                    }
+                }
+            }
+            GuiEvent::LoadCheckpoint => {
+                let files = FileDialog::new().add_filter("json", &["json"]).pick_file();
+                println!("files {:?}", files);
+                if let Some(path_buf) = files {
+                    if let Err(err) = self.simulator.load_checkpoint(&path_buf, &mut self.clock) {
+                        self.error = err.to_string();
+                        self.show_error = true;
+                    }
+                    self.pause = true;
+                }
+            }
+            GuiEvent::ExportWaveform => {
+                let file = FileDialog::new().add_filter("vcd", &["vcd"]).save_file();
+                println!("file {:?}", file);
+                if let Some(path_buf) = file {
+                    if let Err(err) = self.simulator.save_vcd(&path_buf) {
+                        self.error = err.to_string();
+                        self.show_error = true;
+                    }
+                }
+            }
+            GuiEvent::ExportActivity => {
+                let file = FileDialog::new()
+                    .add_filter("activity", &["txt", "json"])
+                    .save_file();
+                println!("file {:?}", file);
+                if let Some(path_buf) = file {
+                    if let Err(err) = self.simulator.save_activity(&path_buf) {
+                        self.error = err.to_string();
+                        self.show_error = true;
+                    }
+                }
+            }
+            GuiEvent::LoadStimulus => {
+                let files = FileDialog::new()
+                    .add_filter("stimulus", &["csv", "json"])
+                    .pick_file();
+                println!("files {:?}", files);
+                if let Some(path_buf) = files {
+                    if let Err(err) = self.simulator.load_stimulus(&path_buf, &mut self.clock) {
+                        self.error = err.to_string();
+                        self.show_error = true;
+                    }
+                    self.pause = true;
+                }
+            }
+            GuiEvent::EditBreakpoint(text) => self.breakpoint = text.clone(),
+            GuiEvent::AddBreakpoint(text) => {
+                match Condition::parse(text).and_then(|c| c.check(&self.simulator)) {
+                    Ok(()) => {
+                        self.breakpoints.push(text.clone());
+                        self.breakpoint = String::new();
+                    }
+                    Err(err) => {
+                        self.error = err.to_string();
+                        self.show_error = true;
+                    }
+                }
+            }
+            GuiEvent::RemoveBreakpoint(index) => {
+                self.breakpoints.remove(*index);
+            }
+            GuiEvent::RunUntil => {
+                if let Some(condition) = self.breakpoint_condition() {
+                    self.simulator.run_until(&condition, RUN_UNTIL_MAX_CYCLES, &mut self.clock);
+                }
+                self.pause = true;
+            }
+            GuiEvent::RewindUntil => {
+                if let Some(condition) = self.breakpoint_condition() {
+                    self.simulator.rewind_until(&condition, &mut self.clock);
+                }
+                self.pause = true;
+            }
+            // tracked from zero, shown as a heatmap from the next clock
+            GuiEvent::ActivityToggle => {
+                let activity = self.simulator.activity.is_none();
+                self.simulator.set_activity(activity);
+            }
+            GuiEvent::FourStateToggle => {
+                let four_state = !self.simulator.four_state;
+                self.simulator.set_four_state(four_state, &mut self.clock);
+                self.pause = true;
+            }
             // GuiEvent::SelectComponent(index) => self.selected_id = *index,
         });
     }
 }
 
 impl GuiData {
-    fn open(&mut self) {
-        // Re-Open model
-        println!("open path {:?}", self.path);
-        let cs = Box::new(ComponentStore::load_file(&self.path));
-        let simulator = Simulator::new(&cs, &mut self.clock);
+    // any of the breakpoints, None if there are no breakpoints
+    fn breakpoint_condition(&self) -> Option<Condition> {
+        if self.breakpoints.is_empty() {
+            None
+        } else {
+            // the breakpoints parsed as added (and checked as the model changes)
+            Some(Condition::any(
+                self.breakpoints
+                    .iter()
+                    .map(|b| Condition::parse(b).unwrap())
+                    .collect(),
+            ))
+        }
+    }
+
+    // remove the breakpoints not valid in the current model, and report them
+    fn check_breakpoints(&mut self) {
+        if let Err(err) = self.simulator.check_breakpoints(&mut self.breakpoints) {
+            self.error = err.to_string();
+            self.show_error = true;
+        }
+    }
 
-        self.simulator = simulator;
+    // show the model if it loads and simulates, replacing the current model,
+    // otherwise the current model is kept and the error reported, returns true if shown
+    fn show(&mut self, cs: Result<ComponentStore, SimulatorError>) -> bool {
+        let mut clock = 0;
+        match cs.and_then(|cs| Ok((problems(&cs), Simulator::try_new(&cs, &mut clock)?))) {
+            Ok((problems, mut simulator)) => {
+                // keep the four-state mode of the current model
+                if self.simulator.four_state {
+                    simulator.set_four_state(true, &mut clock);
+                }
+                self.problems = problems;
+                self.timing = timing(&simulator);
+                self.critical = simulator.critical_bounds();
+                self.simulator = simulator;
+                self.clock = clock;
+                self.check_breakpoints();
+                true
+            }
+            Err(err) => {
+                // keep the current model, and report the error
+                println!("error {}", err);
+                self.error = err.to_string();
+                self.show_error = true;
+                false
+            }
+        }
+    }
 
-        println!("opened");
+    // (re-)open the model file, the path is kept only if the model is shown
+    fn open(&mut self, path: PathBuf) {
+        println!("open path {:?}", path);
+        let cs = ComponentStore::try_load_file(&path);
+        if let Ok(cs) = &cs {
+            cs.save_dot(&path);
+        }
+        if self.show(cs) {
+            self.path = path;
+            self.outer.clear();
+            self.subcircuit = String::new();
+            println!("opened");
+        }
     }
+
+    // drill into the subcircuit, the current model is kept to return to
+    fn open_subcircuit(&mut self, id: &str) {
+        let model = self
+            .simulator
+            .component_ids
+            .iter()
+            .position(|i| i == id)
+            .and_then(|i| self.simulator.ordered_components[i].model());
+        let outer = OuterModel {
+            id: id.to_string(),
+            clock: self.clock,
+            simulator: self.simulator.clone(),
+            problems: self.problems.clone(),
+            timing: self.timing.clone(),
+            critical: self.critical.clone(),
+        };
+        if let Some(model) = model {
+            if self.show(model) {
+                self.outer.push(outer);
+                self.subcircuit = self.subcircuit_path();
+                self.pause = true;
+            }
+        }
+    }
+
+    // ids of the opened subcircuits, as `id/id`
+    fn subcircuit_path(&self) -> String {
+        let ids: Vec<_> = self.outer.iter().map(|o| o.id.as_str()).collect();
+        ids.join("/")
+    }
+
+    // return to the model the subcircuit was opened from
+    fn close_subcircuit(&mut self) {
+        if let Some(outer) = self.outer.pop() {
+            self.clock = outer.clock;
+            self.simulator = outer.simulator;
+            self.problems = outer.problems;
+            self.timing = outer.timing;
+            self.critical = outer.critical;
+            self.subcircuit = self.subcircuit_path();
+            self.check_breakpoints();
+            self.pause = true;
+        }
+    }
+}
+
+// problems found in the model, one line each
+fn problems(cs: &ComponentStore) -> Vec<String> {
+    cs.validate().iter().map(|p| p.to_string()).collect()
+}
+
+// timing analysis of the model, the summary followed by the paths, longest first
+fn timing(simulator: &Simulator) -> Vec<String> {
+    let timing = simulator.timing();
+    std::iter::once(timing.to_string())
+        .chain(timing.paths.iter().map(|path| path.to_string()))
+        .collect()
 }
 
-pub fn gui(cs: &ComponentStore, path: &PathBuf) {
+/// Open the GUI on the model, the error (if any) of loading the model is reported on start.
+pub fn gui(cs: &ComponentStore, path: &PathBuf, error: Option<SimulatorError>) {
     let mut clock = 0;
-    let simulator = Simulator::new(cs, &mut clock);
+    let problems = problems(cs);
+    let (simulator, error) = match Simulator::try_new(cs, &mut clock) {
+        Ok(simulator) => (simulator, error.map(|err| err.to_string())),
+        // start with an empty model, and report the error
+        Err(err) => (
+            Simulator::new(&ComponentStore { store: vec![] }, &mut clock),
+            Some(err.to_string()),
+        ),
+    };
+    let timing = timing(&simulator);
+    let critical = simulator.critical_bounds();
     let path = path.to_owned();
-    simulator.save_dot(&path);
+    cs.save_dot(&path);
 
     Application::new(move |cx| {
 
@@ -106,7 +381,18 @@ pub fn gui(cs: &ComponentStore, path: &PathBuf) {
             pause: true,
             is_saved: false,
             show_about: false,
+            show_error: error.is_some(),
+            error: error.unwrap_or_default(),
+            show_problems: false,
+            problems,
+            show_timing: false,
+            timing,
+            critical,
             selected_id: 0,
+            breakpoint: String::new(),
+            breakpoints: vec![],
+            outer: vec![],
+            subcircuit: String::new(),
         }
         .build(cx);
 
@@ -119,6 +405,11 @@ pub fn gui(cs: &ComponentStore, path: &PathBuf) {
                         .top(Stretch(1.0))
                         .bottom(Stretch(1.0))
                         .height(Auto);
+                    // the opened subcircuit, if any
+                    Label::new(cx, GuiData::subcircuit)
+                        .top(Stretch(1.0))
+                        .bottom(Stretch(1.0))
+                        .height(Auto);
                 })
                 .col_between(Pixels(10.0))
                 .top(Stretch(1.0))
@@ -129,14 +420,45 @@ pub fn gui(cs: &ComponentStore, path: &PathBuf) {
             .height(Auto);
 
             Grid::new(cx, |cx| {
-                // (re-)bind all components when simulator changed
+                // (re-)bind all components when simulator changed,
+                // the components of expanded subcircuits are not shown
                 Binding::new(
                     cx,
                     GuiData::simulator.then(Simulator::ordered_components),
                     |cx, wrapper_oc| {
                         let oc = wrapper_oc.get(cx);
-                        for c in oc {
-                            c.view(cx);
+                        let scopes = GuiData::simulator.then(Simulator::scopes).get(cx);
+                        for (c, scope) in oc.iter().zip(scopes) {
+                            if scope.is_empty() {
+                                c.view(cx);
+                            }
+                        }
+                        // activity heatmap, more active components in deeper red
+                        Binding::new(cx, GuiData::clock, |cx, _| {
+                            // mapped, such that only the heat is cloned from the simulator
+                            let heat = GuiData::simulator.map(Simulator::activity_heat).get(cx);
+                            for (((x, y), (width, height)), heat) in heat {
+                                Element::new(cx)
+                                    .position_type(PositionType::SelfDirected)
+                                    .left(Pixels(x - width / 2.0))
+                                    .top(Pixels(y - height / 2.0))
+                                    .width(Pixels(width))
+                                    .height(Pixels(height))
+                                    .background_color(Color::rgba(255, 0, 0, (heat * 128.0) as u8))
+                                    .hoverable(false);
+                            }
+                        });
+                        // highlight the critical path
+                        for ((x, y), (width, height)) in GuiData::critical.get(cx) {
+                            Element::new(cx)
+                                .position_type(PositionType::SelfDirected)
+                                .left(Pixels(x - width / 2.0))
+                                .top(Pixels(y - height / 2.0))
+                                .width(Pixels(width))
+                                .height(Pixels(height))
+                                .border_color(Color::blue())
+                                .border_width(Pixels(2.0))
+                                .hoverable(false);
                         }
                     },
                 )
@@ -164,6 +486,59 @@ pub fn gui(cs: &ComponentStore, path: &PathBuf) {
             })
             .on_blur(|cx| cx.emit(GuiEvent::HideAbout))
             .class("modal");
+
+            // Error
+            Popup::new(cx, GuiData::show_error, true, |cx| {
+                Label::new(cx, "Error").class("title");
+                Label::new(cx, GuiData::error);
+
+                Button::new(
+                    cx,
+                    |cx| cx.emit(GuiEvent::HideError),
+                    |cx| Label::new(cx, "Ok"),
+                )
+                .class("accent");
+            })
+            .on_blur(|cx| cx.emit(GuiEvent::HideError))
+            .class("modal");
+
+            // Problems
+            Popup::new(cx, GuiData::show_problems, true, |cx| {
+                Label::new(cx, "Problems").class("title");
+                Label::new(
+                    cx,
+                    GuiData::problems.map(|problems| format!("{} problems found", problems.len())),
+                );
+                List::new(cx, GuiData::problems, |cx, _, item| {
+                    Label::new(cx, item);
+                });
+
+                Button::new(
+                    cx,
+                    |cx| cx.emit(GuiEvent::HideProblems),
+                    |cx| Label::new(cx, "Ok"),
+                )
+                .class("accent");
+            })
+            .on_blur(|cx| cx.emit(GuiEvent::HideProblems))
+            .class("modal");
+
+            // Timing
+            Popup::new(cx, GuiData::show_timing, true, |cx| {
+                Label::new(cx, "Timing").class("title");
+                List::new(cx, GuiData::timing, |cx, _, item| {
+                    Label::new(cx, item);
+                });
+
+                Button::new(
+                    cx,
+                    |cx| cx.emit(GuiEvent::HideTiming),
+                    |cx| Label::new(cx, "Ok"),
+                )
+                .class("accent");
+            })
+            .on_blur(|cx| cx.emit(GuiEvent::HideTiming))
+            .class("modal");
         });
     })
     .title("SyncRim")
@@ -53,6 +53,14 @@ impl Menu {
                                     })
                                 },
                             );
+                            MenuButton::new(
+                                cx,
+                                |cx| {
+                                    println!("Close Subcircuit");
+                                    cx.emit(GuiEvent::CloseSubcircuit);
+                                },
+                                |cx| Label::new(cx, "Close Subcircuit"),
+                            );
                             Submenu::new(
                                 cx,
                                 |cx| Label::new(cx, "Open Recent"),
@@ -98,6 +106,32 @@ impl Menu {
                                 |cx| Label::new(cx, "Save As"),
                             );
                             MenuDivider::new(cx);
+                            MenuButton::new(
+                                cx,
+                                |cx| cx.emit(GuiEvent::SaveCheckpoint),
+                                |cx| Label::new(cx, "Save Checkpoint"),
+                            );
+                            MenuButton::new(
+                                cx,
+                                |cx| cx.emit(GuiEvent::LoadCheckpoint),
+                                |cx| Label::new(cx, "Load Checkpoint"),
+                            );
+                            MenuButton::new(
+                                cx,
+                                |cx| cx.emit(GuiEvent::ExportWaveform),
+                                |cx| Label::new(cx, "Export Waveform"),
+                            );
+                            MenuButton::new(
+                                cx,
+                                |cx| cx.emit(GuiEvent::ExportActivity),
+                                |cx| Label::new(cx, "Export Activity"),
+                            );
+                            MenuButton::new(
+                                cx,
+                                |cx| cx.emit(GuiEvent::LoadStimulus),
+                                |cx| Label::new(cx, "Load Stimulus"),
+                            );
+                            MenuDivider::new(cx);
                             MenuButton::new(
                                 cx,
                                 |cx| cx.emit(GuiEvent::Preferences),
@@ -209,6 +243,27 @@ impl Menu {
                                     );
                                 },
                             );
+                            MenuDivider::new(cx);
+                            MenuButton::new(
+                                cx,
+                                |cx| cx.emit(GuiEvent::FourStateToggle),
+                                |cx| Label::new(cx, "Four-State (X/Z)"),
+                            );
+                            MenuButton::new(
+                                cx,
+                                |cx| cx.emit(GuiEvent::ActivityToggle),
+                                |cx| Label::new(cx, "Activity Heatmap"),
+                            );
+                            MenuButton::new(
+                                cx,
+                                |cx| cx.emit(GuiEvent::ShowProblems),
+                                |cx| Label::new(cx, "Problems"),
+                            );
+                            MenuButton::new(
+                                cx,
+                                |cx| cx.emit(GuiEvent::ShowTiming),
+                                |cx| Label::new(cx, "Timing"),
+                            );
                         },
                     );
 
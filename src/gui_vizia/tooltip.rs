@@ -6,33 +6,28 @@ pub fn new_component_tooltip(cx: &mut Context, component: &dyn Component) {
         let (id, ports) = component.get_id_ports();
         Label::new(cx, &id);
 
-        for input in ports.inputs {
+        for (input, name) in ports.inputs.into_iter().zip(ports.input_names) {
             HStack::new(cx, |cx| {
-                Label::new(cx, &input.id);
+                Label::new(cx, &name);
                 Binding::new(cx, GuiData::clock, move |cx, _| {
-                    Label::new(
-                        cx,
-                        &format!("{:?}", GuiData::simulator.get(cx).get_input_val(&input)),
-                    )
+                    Label::new(cx, &{
+                        GuiData::simulator.get(cx).display_input_value(&input)
+                    })
                     .class("tt_shortcut");
                 })
             })
             .size(Auto);
         }
-        for output in 0..ports.outputs.len() {
+        for (output, name) in ports.output_names.into_iter().enumerate() {
             let id_clone = id.clone();
             HStack::new(cx, move |cx| {
-                Label::new(cx, &format!("out {}", output));
+                Label::new(cx, &name);
                 Binding::new(cx, GuiData::clock, move |cx, _| {
-                    Label::new(
-                        cx,
-                        &format!(
-                            "{:?}",
-                            GuiData::simulator.get(cx).get(
-                                GuiData::simulator.get(cx).get_id_start_index(&id_clone) + output
-                            )
-                        ),
-                    )
+                    Label::new(cx, &{
+                        let simulator = GuiData::simulator.get(cx);
+                        let index = simulator.get_id_start_index(&id_clone) + output;
+                        simulator.display_value(index)
+                    })
                     .class("tt_shortcut");
                 });
                 // Label::new(cx, v).class("tt_shortcut");
@@ -102,6 +102,52 @@ impl Transport {
                     })
                     .size(Auto);
                 });
+
+                // Rewind until breakpoint
+                Button::new(
+                    cx,
+                    |ex| ex.emit(GuiEvent::RewindUntil),
+                    |cx| Label::new(cx, icons::ICON_CHEVRONS_LEFT).class("icon"),
+                )
+                .tooltip(|cx| {
+                    Label::new(cx, "UnClock until breakpoint");
+                });
+
+                // Run until breakpoint
+                Button::new(
+                    cx,
+                    |ex| ex.emit(GuiEvent::RunUntil),
+                    |cx| Label::new(cx, icons::ICON_CHEVRONS_RIGHT).class("icon"),
+                )
+                .tooltip(|cx| {
+                    Label::new(cx, "Clock until breakpoint");
+                });
+
+                // Breakpoint condition, e.g., `reg.0 == 0x40`
+                Textbox::new(cx, GuiData::breakpoint)
+                    .on_edit(|cx, text| cx.emit(GuiEvent::EditBreakpoint(text)))
+                    .on_submit(|cx, text, _| cx.emit(GuiEvent::AddBreakpoint(text)))
+                    .width(Pixels(200.0))
+                    .tooltip(|cx| {
+                        Label::new(cx, "Add breakpoint condition (Enter)");
+                    });
+
+                // Breakpoints
+                List::new(cx, GuiData::breakpoints, |cx, index, item| {
+                    HStack::new(cx, |cx| {
+                        Label::new(cx, item);
+                        Button::new(
+                            cx,
+                            move |ex| ex.emit(GuiEvent::RemoveBreakpoint(index)),
+                            |cx| Label::new(cx, icons::ICON_X).class("icon"),
+                        );
+                    })
+                    .col_between(Pixels(5.0))
+                    .size(Auto);
+                })
+                .layout_type(LayoutType::Row)
+                .col_between(Pixels(5.0))
+                .size(Auto);
             })
             .col_between(Pixels(5.0))
             .size(Auto);
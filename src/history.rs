@@ -0,0 +1,402 @@
+use crate::common::SignalValue;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+/// Default maximum number of clock cycles kept in the history
+pub const DEFAULT_HISTORY_DEPTH: usize = 100_000;
+
+/// Default number of clock cycles between keyframes
+pub const DEFAULT_KEYFRAME_INTERVAL: usize = 100;
+
+/// (cycle, (index, value) of the signals changed at the cycle)
+pub type Changes = Vec<(usize, Vec<(usize, SignalValue)>)>;
+
+/// Bounded simulation history.
+///
+/// Each clock cycle is stored as a delta, holding the previous values of the signals
+/// changed by the clock, or edited before the next clock (e.g., set by the user).
+/// Every `keyframe_interval` cycles a full copy of the state is kept
+/// (keyframe), bounding the work needed to look up the value of a signal at a past cycle.
+/// At most `max_depth` cycles are kept, older cycles are dropped (ring buffer).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct History {
+    pub max_depth: usize,
+    pub keyframe_interval: usize,
+    // cycle of the oldest entry
+    first: usize,
+    entries: VecDeque<Entry>,
+    // state after the latest clock, signals differing from it are edits since the clock
+    #[serde(default)]
+    next: Option<Vec<SignalValue>>,
+}
+
+/// History entry for a single clock cycle
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Entry {
+    // full state at the cycle (keyframe)
+    pub keyframe: Option<Vec<SignalValue>>,
+    // (index, value) of the signals changed by the clock or edited since, value at the cycle
+    pub delta: Vec<(usize, SignalValue)>,
+    // (index, undo log) of the components changed by the clock (see `Component::undo_log`)
+    pub components: Vec<(usize, serde_json::Value)>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new(DEFAULT_HISTORY_DEPTH, DEFAULT_KEYFRAME_INTERVAL)
+    }
+}
+
+impl History {
+    pub fn new(max_depth: usize, keyframe_interval: usize) -> Self {
+        History {
+            max_depth,
+            keyframe_interval: keyframe_interval.max(1),
+            first: 0,
+            entries: VecDeque::new(),
+            next: None,
+        }
+    }
+
+    /// number of cycles kept
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// no cycles kept
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// oldest cycle kept
+    pub fn first(&self) -> usize {
+        self.first
+    }
+
+    /// drop all cycles, continuing from `cycle`
+    pub fn clear(&mut self, cycle: usize) {
+        self.first = cycle;
+        self.entries.clear();
+        self.next = None;
+    }
+
+    /// set the maximum number of cycles kept, dropping the oldest cycles if needed
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+        self.truncate();
+    }
+
+    /// push the `cycle`, given the state at the cycle and the state after the clock
+    pub fn push(
+        &mut self,
+        cycle: usize,
+        state: Vec<SignalValue>,
+        next: &[SignalValue],
+        components: Vec<(usize, serde_json::Value)>,
+    ) {
+        if self.entries.is_empty() {
+            self.first = cycle;
+        }
+        // signals edited since the previous clock are changes of the previous cycle
+        if let (Some(entry), Some(previous)) = (self.entries.back_mut(), &self.next) {
+            entry.delta = merge(&entry.delta, previous, &state);
+        }
+        self.next = Some(next.to_vec());
+        let delta = state
+            .iter()
+            .zip(next)
+            .enumerate()
+            .filter(|(_, (value, next))| value != next)
+            .map(|(index, (value, _))| (index, *value))
+            .collect();
+        let keyframe = if cycle.is_multiple_of(self.keyframe_interval) {
+            Some(state)
+        } else {
+            None
+        };
+        self.entries.push_back(Entry {
+            keyframe,
+            delta,
+            components,
+        });
+        self.truncate();
+    }
+
+    /// pop the latest cycle, given the current state,
+    /// the delta of the entry also restores the signals edited since the clock
+    pub fn pop(&mut self, current: &[SignalValue]) -> Option<Entry> {
+        let mut entry = self.entries.pop_back()?;
+        if let Some(next) = &self.next {
+            entry.delta = merge(&entry.delta, next, current);
+        }
+        // the state at the popped cycle, later edits are compared with
+        let mut state = current.to_vec();
+        for (index, value) in &entry.delta {
+            state[*index] = *value;
+        }
+        self.next = Some(state);
+        Some(entry)
+    }
+
+    /// value of the signal at `index` at a past `cycle`, given the current state,
+    /// None if the cycle is not kept in the history
+    pub fn value_at(
+        &self,
+        cycle: usize,
+        index: usize,
+        current: &[SignalValue],
+    ) -> Option<SignalValue> {
+        if cycle < self.first || cycle > self.first + self.entries.len() {
+            return None;
+        }
+        // the value at the cycle is the previous value of the first later change,
+        // or found in the first later keyframe
+        for position in cycle - self.first..self.entries.len() {
+            if let Some(keyframe) = &self.entries[position].keyframe {
+                return Some(keyframe[index]);
+            }
+            let delta = self.delta(position, current);
+            if let Some((_, value)) = delta.iter().find(|(i, _)| *i == index) {
+                return Some(*value);
+            }
+        }
+        Some(current[index])
+    }
+
+    /// replay of the history, given the current state, as the state at the oldest cycle kept
+    /// and the (index, value) of the signals changed at each later cycle
+    pub fn changes(&self, current: &[SignalValue]) -> (Vec<SignalValue>, Changes) {
+        // walk backwards from the current state, undoing the deltas
+        let mut state = current.to_vec();
+        let mut changes = vec![];
+        for i in (0..self.entries.len()).rev() {
+            let delta = self.delta(i, &state);
+            if !delta.is_empty() {
+                let changed = delta
+                    .iter()
+                    .map(|(index, _)| (*index, state[*index]))
+                    .collect();
+                changes.push((self.first + i + 1, changed));
+            }
+            for (index, value) in delta.iter() {
+                state[*index] = *value;
+            }
+        }
+        changes.reverse();
+        (state, changes)
+    }
+
+    // delta of the entry at `position`, for the latest entry including the edits since the clock
+    fn delta(&self, position: usize, current: &[SignalValue]) -> Cow<'_, [(usize, SignalValue)]> {
+        let delta = &self.entries[position].delta;
+        match &self.next {
+            Some(next) if position + 1 == self.entries.len() => {
+                Cow::Owned(merge(delta, next, current))
+            }
+            _ => Cow::Borrowed(delta),
+        }
+    }
+
+    // drop the oldest cycles exceeding the maximum depth
+    fn truncate(&mut self) {
+        while self.entries.len() > self.max_depth {
+            self.entries.pop_front();
+            self.first += 1;
+        }
+    }
+}
+
+// the `delta` of a clock extended by the signals edited after the clock,
+// given the state after the clock (`next`) and the state after the edits (`current`),
+// signals restored to their value at the cycle are left out
+fn merge(
+    delta: &[(usize, SignalValue)],
+    next: &[SignalValue],
+    current: &[SignalValue],
+) -> Vec<(usize, SignalValue)> {
+    let mut merged: Vec<_> = delta
+        .iter()
+        .filter(|(index, value)| current[*index] != *value)
+        .copied()
+        .collect();
+    merged.extend(
+        next.iter()
+            .zip(current)
+            .enumerate()
+            .filter(|(index, (next, current))| {
+                next != current && delta.binary_search_by_key(index, |(i, _)| *i).is_err()
+            })
+            .map(|(index, (next, _))| (index, *next)),
+    );
+    merged.sort_by_key(|(index, _)| *index);
+    merged
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::{Input, Simulator};
+    use crate::components::counter;
+
+    fn state(values: &[u128]) -> Vec<SignalValue> {
+        values.iter().map(|v| SignalValue::Data(*v)).collect()
+    }
+
+    #[test]
+    fn test_value_at() {
+        let mut history = History::new(10, 4);
+        // signal 0 counts up, signal 1 is constant
+        for cycle in 0..8 {
+            history.push(
+                cycle,
+                state(&[cycle as u128, 7]),
+                &state(&[cycle as u128 + 1, 7]),
+                vec![],
+            );
+        }
+        let current = state(&[8, 7]);
+        assert_eq!(history.len(), 8);
+        for cycle in 0..=8 {
+            assert_eq!(
+                history.value_at(cycle, 0, &current),
+                Some(SignalValue::Data(cycle as u128))
+            );
+            assert_eq!(
+                history.value_at(cycle, 1, &current),
+                Some(SignalValue::Data(7))
+            );
+        }
+        assert_eq!(history.value_at(9, 0, &current), None);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let mut history = History::new(3, 4);
+        for cycle in 0..8 {
+            history.push(
+                cycle,
+                state(&[cycle as u128]),
+                &state(&[cycle as u128 + 1]),
+                vec![],
+            );
+        }
+        let current = state(&[8]);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.first(), 5);
+        assert_eq!(history.value_at(4, 0, &current), None);
+        assert_eq!(history.value_at(5, 0, &current), Some(SignalValue::Data(5)));
+
+        // pop restores the values of the latest cycle
+        let entry = history.pop(&current).unwrap();
+        assert_eq!(entry.delta, vec![(0, SignalValue::Data(7))]);
+        assert_eq!(history.len(), 2);
+
+        history.set_max_depth(1);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history.first(), 6);
+    }
+
+    #[test]
+    fn test_changes() {
+        let mut history = History::new(10, 4);
+        // signal 0 toggles every cycle, signal 1 is constant
+        for cycle in 2..5 {
+            history.push(
+                cycle,
+                state(&[cycle as u128 % 2, 7]),
+                &state(&[(cycle as u128 + 1) % 2, 7]),
+                vec![],
+            );
+        }
+        let (initial, changes) = history.changes(&state(&[1, 7]));
+        assert_eq!(initial, state(&[0, 7]));
+        assert_eq!(
+            changes,
+            vec![
+                (3, vec![(0, SignalValue::Data(1))]),
+                (4, vec![(0, SignalValue::Data(0))]),
+                (5, vec![(0, SignalValue::Data(1))]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut history = History::new(10, 4);
+        // signal 0 counts up, signal 1 is edited to 7 after the clock of cycle 0
+        history.push(0, state(&[0, 0]), &state(&[1, 0]), vec![]);
+        history.push(1, state(&[1, 7]), &state(&[2, 7]), vec![]);
+        // and to 9 after the clock of cycle 1
+        let current = state(&[2, 9]);
+        assert_eq!(history.value_at(0, 1, &current), Some(SignalValue::Data(0)));
+        assert_eq!(history.value_at(1, 1, &current), Some(SignalValue::Data(7)));
+        assert_eq!(history.value_at(2, 1, &current), Some(SignalValue::Data(9)));
+        let (initial, changes) = history.changes(&current);
+        assert_eq!(initial, state(&[0, 0]));
+        assert_eq!(
+            changes,
+            vec![
+                (
+                    1,
+                    vec![(0, SignalValue::Data(1)), (1, SignalValue::Data(7))]
+                ),
+                (
+                    2,
+                    vec![(0, SignalValue::Data(2)), (1, SignalValue::Data(9))]
+                ),
+            ]
+        );
+
+        // pop restores the edited values
+        let entry = history.pop(&current).unwrap();
+        assert_eq!(
+            entry.delta,
+            vec![(0, SignalValue::Data(1)), (1, SignalValue::Data(7))]
+        );
+        let entry = history.pop(&state(&[1, 7])).unwrap();
+        assert_eq!(
+            entry.delta,
+            vec![(0, SignalValue::Data(0)), (1, SignalValue::Data(0))]
+        );
+    }
+
+    #[test]
+    fn test_history() {
+        let cs = counter(32);
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+
+        // counter
+        let reg = &Input::new("reg", 0);
+        let mut values = vec![simulator.get_input_value(reg)];
+        for _ in 0..250 {
+            simulator.clock(&mut clock);
+            values.push(simulator.get_input_value(reg));
+        }
+        assert_eq!(clock, 251);
+        assert_eq!(simulator.get_input_val(reg), 250);
+
+        // values at past cycles, without restoring the state
+        for (cycle, value) in values.iter().enumerate() {
+            assert_eq!(simulator.get_input_value_at(cycle + 1, reg), Some(*value));
+        }
+        assert_eq!(simulator.get_input_value_at(252, reg), None);
+
+        // bounded history, only the last 2 cycles are kept
+        simulator.set_history_depth(2);
+        assert_eq!(simulator.get_input_value_at(248, reg), None);
+        assert_eq!(
+            simulator.get_input_value_at(249, reg),
+            Some(SignalValue::Data(248))
+        );
+        simulator.un_clock(&mut clock);
+        simulator.un_clock(&mut clock);
+        assert_eq!(clock, 249);
+        assert_eq!(simulator.get_input_val(reg), 248);
+        // no more history
+        simulator.un_clock(&mut clock);
+        assert_eq!(clock, 249);
+        assert_eq!(simulator.get_input_val(reg), 248);
+    }
+}
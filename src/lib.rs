@@ -1,6 +1,19 @@
+pub mod activity;
+pub mod checkpoint;
+pub mod cli;
 pub mod common;
 pub mod component_store;
+pub mod condition;
+pub mod force;
+pub mod history;
+pub mod migration;
+pub mod observer;
 pub mod simulator;
+pub mod stimulus;
+pub mod testbench;
+pub mod timing;
+pub mod validate;
+pub mod vcd;
 
 // Default provided components
 #[cfg(feature = "components")]
@@ -15,5 +28,7 @@ pub mod gui_vizia;
 pub mod gui_egui;
 
 // Re-export
+pub use inventory;
+
 #[cfg(feature = "gui-vizia")]
 pub use vizia;
@@ -0,0 +1,211 @@
+use crate::common::SimulatorError;
+use serde::Deserialize;
+use serde_json::{value::RawValue, Value};
+use std::borrow::Cow;
+
+/// Current model format version, stored as `version` in saved models.
+///
+/// Models saved before versioning are at version 0. Bump the version when a change
+/// to a component breaks previously saved models, and submit a `Migration` for the component.
+/// No change has broken saved models yet.
+pub const MODEL_VERSION: u32 = 0;
+
+/// Migration of a component from a model format version to the next.
+///
+/// Migrations are registered by `inventory::submit!`, e.g.,
+///
+/// ```ignore
+/// syncrim::inventory::submit! {
+///     Migration {
+///         component: "Mem",
+///         version: 1,
+///         migrate: |json| json["width"] = 100.into(),
+///     }
+/// }
+/// ```
+///
+/// and applied in order of version, before the component is deserialized.
+pub struct Migration {
+    // component type, as tagged in the model (`"type"`)
+    pub component: &'static str,
+    // version migrated from (to `version + 1`)
+    pub version: u32,
+    // upgrade the serialized component
+    pub migrate: fn(&mut Value),
+}
+
+inventory::collect!(Migration);
+
+// model, with components kept as source
+#[derive(Deserialize)]
+struct RawModel<'a> {
+    #[serde(default)]
+    version: u32,
+    #[serde(borrow, default)]
+    store: Vec<&'a RawValue>,
+}
+
+// component type of a serialized component, and the model it embeds (e.g., `Subcircuit`)
+#[derive(Deserialize)]
+struct ComponentType<'a> {
+    #[serde(rename = "type")]
+    component_type: String,
+    #[serde(borrow, default)]
+    model: Option<&'a RawValue>,
+}
+
+// embedded model, at the version of the model it is embedded in
+#[derive(Deserialize)]
+struct EmbeddedModel<'a> {
+    #[serde(borrow)]
+    store: Vec<&'a RawValue>,
+}
+
+fn parse_err(err: serde_json::Error) -> SimulatorError {
+    SimulatorError::Parse {
+        line: err.line(),
+        col: err.column(),
+        msg: err.to_string(),
+    }
+}
+
+// migrations of the component type, from `version` up to version `to`
+fn migrations(component_type: &str, version: u32, to: u32) -> Vec<&'static Migration> {
+    let mut migrations: Vec<_> = inventory::iter::<Migration>
+        .into_iter()
+        .filter(|m| m.component == component_type)
+        .filter(|m| m.version >= version && m.version < to)
+        .collect();
+    migrations.sort_by_key(|m| m.version);
+    migrations
+}
+
+/// model format version of a serialized model
+pub fn model_version(json: &str) -> Result<u32, SimulatorError> {
+    let model: RawModel = serde_json::from_str(json).map_err(parse_err)?;
+    Ok(model.version)
+}
+
+/// migrate a serialized model to the current version, the model is returned unchanged
+/// if no migrations apply, returns an error if the model is newer than supported
+pub fn migrate(json: &str) -> Result<Cow<'_, str>, SimulatorError> {
+    migrate_to(json, MODEL_VERSION)
+}
+
+// migrate a serialized model to version `to`
+fn migrate_to(json: &str, to: u32) -> Result<Cow<'_, str>, SimulatorError> {
+    let model: RawModel = serde_json::from_str(json).map_err(parse_err)?;
+    if model.version > to {
+        return Err(SimulatorError::UnsupportedVersion {
+            version: model.version,
+            supported: to,
+        });
+    }
+
+    let store = migrate_store(&model.store, model.version, to)?;
+    match store {
+        Some(store) => Ok(Cow::Owned(format!(
+            "{{\"version\":{},\"store\":[{}]}}",
+            to,
+            store.join(",")
+        ))),
+        None => Ok(Cow::Borrowed(json)),
+    }
+}
+
+// migrate serialized components from `version` to `to`, including the components
+// of embedded models, None if no migrations apply
+fn migrate_store(
+    store: &[&RawValue],
+    version: u32,
+    to: u32,
+) -> Result<Option<Vec<String>>, SimulatorError> {
+    let mut migrated = false;
+    let mut components = vec![];
+    for component in store {
+        let (component_type, model) = serde_json::from_str::<ComponentType>(component.get())
+            .map(|c| (c.component_type, c.model))
+            .unwrap_or_default();
+        // a field `model` not holding a model is not an embedded model
+        let model = model.and_then(|m| serde_json::from_str::<EmbeddedModel>(m.get()).ok());
+        let embedded = match model {
+            Some(model) => migrate_store(&model.store, version, to)?,
+            None => None,
+        };
+        let migrations = migrations(&component_type, version, to);
+        if migrations.is_empty() && embedded.is_none() {
+            components.push(component.get().to_string());
+            continue;
+        }
+        let mut value: Value = serde_json::from_str(component.get()).map_err(parse_err)?;
+        if let Some(embedded) = embedded {
+            value["model"]["store"] = embedded
+                .iter()
+                .map(|c| serde_json::from_str(c))
+                .collect::<Result<Vec<Value>, _>>()
+                .map_err(parse_err)?
+                .into();
+        }
+        for migration in migrations {
+            (migration.migrate)(&mut value);
+        }
+        components.push(value.to_string());
+        migrated = true;
+    }
+    Ok(migrated.then_some(components))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    inventory::submit! {
+        Migration {
+            component: "TestMigration",
+            version: 0,
+            migrate: |json| json["renamed"] = json["old"].take(),
+        }
+    }
+
+    // migrated to a version 1, as the current version has no migrations
+    #[test]
+    fn test_migrate() {
+        assert!(migrations("TestMigration", 0, MODEL_VERSION).is_empty());
+        let json = r#"{"store": [{"type": "TestMigration", "old": 1}, {"type": "Other"}]}"#;
+        assert_eq!(model_version(json), Ok(0));
+        let migrated = migrate_to(json, 1).unwrap();
+        assert_eq!(
+            migrated,
+            format!(
+                "{{\"version\":1,\"store\":[{},{}]}}",
+                r#"{"old":null,"renamed":1,"type":"TestMigration"}"#, r#"{"type": "Other"}"#
+            )
+        );
+
+        // already at the version
+        assert_eq!(model_version(&migrated), Ok(1));
+        assert!(matches!(migrate_to(&migrated, 1), Ok(Cow::Borrowed(_))));
+
+        // components of embedded models are migrated along with the model
+        let json = r#"{"store": [{"type": "Subcircuit", "model": {"store": [{"type": "TestMigration", "old": 1}]}}]}"#;
+        assert_eq!(
+            migrate_to(json, 1).unwrap(),
+            format!(
+                "{{\"version\":1,\"store\":[{}]}}",
+                r#"{"model":{"store":[{"old":null,"renamed":1,"type":"TestMigration"}]},"type":"Subcircuit"}"#
+            )
+        );
+        let json =
+            r#"{"store": [{"type": "Subcircuit", "model": {"store": [{"type": "Other"}]}}]}"#;
+        assert!(matches!(migrate_to(json, 1), Ok(Cow::Borrowed(_))));
+
+        let json = r#"{"version": 1000, "store": []}"#;
+        assert_eq!(
+            migrate(json),
+            Err(SimulatorError::UnsupportedVersion {
+                version: 1000,
+                supported: MODEL_VERSION
+            })
+        );
+    }
+}
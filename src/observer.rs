@@ -0,0 +1,203 @@
+use crate::common::{Input, SignalValue, Simulator};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc::Sender;
+
+/// A change of a signal value, by a clock, un_clock or reset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    // the output (`id.index`) changed
+    pub signal: Input,
+    pub old: SignalValue,
+    pub new: SignalValue,
+}
+
+/// Observer of a running simulation.
+///
+/// Observers are registered by `Simulator::add_observer` and called by the simulator,
+/// such that embedding applications (e.g., grading scripts or co-simulators) may react
+/// to the simulation without polling the state. Observers have read access to the simulator,
+/// during evaluation the components are taken from `ordered_components`.
+pub trait Observer {
+    /// before each clock, at the current cycle
+    fn before_clock(&mut self, _simulator: &Simulator) {}
+
+    /// after each clock, when all components have been evaluated
+    fn after_clock(&mut self, _simulator: &Simulator) {}
+
+    /// after each evaluation of component `id` on a clock (including forced outputs)
+    fn evaluated(&mut self, _simulator: &Simulator, _id: &str) {}
+
+    /// after reset (see `Simulator::reset`), clocks on reset are not observed
+    fn reset(&mut self, _simulator: &Simulator) {}
+
+    /// after stepping back a clock (see `Simulator::un_clock`)
+    fn un_clock(&mut self, _simulator: &Simulator) {}
+
+    /// signals changed by a clock, un_clock or reset, called after the corresponding hook
+    fn changed(&mut self, _simulator: &Simulator, _changes: &[Change]) {}
+}
+
+/// Change notification stream, each change is sent to the receiver
+/// (changes are dropped once the receiver is gone).
+impl Observer for Sender<Change> {
+    fn changed(&mut self, _simulator: &Simulator, changes: &[Change]) {
+        for change in changes {
+            let _ = self.send(change.clone());
+        }
+    }
+}
+
+impl Simulator {
+    /// register observer, called in order of registration
+    pub fn add_observer(&mut self, observer: Rc<RefCell<dyn Observer>>) {
+        self.observers.push(observer);
+    }
+
+    /// remove all observers
+    pub fn clear_observers(&mut self) {
+        self.observers.clear();
+    }
+
+    // call each observer
+    pub(crate) fn notify(&self, f: impl Fn(&mut dyn Observer)) {
+        for observer in &self.observers {
+            f(&mut *observer.borrow_mut());
+        }
+    }
+
+    // signals changed from the previous state
+    pub(crate) fn changes(&self, previous: &[SignalValue]) -> Vec<Change> {
+        let mut changes = vec![];
+        for (id, outputs) in self.component_ids.iter().zip(&self.output_ranges) {
+            for index in outputs.clone() {
+                if previous[index] != self.sim_state[index] {
+                    changes.push(Change {
+                        signal: Input::new(id, index - outputs.start),
+                        old: previous[index],
+                        new: self.sim_state[index],
+                    });
+                }
+            }
+        }
+        changes
+    }
+
+    // notify observers of changed signals, if any
+    pub(crate) fn notify_changes(&self, changes: &[Change]) {
+        if !changes.is_empty() {
+            self.notify(|observer| observer.changed(self, changes));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::counter;
+
+    // records the observed events
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+    }
+
+    impl Observer for Recorder {
+        fn before_clock(&mut self, simulator: &Simulator) {
+            self.events
+                .push(format!("before_clock {}", simulator.cycle));
+        }
+
+        fn after_clock(&mut self, simulator: &Simulator) {
+            self.events.push(format!("after_clock {}", simulator.cycle));
+        }
+
+        fn evaluated(&mut self, _simulator: &Simulator, id: &str) {
+            self.events.push(format!("evaluated {}", id));
+        }
+
+        fn reset(&mut self, simulator: &Simulator) {
+            self.events.push(format!("reset {}", simulator.cycle));
+        }
+
+        fn un_clock(&mut self, simulator: &Simulator) {
+            self.events.push(format!("un_clock {}", simulator.cycle));
+        }
+
+        fn changed(&mut self, _simulator: &Simulator, changes: &[Change]) {
+            for change in changes {
+                self.events.push(format!(
+                    "changed {}.{} {} -> {}",
+                    change.signal.id, change.signal.index, change.old, change.new
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_observer() {
+        let cs = counter(32);
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        let recorder = Rc::new(RefCell::new(Recorder::default()));
+        simulator.add_observer(recorder.clone());
+
+        simulator.clock(&mut clock);
+        assert_eq!(
+            recorder.borrow().events,
+            vec![
+                "before_clock 1",
+                "evaluated reg",
+                "evaluated c",
+                "evaluated add",
+                "after_clock 2",
+                "changed reg.0 0 -> 1",
+                "changed add.0 1 -> 2",
+            ]
+        );
+
+        simulator.clock(&mut clock);
+        recorder.borrow_mut().events.clear();
+        simulator.un_clock(&mut clock);
+        simulator.reset(&mut clock);
+        assert_eq!(
+            recorder.borrow().events,
+            vec![
+                "un_clock 2",
+                "changed reg.0 2 -> 1",
+                "changed add.0 3 -> 2",
+                "reset 1",
+                "changed reg.0 1 -> 0",
+                "changed add.0 2 -> 1",
+            ]
+        );
+
+        // removed observers are no longer called
+        recorder.borrow_mut().events.clear();
+        simulator.clear_observers();
+        simulator.clock(&mut clock);
+        assert!(recorder.borrow().events.is_empty());
+    }
+
+    #[test]
+    fn test_change_stream() {
+        let cs = counter(32);
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        simulator.add_observer(Rc::new(RefCell::new(sender)));
+
+        simulator.clock(&mut clock);
+        simulator.clock(&mut clock);
+        let changes: Vec<Change> = receiver.try_iter().collect();
+        assert_eq!(changes.len(), 4);
+        assert_eq!(
+            changes[3],
+            Change {
+                signal: Input::new("add", 0),
+                old: SignalValue::Data(2),
+                new: SignalValue::Data(3),
+            }
+        );
+    }
+}
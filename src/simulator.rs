@@ -1,140 +1,303 @@
-use crate::common::{Component, ComponentStore, Input, OutputType, Signal, Simulator};
+use crate::common::{
+    mask, Component, ComponentStore, Components, Cycle, IdPorts, Input, OutputType, Ports, Signal,
+    SignalValue, Simulator, SimulatorError,
+};
+use crate::debug;
+use crate::history::History;
+use crate::stimulus::Vectors;
+use crate::timing;
+use crate::validate::bounds;
 use petgraph::{
-    algo::toposort,
+    algo::{tarjan_scc, toposort},
     dot::{Config, Dot},
+    visit::EdgeRef,
     Graph,
 };
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::{fs::File, io::prelude::*, path::PathBuf};
 
 pub struct IdComponent(pub HashMap<String, Box<dyn Component>>);
 
 // Notice:
-// The topological order does not enforce any specific order of registers
-// Thus registers cannot point to other registers in a cyclic fashion
-// This is (likely) not occurring in practice.
+// Simulation is performed in two phases on each clock.
+//
+// 1. The clock edge, where all sequential components sample their inputs
+//    from the current state, and their outputs are committed atomically.
+// 2. Propagation, where combinatorial components are evaluated in topological order.
 //
-// A solution is to evaluate register updates separately from other components
-// ... but not currently implemented ...
+// Thus registers may depend on other registers (even in a cyclic fashion),
+// independent of the order in which they are evaluated.
 impl Simulator {
+    /// create a simulator from the model, panics if the model is faulty
     pub fn new(component_store: &ComponentStore, clock: &mut usize) -> Self {
+        Simulator::try_new(component_store, clock).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// create a simulator from the model, returns an error if the model is faulty
+    pub fn try_new(
+        component_store: &ComponentStore,
+        clock: &mut usize,
+    ) -> Result<Self, SimulatorError> {
         let mut lens_values = vec![];
+        let mut signal_widths = vec![];
 
         let mut id_start_index = HashMap::new();
         let mut id_component = HashMap::new(); // IdComponent(HashMap::new());
 
         let mut id_nr_outputs = HashMap::new();
-        // allocate storage for lensed outputs
 
-        println!("-- allocate storage for lensed outputs");
-        for c in &component_store.store {
-            let (id, ports) = c.get_id_ports();
+        // expand subcircuits, ids and inputs are made global by prefixing the scope
+        let (scopes, components) = component_store.flatten()?;
+        let id_ports: Vec<(String, Ports)> = scopes
+            .iter()
+            .zip(&components)
+            .map(|(scope, c)| scoped_id_ports(scope, c.get_id_ports()))
+            .collect();
 
-            println!("id {}, ports {:?}", id, ports);
+        // allocate storage for lensed outputs
+        debug!("-- allocate storage for lensed outputs");
+        for (i, (id, ports)) in id_ports.iter().enumerate() {
+            debug!("id {}, ports {:?}", id, ports);
             // start index for outputs related to component
             if id_start_index
                 .insert(id.clone(), lens_values.len())
                 .is_some()
             {
-                panic!("Component identifier {} is defined twice", id);
+                return Err(SimulatorError::DuplicateId(id.clone()));
             }
 
-            id_component.insert(id.clone(), c);
+            id_component.insert(id.clone(), i);
+
+            assert_eq!(
+                ports.outputs.len(),
+                ports.output_widths.len(),
+                "Component {} should declare a bit width for each output",
+                id
+            );
+            assert_eq!(
+                ports.inputs.len(),
+                ports.input_widths.len(),
+                "Component {} should declare a bit width for each input",
+                id
+            );
+            assert_eq!(
+                ports.outputs.len(),
+                ports.output_names.len(),
+                "Component {} should declare a name for each output",
+                id
+            );
+            assert_eq!(
+                ports.inputs.len(),
+                ports.input_names.len(),
+                "Component {} should declare a name for each input",
+                id
+            );
+
+            // components compute on widths of 1 to 128 bits
+            let widths = ports.input_names.iter().zip(&ports.input_widths);
+            let widths = widths.chain(ports.output_names.iter().zip(&ports.output_widths));
+            for (port, bit_width) in widths {
+                if *bit_width == 0 || *bit_width > Signal::BITS {
+                    return Err(SimulatorError::InvalidWidth {
+                        component: id.clone(),
+                        port: port.clone(),
+                        bit_width: *bit_width,
+                    });
+                }
+            }
 
             // create placeholder for output
             #[allow(clippy::same_item_push)]
             for _ in ports.outputs.clone() {
                 // create the value with a default to 0
-                lens_values.push(0);
+                lens_values.push(SignalValue::Data(0));
             }
+            signal_widths.extend(ports.output_widths.iter());
             id_nr_outputs.insert(id.clone(), ports.outputs.len());
         }
 
-        let mut graph = Graph::<_, (), petgraph::Directed>::new();
-        let mut id_node = HashMap::new();
-        let mut node_comp = HashMap::new();
-
-        // insert nodes
-        for (id, c) in &id_component {
-            let node = graph.add_node(id.to_owned());
-            id_node.insert(id, node);
-            node_comp.insert(node, c);
-        }
-
-        println!("\nid_node {:?}", id_node);
-
-        for (node, c) in &node_comp {
-            println!("node {:?}, comp_id {:?}", node, c.get_id_ports());
-        }
+        let graph = dependency_graph(&id_ports);
+        debug!("graph {:?}", graph);
+        let ids: Vec<String> = id_ports.iter().map(|(id, _)| id.clone()).collect();
+        let id_ports: IdPorts = id_ports.into_iter().collect();
 
-        // insert edges
-        for (to_id, c) in &id_component {
-            let to_component = id_component.get(to_id).unwrap();
-            let (_, ports) = to_component.get_id_ports();
-
-            println!("to_id :{}, ports: {:?}", to_id, ports);
-
-            if ports.out_type == OutputType::Combinatorial {
-                let to_node = id_node.get(to_id).unwrap();
-                let (_, ports) = c.get_id_ports();
-                for in_port in &ports.inputs {
-                    let from_id = &in_port.id;
-
-                    let from_node = id_node.get(from_id).unwrap();
-                    graph.add_edge(*from_node, *to_node, ());
-                    println!(
-                        "add_edge {}:{:?} -> {}:{:?}",
-                        from_id, from_node, to_id, to_node
-                    );
+        // check that all inputs refer to existing outputs, of compatible width
+        for id in &ids {
+            let ports = &id_ports[id];
+            for (i, (input, expected)) in ports.inputs.iter().zip(&ports.input_widths).enumerate() {
+                let port = ports.input_names[i].clone();
+                match id_nr_outputs.get(&input.id) {
+                    None => {
+                        return Err(SimulatorError::UnknownSource {
+                            component: id.clone(),
+                            port,
+                            input: input.clone(),
+                        })
+                    }
+                    Some(nr_outputs) if input.index >= *nr_outputs => {
+                        return Err(SimulatorError::PortIndexOutOfRange {
+                            component: id.clone(),
+                            port,
+                            input: input.clone(),
+                            nr_outputs: *nr_outputs,
+                        })
+                    }
+                    _ => {}
+                }
+                let bit_width = signal_widths[id_start_index[&input.id] + input.index];
+                if bit_width > *expected {
+                    return Err(SimulatorError::WidthMismatch {
+                        component: id.clone(),
+                        port,
+                        output: output_name(&id_ports, input),
+                        bit_width,
+                        expected: *expected,
+                    });
                 }
             }
         }
 
         // topological order
-        let top = toposort(&graph, None).unwrap();
-        println!("--- top \n{:?}", top);
+        let top = toposort(&graph, None).map_err(|_| SimulatorError::CombinationalLoop {
+            cycles: combinational_loops(&graph, &id_ports),
+        })?;
+        debug!("--- top \n{:?}", top);
 
-        let mut ordered_components = vec![];
+        // sequential components are placed first (they have no incoming edges),
+        // followed by the combinatorial components in topological order
+        let mut sequential_components = vec![];
+        let mut combinatorial_components = vec![];
         for node in &top {
-            // #[allow(suspicious_double_ref_op)] // changed in nightly
-            #[allow(clippy::clone_double_ref)]
-            let c = (**node_comp.get(node).unwrap()).clone();
-            ordered_components.push(c);
+            let i = id_component[&graph[*node]];
+            if id_ports[&ids[i]].out_type == OutputType::Sequential {
+                sequential_components.push(i);
+            } else {
+                combinatorial_components.push(i);
+            }
+        }
+        let nr_sequential = sequential_components.len();
+        let mut order = sequential_components;
+        order.append(&mut combinatorial_components);
+
+        let ordered_components: Components = order.iter().map(|i| components[*i].clone()).collect();
+        let component_ids: Vec<String> = order.iter().map(|i| ids[*i].clone()).collect();
+        let scopes: Vec<String> = order.iter().map(|i| scopes[*i].clone()).collect();
+
+        let initial_state: Vec<_> = ordered_components.iter().map(|c| c.snapshot()).collect();
+
+        // position and size of the components shown, computed once for highlighting
+        let component_bounds = ordered_components
+            .iter()
+            .zip(&scopes)
+            .map(|(c, scope)| match scope.is_empty() {
+                true => bounds(&serde_json::to_value(&**c).ok()?),
+                false => None,
+            })
+            .collect();
+
+        let output_ranges = component_ids
+            .iter()
+            .map(|id| id_start_index[id]..id_start_index[id] + id_nr_outputs[id])
+            .collect();
+
+        // combinatorial components reading the outputs of each component, by evaluation order
+        let mut fanout = vec![vec![]; order.len()];
+        let position: HashMap<&String, usize> = component_ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id, i))
+            .collect();
+        for edge in graph.edge_references() {
+            let from = position[&graph[edge.source()]];
+            let to = position[&graph[edge.target()]];
+            if !fanout[from].contains(&to) {
+                fanout[from].push(to);
+            }
         }
 
-        let component_ids: Vec<String> = ordered_components
+        // combinatorial components sampling inputs on the clock edge
+        let clocked = component_ids
             .iter()
-            .map(|c| c.get_id_ports().0)
+            .enumerate()
+            .filter(|(_, id)| !id_ports[*id].clocked_inputs.is_empty())
+            .map(|(i, _)| i)
             .collect();
 
         let mut simulator = Simulator {
             id_start_index,
             ordered_components,
+            nr_sequential,
             sim_state: lens_values,
+            signal_widths,
+            four_state: false,
             id_nr_outputs,
-            history: vec![],
+            cycle: 0,
+            history: History::default(),
+            stimulus: Vectors::default(),
+            initial_state,
             component_ids,
+            scopes,
+            bounds: component_bounds,
+            scope: None,
+            id_ports,
+            debug: false,
+            undeclared_inputs: RefCell::new(vec![]),
+            event_driven: false,
+            settled: None,
+            fanout,
+            clocked,
+            output_ranges,
+            forced: BTreeMap::new(),
+            observers: vec![],
+            activity: None,
+            nr_evaluations: 0,
             graph,
         };
 
-        println!("sim_state {:?}", simulator.sim_state);
+        debug!("sim_state {:?}", simulator.sim_state);
 
-        simulator.clock(clock);
-        simulator
+        simulator.reset(clock);
+        Ok(simulator)
     }
 
-    /// get input by index
+    // id in the scope of the component being evaluated (if any)
+    fn scoped_id<'a>(&self, id: &'a str) -> Cow<'a, str> {
+        match self.scope {
+            Some(i) if !self.scopes[i].is_empty() => {
+                Cow::Owned(format!("{}{}", self.scopes[i], id))
+            }
+            _ => Cow::Borrowed(id),
+        }
+    }
+
+    /// get input by index, unknown (X) and high-impedance (Z) values read as 0
     pub fn get(&self, index: usize) -> Signal {
-        self.sim_state[index]
+        self.get_value(index).data().unwrap_or(0)
     }
 
-    /// get input value
+    /// get input value, unknown (X) and high-impedance (Z) values read as 0
     pub fn get_input_val(&self, input: &Input) -> Signal {
-        let nr_out = *self.id_nr_outputs.get(&input.id).unwrap();
+        self.get_input_value(input).data().unwrap_or(0)
+    }
+
+    /// get input by index, as a four-state value
+    pub fn get_value(&self, index: usize) -> SignalValue {
+        self.sim_state[index]
+    }
+
+    /// get input value, as a four-state value
+    pub fn get_input_value(&self, input: &Input) -> SignalValue {
+        let id = self.scoped_id(&input.id);
+        if self.debug {
+            self.check_declared(&id, input.index);
+        }
+        let nr_out = *self.id_nr_outputs.get(id.as_ref()).unwrap();
         if input.index < nr_out {
-            let start_index = *self.id_start_index.get(&input.id).unwrap();
-            self.get(start_index + input.index)
+            let start_index = *self.id_start_index.get(id.as_ref()).unwrap();
+            self.get_value(start_index + input.index)
         } else {
             panic!(
                 "Attempt to read {} at index {}, where {} has only {} outputs.",
@@ -143,22 +306,71 @@ impl Simulator {
         }
     }
 
+    // report a read of an input not declared by the component being evaluated (if any)
+    fn check_declared(&self, id: &str, index: usize) {
+        if let Some(i) = self.scope {
+            let component = &self.component_ids[i];
+            let declared = self.id_ports[component]
+                .inputs
+                .iter()
+                .any(|input| input.id == id && input.index == index);
+            if !declared {
+                let err = SimulatorError::UndeclaredInput {
+                    component: component.clone(),
+                    input: Input::new(id, index),
+                };
+                let mut undeclared_inputs = self.undeclared_inputs.borrow_mut();
+                if !undeclared_inputs.contains(&err) {
+                    debug!("{}", err);
+                    undeclared_inputs.push(err);
+                }
+            }
+        }
+    }
+
     /// get start index by id
     pub fn get_id_start_index(&self, id: &str) -> usize {
-        *self.id_start_index.get(id).unwrap()
+        *self
+            .id_start_index
+            .get(self.scoped_id(id).as_ref())
+            .unwrap()
+    }
+
+    /// get bit width by index
+    pub fn get_width(&self, index: usize) -> u32 {
+        self.signal_widths[index]
+    }
+
+    /// get bit width of input
+    pub fn get_input_width(&self, input: &Input) -> u32 {
+        self.get_width(self.get_id_start_index(&input.id) + input.index)
     }
 
-    /// set value by index
+    /// set value by index, masked to the bit width of the signal
     pub fn set(&mut self, index: usize, value: Signal) {
-        self.sim_state[index] = value;
+        self.set_value(index, SignalValue::Data(value));
     }
 
     /// set value by id and offset (index)
     pub fn set_id_index(&mut self, id: &str, index: usize, value: Signal) {
-        let nr_out = *self.id_nr_outputs.get(id).unwrap();
+        self.set_id_index_value(id, index, SignalValue::Data(value));
+    }
+
+    /// set four-state value by index, data is masked to the bit width of the signal
+    pub fn set_value(&mut self, index: usize, value: SignalValue) {
+        self.sim_state[index] = match value {
+            SignalValue::Data(value) => SignalValue::Data(value & mask(self.signal_widths[index])),
+            _ => value,
+        };
+    }
+
+    /// set four-state value by id and offset (index)
+    pub fn set_id_index_value(&mut self, id: &str, index: usize, value: SignalValue) {
+        let scoped_id = self.scoped_id(id);
+        let nr_out = *self.id_nr_outputs.get(scoped_id.as_ref()).unwrap();
         if index < nr_out {
-            let start_index = self.get_id_start_index(id);
-            self.set(start_index + index, value);
+            let start_index = *self.id_start_index.get(scoped_id.as_ref()).unwrap();
+            self.set_value(start_index + index, value);
         } else {
             panic!(
                 "Attempt to write to {} at index {}, where {} has only {} outputs.",
@@ -169,46 +381,413 @@ impl Simulator {
 
     /// iterate over the evaluators and increase clock by one
     pub fn clock(&mut self, clock: &mut usize) {
-        // push current state
-        self.history.push(self.sim_state.clone());
-        let ordered_components = self.ordered_components.clone();
+        self.notify(|observer| observer.before_clock(self));
+        // current state
+        let state = self.sim_state.clone();
+        self.apply_stimulus(self.cycle + 1);
+        // taken during evaluation, components access the simulator only through its state
+        let ordered_components = std::mem::take(&mut self.ordered_components);
+        let (sequential, combinatorial) = ordered_components.split_at(self.nr_sequential);
 
-        for component in ordered_components {
+        // clock edge, each sequential component samples its inputs from the current state,
+        // except on the reset clock (cycle 0), where the outputs set on reset are kept
+        let mut next = vec![];
+        let edge = if self.cycle > 0 { sequential } else { &[] };
+        for (i, component) in edge.iter().enumerate() {
+            self.scope = Some(i);
             component.evaluate(self);
+            self.nr_evaluations += 1;
+            self.scope = None;
+            let outputs = self.output_ranges[i].clone();
+            self.apply_forced(outputs.clone());
+            self.notify(|observer| observer.evaluated(self, &self.component_ids[i]));
+            next.push((outputs.start, self.sim_state[outputs.clone()].to_vec()));
+            // restore outputs, such that later sequential components sample the current state
+            self.sim_state[outputs.clone()].copy_from_slice(&state[outputs]);
         }
-        *clock = self.history.len();
+
+        // combinatorial components sample their clocked inputs from the current state
+        if self.cycle > 0 {
+            for k in 0..self.clocked.len() {
+                let i = self.clocked[k];
+                self.scope = Some(i);
+                ordered_components[i].clock_edge(self);
+                self.scope = None;
+            }
+        }
+
+        // commit all sequential outputs atomically
+        for (start, values) in next {
+            self.sim_state[start..start + values.len()].copy_from_slice(&values);
+        }
+        // forced outputs override outputs set on reset, by stimulus or by the user
+        self.apply_forced(0..self.sim_state.len());
+
+        // components to evaluate, in event-driven mode only those depending on changed outputs
+        // (changed since the last propagation, by the clock edge, stimulus or set by the user)
+        let mut evaluated = vec![true; ordered_components.len()];
+        if let (true, Some(settled)) = (self.event_driven, &self.settled) {
+            evaluated[self.nr_sequential..].fill(false);
+            for i in 0..ordered_components.len() {
+                let outputs = self.output_ranges[i].clone();
+                if self.sim_state[outputs.clone()] != settled[outputs] {
+                    // outputs set on a combinatorial component are re-evaluated, as in full mode
+                    evaluated[i] = true;
+                    self.fanout[i].iter().for_each(|j| evaluated[*j] = true);
+                }
+            }
+            // the internal state of components sampling on the clock edge may have changed
+            self.clocked.iter().for_each(|i| evaluated[*i] = true);
+        }
+
+        // propagate through combinatorial components
+        for (i, component) in combinatorial.iter().enumerate() {
+            let i = self.nr_sequential + i;
+            if !evaluated[i] {
+                continue;
+            }
+            let outputs = self.output_ranges[i].clone();
+            let previous = self
+                .event_driven
+                .then(|| self.sim_state[outputs.clone()].to_vec());
+            self.scope = Some(i);
+            component.evaluate(self);
+            self.nr_evaluations += 1;
+            self.scope = None;
+            self.apply_forced(outputs.clone());
+            self.notify(|observer| observer.evaluated(self, &self.component_ids[i]));
+            if previous.is_some_and(|previous| self.sim_state[outputs] != previous[..]) {
+                self.fanout[i].iter().for_each(|j| evaluated[*j] = true);
+            }
+        }
+        self.settled = self.event_driven.then(|| self.sim_state.clone());
+
+        // keep the changes to the internal state of components by this clock, for un_clock
+        let changed = ordered_components
+            .iter()
+            .enumerate()
+            .filter_map(|(i, component)| component.undo_log().map(|log| (i, log)))
+            .collect();
+        if self.activity.is_some() {
+            self.track_activity(&state, &ordered_components);
+        }
+        self.ordered_components = ordered_components;
+        let changes = (!self.observers.is_empty()).then(|| self.changes(&state));
+        self.history
+            .push(self.cycle, state, &self.sim_state, changed);
+        self.cycle += 1;
+        if let Some(changes) = changes {
+            self.notify(|observer| observer.after_clock(self));
+            self.notify_changes(&changes);
+        }
+
+        *clock = self.cycle;
     }
 
-    /// reverse simulation using history if clock > 1
+    /// reverse simulation using history if clock > 1 (and the previous cycle is kept)
     pub fn un_clock(&mut self, clock: &mut usize) {
-        if *clock > 1 {
-            let state = self.history.pop().unwrap();
-            // set old state
-            self.sim_state = state;
+        if self.cycle > 1 {
+            if let Some(entry) = self.history.pop(&self.sim_state) {
+                let state = (!self.observers.is_empty()).then(|| self.sim_state.clone());
+                // set old state
+                for (index, value) in entry.delta {
+                    self.sim_state[index] = value;
+                }
+
+                // undo the changes to the internal state of components by the clock
+                for (i, log) in entry.components {
+                    self.ordered_components[i].undo(&log);
+                }
+                self.cycle -= 1;
+                self.settled = None;
+                if let Some(state) = state {
+                    self.notify(|observer| observer.un_clock(self));
+                    self.notify_changes(&self.changes(&state));
+                }
+            }
+        }
+        *clock = self.cycle;
+    }
+
+    /// set the maximum number of clock cycles kept in history
+    pub fn set_history_depth(&mut self, max_depth: usize) {
+        self.history.set_max_depth(max_depth);
+    }
+
+    /// get value by index at a past clock cycle,
+    /// None if the cycle is not kept in history
+    pub fn get_value_at(&self, cycle: usize, index: usize) -> Option<SignalValue> {
+        self.history.value_at(cycle, index, &self.sim_state)
+    }
+
+    /// get input value at a past clock cycle,
+    /// None if the cycle is not kept in history
+    pub fn get_input_value_at(&self, cycle: usize, input: &Input) -> Option<SignalValue> {
+        let nr_out = *self
+            .id_nr_outputs
+            .get(self.scoped_id(&input.id).as_ref())
+            .unwrap();
+        if input.index < nr_out {
+            self.get_value_at(cycle, self.get_id_start_index(&input.id) + input.index)
+        } else {
+            panic!(
+                "Attempt to read {} at index {}, where {} has only {} outputs.",
+                input.id, input.index, input.id, nr_out
+            )
         }
-        *clock = self.history.len();
     }
 
-    /// reset simulator
+    /// reset simulator and components to their power-on state (see `Component::reset`),
+    /// in four-state mode all signals are reset to unknown (X) unless set by their component
     pub fn reset(&mut self, clock: &mut usize) {
-        self.history = vec![];
-        self.sim_state.iter_mut().for_each(|val| *val = 0);
+        let value = if self.four_state {
+            SignalValue::Unknown
+        } else {
+            SignalValue::Data(0)
+        };
+        // the reset clock is not observed
+        let observers = std::mem::take(&mut self.observers);
+        let state = (!observers.is_empty()).then(|| self.sim_state.clone());
+        self.cycle = 0;
+        self.history.clear(0);
+        self.sim_state.iter_mut().for_each(|val| *val = value);
+        self.settled = None;
+
+        let ordered_components = std::mem::take(&mut self.ordered_components);
+        let initial_state = std::mem::take(&mut self.initial_state);
+        for (i, component) in ordered_components.iter().enumerate() {
+            self.scope = Some(i);
+            component.reset(self, initial_state[i].as_ref());
+        }
+        self.scope = None;
+        self.ordered_components = ordered_components;
+        self.initial_state = initial_state;
+
+        self.apply_stimulus(0);
         self.clock(clock);
+        // activity is tracked from reset
+        if self.activity.is_some() {
+            self.set_activity(true);
+        }
+
+        self.observers = observers;
+        if let Some(state) = state {
+            self.notify(|observer| observer.reset(self));
+            self.notify_changes(&self.changes(&state));
+        }
+    }
+
+    /// enable or disable four-state mode, and reset the simulator
+    pub fn set_four_state(&mut self, four_state: bool, clock: &mut usize) {
+        self.four_state = four_state;
+        self.reset(clock);
+    }
+
+    /// enable or disable event-driven evaluation, where only the combinatorial components
+    /// depending on changed outputs are evaluated on each clock, all components are evaluated
+    /// on the next clock
+    pub fn set_event_driven(&mut self, event_driven: bool) {
+        self.event_driven = event_driven;
+        self.settled = None;
+    }
+
+    /// enable or disable debug mode, where inputs read by a component without being declared
+    /// in its `Ports` (and thus possibly evaluated before their source) are reported
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+    }
+
+    /// undeclared inputs read in debug mode, each reported once
+    pub fn undeclared_inputs(&self) -> Vec<SimulatorError> {
+        self.undeclared_inputs.borrow().clone()
     }
 
     /// save as `dot` file with `.gv` extension
     pub fn save_dot(&self, path: &PathBuf) {
-        let mut path = path.to_owned();
-        path.set_extension("gv");
-        let mut file = File::create(path).unwrap();
-        let dot_string = format!(
-            "{:?}",
-            Dot::with_config(&self.graph, &[Config::EdgeNoLabel])
+        save_dot(
+            &self.graph,
+            &self.id_ports,
+            &self.timing().critical_ids(),
+            path,
         );
-        file.write_all(dot_string.as_bytes()).unwrap();
     }
 }
 
+// (id, ports) of a component in scope, the scope is prefixed to the id and inputs
+pub(crate) fn scoped_id_ports(scope: &str, (id, mut ports): (String, Ports)) -> (String, Ports) {
+    if scope.is_empty() {
+        return (id, ports);
+    }
+    for input in &mut ports.inputs {
+        input.id = format!("{}{}", scope, input.id);
+    }
+    (format!("{}{}", scope, id), ports)
+}
+
+/// dependency graph, with a node for each component (id),
+/// and an edge for each input of a combinatorial component
+pub fn dependency_graph(id_ports: &[(String, Ports)]) -> Graph<String, Input> {
+    let mut graph = Graph::new();
+    let mut id_node = HashMap::new();
+
+    // insert nodes
+    for (id, _) in id_ports {
+        id_node
+            .entry(id.clone())
+            .or_insert_with(|| graph.add_node(id.clone()));
+    }
+
+    // insert edges
+    for (to_id, ports) in id_ports {
+        if ports.out_type == OutputType::Combinatorial {
+            let to_node = id_node[to_id];
+            // inputs sampled on the clock edge are not evaluation dependencies
+            for (_, input) in ports
+                .inputs
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !ports.clocked_inputs.contains(i))
+            {
+                // inputs from unknown components are reported by `Simulator::try_new`
+                if let Some(from_node) = id_node.get(&input.id) {
+                    graph.add_edge(*from_node, to_node, input.clone());
+                }
+            }
+        }
+    }
+    graph
+}
+
+/// name of the output read by the input, as `id.name` (`id.index` if not known)
+pub fn output_name(id_ports: &IdPorts, input: &Input) -> String {
+    match id_ports
+        .get(&input.id)
+        .and_then(|ports| ports.output_names.get(input.index))
+    {
+        Some(name) => format!("{}.{}", input.id, name),
+        None => format!("{}.{}", input.id, input.index),
+    }
+}
+
+// name of the (first) input of the component reading the output, as `id.name`
+fn input_name(id_ports: &IdPorts, id: &str, input: &Input) -> String {
+    let name = id_ports.get(id).and_then(|ports| {
+        let i = ports.inputs.iter().position(|i| i == input)?;
+        ports.input_names.get(i)
+    });
+    match name {
+        Some(name) => format!("{}.{}", id, name),
+        None => id.to_string(),
+    }
+}
+
+/// combinatorial loops in the dependency graph, one for each strongly connected component
+pub fn combinational_loops(graph: &Graph<String, Input>, id_ports: &IdPorts) -> Vec<Cycle> {
+    let mut cycles: Vec<Cycle> = tarjan_scc(graph)
+        .into_iter()
+        .filter(|scc| scc.len() > 1 || graph.contains_edge(scc[0], scc[0]))
+        .map(|scc| {
+            // order components by following the dependencies,
+            // starting from the (alphabetically) first component
+            let mut ids: Vec<String> = vec![];
+            let mut stack = vec![*scc.iter().min_by_key(|node| &graph[**node]).unwrap()];
+            while let Some(node) = stack.pop() {
+                if ids.contains(&graph[node]) {
+                    continue;
+                }
+                ids.push(graph[node].clone());
+                let mut next: Vec<_> = graph
+                    .neighbors(node)
+                    .filter(|node| scc.contains(node))
+                    .collect();
+                // pushed in reverse, such that the first component is visited first
+                next.sort_by(|a, b| graph[*b].cmp(&graph[*a]));
+                stack.append(&mut next);
+            }
+
+            let position = |id: &String| ids.iter().position(|i| i == id).unwrap();
+            let mut inputs: Vec<(String, Input)> = graph
+                .edge_references()
+                .filter(|e| scc.contains(&e.source()) && scc.contains(&e.target()))
+                .map(|e| (graph[e.target()].clone(), e.weight().clone()))
+                .collect();
+            inputs.sort_by_key(|(id, input)| (position(id), position(&input.id), input.index));
+            inputs.dedup();
+
+            let names = inputs
+                .iter()
+                .map(|(id, input)| {
+                    (
+                        input_name(id_ports, id, input),
+                        output_name(id_ports, input),
+                    )
+                })
+                .collect();
+
+            Cycle { ids, inputs, names }
+        })
+        .collect();
+    cycles.sort_by(|a, b| a.ids.cmp(&b.ids));
+    cycles
+}
+
+/// `dot` representation of the dependency graph, edges are labeled by output name,
+/// components and edges part of combinatorial loops are coloured red
+pub fn dot_string(graph: &Graph<String, Input>, id_ports: &IdPorts, critical: &[String]) -> String {
+    let cycles = combinational_loops(graph, id_ports);
+    let edge_attributes = |g: &Graph<String, Input>, e: petgraph::graph::EdgeReference<Input>| {
+        let label = format!("label = {:?}", output_name(id_ports, e.weight()));
+        if cycles.iter().any(|c| {
+            c.inputs
+                .iter()
+                .any(|(id, input)| *id == g[e.target()] && input == e.weight())
+        }) {
+            format!("color = red {}", label)
+        } else if critical
+            .windows(2)
+            .any(|w| w[0] == g[e.source()] && w[1] == g[e.target()])
+        {
+            format!("color = blue penwidth = 2 {}", label)
+        } else {
+            label
+        }
+    };
+    let node_attributes = |_: &Graph<String, Input>, (_, id): (_, &String)| {
+        if cycles.iter().any(|c| c.ids.contains(id)) {
+            "color = red".to_string()
+        } else if timing::on_critical(critical, id) {
+            "color = blue penwidth = 2".to_string()
+        } else {
+            String::new()
+        }
+    };
+
+    format!(
+        "{:?}",
+        Dot::with_attr_getters(
+            graph,
+            &[Config::EdgeNoLabel],
+            &edge_attributes,
+            &node_attributes
+        )
+    )
+}
+
+/// save the dependency graph as `dot` file with `.gv` extension,
+/// combinatorial loops are coloured red and the critical path (component ids) blue
+pub fn save_dot(
+    graph: &Graph<String, Input>,
+    id_ports: &IdPorts,
+    critical: &[String],
+    path: &PathBuf,
+) {
+    let mut path = path.to_owned();
+    path.set_extension("gv");
+    let mut file = File::create(path).unwrap();
+    file.write_all(dot_string(graph, id_ports, critical).as_bytes())
+        .unwrap();
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -240,6 +819,266 @@ mod test {
         assert_eq!(clock, 1);
     }
 
+    #[test]
+    fn test_try_new_redefined() {
+        let cs = ComponentStore {
+            store: vec![Rc::new(ProbeOut::new("po1")), Rc::new(ProbeOut::new("po1"))],
+        };
+
+        let mut clock = 0;
+        let err = Simulator::try_new(&cs, &mut clock).err();
+
+        assert_eq!(err, Some(SimulatorError::DuplicateId("po1".to_string())));
+    }
+
+    #[test]
+    fn test_try_new_unknown_source() {
+        let cs = ComponentStore {
+            store: vec![Rc::new(Probe {
+                id: "p".to_string(),
+                pos: (0.0, 0.0),
+                input: Input::new("po1", 0),
+            })],
+        };
+
+        let mut clock = 0;
+        let err = Simulator::try_new(&cs, &mut clock).err();
+
+        assert_eq!(
+            err,
+            Some(SimulatorError::UnknownSource {
+                component: "p".to_string(),
+                port: "input".to_string(),
+                input: Input::new("po1", 0),
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_new_port_out_of_range() {
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(ProbeOut::new("po1")),
+                Rc::new(Register {
+                    id: "r".to_string(),
+                    pos: (0.0, 0.0),
+                    r_in: Input::new("po1", 1),
+                    bit_width: 32,
+                    reset_value: None,
+                    delay: None,
+                }),
+            ],
+        };
+
+        let mut clock = 0;
+        let err = Simulator::try_new(&cs, &mut clock).err();
+
+        assert_eq!(
+            err,
+            Some(SimulatorError::PortIndexOutOfRange {
+                component: "r".to_string(),
+                port: "r_in".to_string(),
+                input: Input::new("po1", 1),
+                nr_outputs: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_new_width_mismatch() {
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(ProbeOut::new("po1")),
+                Rc::new(Register {
+                    id: "r".to_string(),
+                    pos: (0.0, 0.0),
+                    r_in: Input::new("po1", 0),
+                    bit_width: 5,
+                    reset_value: None,
+                    delay: None,
+                }),
+            ],
+        };
+
+        let mut clock = 0;
+        let err = Simulator::try_new(&cs, &mut clock).err();
+
+        assert_eq!(
+            err,
+            Some(SimulatorError::WidthMismatch {
+                component: "r".to_string(),
+                port: "r_in".to_string(),
+                output: "po1.out".to_string(),
+                bit_width: 32,
+                expected: 5,
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_new_invalid_width() {
+        for bit_width in [0, 129] {
+            let cs = ComponentStore {
+                store: vec![
+                    Rc::new(ProbeOut::new("po1")),
+                    Rc::new(Add {
+                        id: "add".to_string(),
+                        pos: (0.0, 0.0),
+                        a_in: Input::new("po1", 0),
+                        b_in: Input::new("po1", 0),
+                        bit_width,
+                        delay: None,
+                    }),
+                ],
+            };
+
+            let mut clock = 0;
+            let err = Simulator::try_new(&cs, &mut clock).err();
+
+            assert_eq!(
+                err,
+                Some(SimulatorError::InvalidWidth {
+                    component: "add".to_string(),
+                    port: "a_in".to_string(),
+                    bit_width,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_masked() {
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(ProbeOut {
+                    id: "po1".to_string(),
+                    bit_width: 5,
+                }),
+                Rc::new(Register {
+                    id: "r".to_string(),
+                    pos: (0.0, 0.0),
+                    r_in: Input::new("po1", 0),
+                    bit_width: 8,
+                    reset_value: None,
+                    delay: None,
+                }),
+            ],
+        };
+
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+
+        simulator.set_id_index("po1", 0, 0xff);
+        assert_eq!(simulator.get_input_val(&Input::new("po1", 0)), 0x1f);
+        assert_eq!(simulator.get_input_width(&Input::new("r", 0)), 8);
+
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_input_val(&Input::new("r", 0)), 0x1f);
+    }
+
+    #[test]
+    fn test_try_new_combinational_loop() {
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(Constant {
+                    id: "c".to_string(),
+                    pos: (0.0, 0.0),
+                    value: 1,
+                    bit_width: 32,
+                }),
+                // loop through mux and add
+                Rc::new(Mux {
+                    id: "mux".to_string(),
+                    pos: (0.0, 0.0),
+                    select: Input::new("c", 0),
+                    m_in: vec![Input::new("c", 0), Input::new("add", 0)],
+                    bit_width: 32,
+                    delay: None,
+                }),
+                Rc::new(Add {
+                    id: "add".to_string(),
+                    pos: (0.0, 0.0),
+                    a_in: Input::new("c", 0),
+                    b_in: Input::new("mux", 0),
+                    bit_width: 32,
+                    delay: None,
+                }),
+                // loop from add to itself
+                Rc::new(Add {
+                    id: "acc".to_string(),
+                    pos: (0.0, 0.0),
+                    a_in: Input::new("c", 0),
+                    b_in: Input::new("acc", 0),
+                    bit_width: 32,
+                    delay: None,
+                }),
+                // register breaks the loop
+                Rc::new(Register {
+                    id: "reg".to_string(),
+                    pos: (0.0, 0.0),
+                    r_in: Input::new("add2", 0),
+                    bit_width: 32,
+                    reset_value: None,
+                    delay: None,
+                }),
+                Rc::new(Add {
+                    id: "add2".to_string(),
+                    pos: (0.0, 0.0),
+                    a_in: Input::new("c", 0),
+                    b_in: Input::new("reg", 0),
+                    bit_width: 32,
+                    delay: None,
+                }),
+            ],
+        };
+
+        let mut clock = 0;
+        let err = Simulator::try_new(&cs, &mut clock).err();
+
+        assert_eq!(
+            err,
+            Some(SimulatorError::CombinationalLoop {
+                cycles: vec![
+                    Cycle {
+                        ids: vec!["acc".to_string()],
+                        inputs: vec![("acc".to_string(), Input::new("acc", 0))],
+                        names: vec![("acc.b_in".to_string(), "acc.sum".to_string())],
+                    },
+                    Cycle {
+                        ids: vec!["add".to_string(), "mux".to_string()],
+                        inputs: vec![
+                            ("add".to_string(), Input::new("mux", 0)),
+                            ("mux".to_string(), Input::new("add", 0)),
+                        ],
+                        names: vec![
+                            ("add.b_in".to_string(), "mux.out".to_string()),
+                            ("mux.in1".to_string(), "add.sum".to_string()),
+                        ],
+                    },
+                ],
+            })
+        );
+
+        // only the loop edges are coloured, edges are labeled by output name
+        let dot = dot_string(&cs.dependency_graph(), &cs.id_ports(), &[]);
+        assert_eq!(
+            dot.matches("-> 1 [ color = red label = \"add.sum\"]")
+                .count(),
+            1
+        ); // add -> mux
+        assert_eq!(
+            dot.matches("-> 2 [ color = red label = \"mux.out\"]")
+                .count(),
+            1
+        ); // mux -> add
+        assert_eq!(
+            dot.matches("-> 3 [ color = red label = \"acc.sum\"]")
+                .count(),
+            1
+        ); // acc -> acc
+        assert_eq!(dot.matches("[ color = red").count(), 3);
+    }
+
     #[test]
     fn test_set_id_out() {
         let cs = ComponentStore {
@@ -253,6 +1092,30 @@ mod test {
         simulator.set_id_index("po1", 0, 7);
     }
 
+    #[test]
+    fn test_set_between_clocks() {
+        let cs = ComponentStore {
+            store: vec![Rc::new(ProbeOut::new("po1"))],
+        };
+
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        let index = simulator.get_id_start_index("po1");
+
+        // a value set between clocks is kept in history
+        simulator.clock(&mut clock);
+        simulator.set_id_index("po1", 0, 7);
+        simulator.clock(&mut clock);
+        assert_eq!(simulator.get_value_at(1, index), Some(SignalValue::Data(0)));
+        assert_eq!(simulator.get_value_at(2, index), Some(SignalValue::Data(7)));
+
+        simulator.un_clock(&mut clock);
+        assert_eq!(simulator.get_input_val(&Input::new("po1", 0)), 7);
+        simulator.un_clock(&mut clock);
+        assert_eq!(clock, 1);
+        assert_eq!(simulator.get_input_val(&Input::new("po1", 0)), 0);
+    }
+
     #[test]
     #[should_panic(expected = "Attempt to write to po1 at index 1, where po1 has only 1 outputs.")]
     fn test_set_id_out_of_range() {
@@ -293,4 +1156,157 @@ mod test {
         assert_eq!(clock, 1);
         let _ = simulator.get_input_val(&Input::new("po1", 1));
     }
+
+    // a faulty component, reading an input without declaring it
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Undeclared {
+        id: String,
+        input: Input,
+    }
+
+    #[typetag::serde]
+    impl Component for Undeclared {
+        fn get_id_ports(&self) -> (String, Ports) {
+            (
+                self.id.clone(),
+                Ports {
+                    inputs: vec![],
+                    input_widths: vec![],
+                    input_names: vec![],
+                    clocked_inputs: vec![],
+                    out_type: OutputType::Combinatorial,
+                    outputs: vec![crate::common::Output::Function],
+                    output_widths: vec![Signal::BITS],
+                    output_names: crate::common::port_names(&["out"]),
+                },
+            )
+        }
+
+        fn evaluate(&self, simulator: &mut Simulator) {
+            let value = simulator.get_input_value(&self.input);
+            simulator.set_id_index_value(&self.id, 0, value);
+        }
+    }
+
+    #[cfg(feature = "gui-vizia")]
+    #[typetag::serde]
+    impl crate::common::ViziaComponent for Undeclared {}
+
+    #[cfg(feature = "gui-egui")]
+    #[typetag::serde]
+    impl crate::common::EguiComponent for Undeclared {}
+
+    #[test]
+    fn test_undeclared_input() {
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(ProbeOut::new("po1")),
+                Rc::new(Undeclared {
+                    id: "faulty".to_string(),
+                    input: Input::new("po1", 0),
+                }),
+            ],
+        };
+
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+
+        // not reported unless in debug mode
+        assert_eq!(simulator.undeclared_inputs(), vec![]);
+
+        simulator.set_debug(true);
+        simulator.clock(&mut clock);
+        simulator.clock(&mut clock);
+
+        // reported once
+        assert_eq!(
+            simulator.undeclared_inputs(),
+            vec![SimulatorError::UndeclaredInput {
+                component: "faulty".to_string(),
+                input: Input::new("po1", 0),
+            }]
+        );
+        assert_eq!(
+            simulator.undeclared_inputs()[0].to_string(),
+            "Component faulty reads po1 at index 0, which is not declared as an input"
+        );
+
+        // reads outside of evaluation (e.g., by the GUI) are not checked
+        let _ = simulator.get_input_value(&Input::new("po1", 0));
+        assert_eq!(simulator.undeclared_inputs().len(), 1);
+    }
+
+    #[test]
+    fn test_event_driven() {
+        // a counter, and an adder of a constant and a probe output
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(Constant {
+                    id: "c".to_string(),
+                    pos: (0.0, 0.0),
+                    value: 1,
+                    bit_width: 8,
+                }),
+                Rc::new(Register {
+                    id: "reg".to_string(),
+                    pos: (0.0, 0.0),
+                    r_in: Input::new("inc", 0),
+                    bit_width: 8,
+                    reset_value: None,
+                    delay: None,
+                }),
+                Rc::new(Add {
+                    id: "inc".to_string(),
+                    pos: (0.0, 0.0),
+                    a_in: Input::new("reg", 0),
+                    b_in: Input::new("c", 0),
+                    bit_width: 8,
+                    delay: None,
+                }),
+                Rc::new(ProbeOut::new("po")),
+                Rc::new(Add {
+                    id: "add".to_string(),
+                    pos: (0.0, 0.0),
+                    a_in: Input::new("po", 0),
+                    b_in: Input::new("c", 0),
+                    bit_width: 32,
+                    delay: None,
+                }),
+            ],
+        };
+        let mut clock = 0;
+        let mut full = Simulator::new(&cs, &mut clock);
+        let mut event_driven = Simulator::new(&cs, &mut clock);
+        event_driven.set_event_driven(true);
+        event_driven.reset(&mut clock);
+        full.reset(&mut clock);
+
+        let evaluations = |simulator: &mut Simulator, clock: &mut usize| {
+            let nr_evaluations = simulator.nr_evaluations;
+            simulator.clock(clock);
+            simulator.nr_evaluations - nr_evaluations
+        };
+        for cycle in 0..6 {
+            if cycle == 3 {
+                full.set_id_index("po", 0, 7);
+                event_driven.set_id_index("po", 0, 7);
+            }
+            assert_eq!(evaluations(&mut full, &mut clock), 5);
+            let nr_evaluations = evaluations(&mut event_driven, &mut clock);
+            assert_eq!(full.sim_state, event_driven.sim_state);
+            // reg and inc on every clock, add when po is changed
+            assert_eq!(nr_evaluations, if cycle == 3 { 4 } else { 2 });
+        }
+        assert_eq!(event_driven.get_input_val(&Input::new("add", 0)), 8);
+
+        // outputs restored by a step back are propagated
+        full.un_clock(&mut clock);
+        event_driven.un_clock(&mut clock);
+        full.set_id_index("po", 0, 2);
+        event_driven.set_id_index("po", 0, 2);
+        full.clock(&mut clock);
+        event_driven.clock(&mut clock);
+        assert_eq!(full.sim_state, event_driven.sim_state);
+        assert_eq!(event_driven.get_input_val(&Input::new("add", 0)), 3);
+    }
 }
@@ -0,0 +1,308 @@
+use crate::common::{
+    resolve_signal, IdPorts, Input, Signal, SignalValue, Simulator, SimulatorError,
+};
+use serde::{de, Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::{fs::File, io::prelude::*, path::PathBuf};
+
+/// Input vectors driving component outputs over time.
+///
+/// The vectors for cycle `c` are applied on the clock into cycle `c` (as if set by
+/// `Simulator::set_id_index` before clocking), vectors for cycle 0 are applied on reset.
+/// Values are kept until changed, thus stimulus is intended for components without
+/// an evaluate function, e.g., `ProbeOut`.
+///
+/// Stimulus files are given either as CSV, with a header naming the signals
+/// (`id.name` or `id.index`, or `id` for index 0) and a row per cycle, empty cells are left
+/// unchanged:
+///
+/// ```text
+/// cycle,po1.out,po2
+/// 1,42,0x539
+/// 3,,X
+/// ```
+///
+/// or as JSON, mapping cycles to signal values:
+///
+/// ```json
+/// { "1": { "po1.out": 42, "po2": "0x539" }, "3": { "po2": "X" } }
+/// ```
+///
+/// Values are given in decimal, hexadecimal (`0x`) or binary (`0b`), or as `X` (unknown)
+/// or `Z` (high-impedance).
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(from = "JsonVectors")]
+pub struct Stimulus {
+    // (signal as named, value) to apply by clock cycle, see `resolve_signal`
+    pub vectors: BTreeMap<usize, Vec<(String, SignalValue)>>,
+}
+
+// input vectors by clock cycle, signals resolved to the outputs of the simulated model
+pub type Vectors = BTreeMap<usize, Vec<(Input, SignalValue)>>;
+
+// signal value, as decimal, hexadecimal (`0x`), binary (`0b`), or X/Z
+pub(crate) fn parse_value(value: &str) -> Option<SignalValue> {
+    let value = value.trim();
+    let data = if let Some(hex) = value.strip_prefix("0x") {
+        Signal::from_str_radix(hex, 16)
+    } else if let Some(bin) = value.strip_prefix("0b") {
+        Signal::from_str_radix(bin, 2)
+    } else {
+        match value {
+            "X" | "x" => return Some(SignalValue::Unknown),
+            "Z" | "z" => return Some(SignalValue::HighZ),
+            _ => value.parse(),
+        }
+    };
+    data.ok().map(SignalValue::Data)
+}
+
+// signal value in JSON, a number or a string (see `parse_value`)
+struct JsonValue(SignalValue);
+
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(u64),
+            String(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Number(n) => Ok(JsonValue(SignalValue::Data(n as Signal))),
+            Raw::String(s) => parse_value(&s)
+                .map(JsonValue)
+                .ok_or_else(|| de::Error::custom(format!("invalid value {}", s))),
+        }
+    }
+}
+
+// vectors in JSON, signal values by cycle
+type JsonVectors = BTreeMap<usize, BTreeMap<String, JsonValue>>;
+
+impl From<JsonVectors> for Stimulus {
+    fn from(json: JsonVectors) -> Self {
+        Stimulus {
+            vectors: json
+                .into_iter()
+                .map(|(cycle, values)| {
+                    let vector = values
+                        .into_iter()
+                        .map(|(signal, value)| (signal, value.0))
+                        .collect();
+                    (cycle, vector)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Stimulus {
+    /// stimulus from CSV, a header naming the signals followed by a row per cycle
+    pub fn parse_csv(source: &str) -> Result<Self, SimulatorError> {
+        let mut stimulus = Stimulus::default();
+        let mut signals: Option<Vec<String>> = None;
+        for (line, text) in source.lines().enumerate() {
+            let text = text.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+            // (column, cell), columns counted from 1
+            let mut col = 1;
+            let mut cells = vec![];
+            for cell in text.split(',') {
+                cells.push((col, cell.trim()));
+                col += cell.len() + 1;
+            }
+            let error = |col: usize, msg: String| SimulatorError::Parse {
+                line: line + 1,
+                col,
+                msg,
+            };
+
+            match &signals {
+                None => {
+                    signals = Some(cells[1..].iter().map(|(_, s)| s.to_string()).collect());
+                }
+                Some(signals) => {
+                    if cells.len() > signals.len() + 1 {
+                        return Err(error(
+                            cells[signals.len() + 1].0,
+                            format!("expected {} values", signals.len()),
+                        ));
+                    }
+                    let (col, cycle) = cells[0];
+                    let cycle = cycle
+                        .parse()
+                        .map_err(|_| error(col, format!("invalid cycle {}", cycle)))?;
+                    let vector = stimulus.vectors.entry(cycle).or_default();
+                    for ((col, cell), signal) in cells[1..].iter().zip(signals) {
+                        if !cell.is_empty() {
+                            let value = parse_value(cell)
+                                .ok_or_else(|| error(*col, format!("invalid value {}", cell)))?;
+                            vector.push((signal.clone(), value));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(stimulus)
+    }
+
+    /// stimulus from JSON, mapping cycles to signal values
+    pub fn parse_json(source: &str) -> Result<Self, SimulatorError> {
+        serde_json::from_str(source).map_err(|err| SimulatorError::Parse {
+            line: err.line(),
+            col: err.column(),
+            msg: err.to_string(),
+        })
+    }
+
+    /// load stimulus file, CSV if the extension is `.csv`, JSON otherwise
+    pub fn load_file(path: &PathBuf) -> Result<Self, SimulatorError> {
+        let mut file =
+            File::open(path).map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))?;
+        let mut source = String::new();
+        file.read_to_string(&mut source)
+            .map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))?;
+
+        if matches!(path.extension().and_then(|ext| ext.to_str()), Some("csv")) {
+            Stimulus::parse_csv(&source)
+        } else {
+            Stimulus::parse_json(&source)
+        }
+    }
+
+    /// vectors with the signals resolved to outputs of the model,
+    /// returns an error if a signal is not found
+    pub fn resolve(&self, id_ports: &IdPorts) -> Result<Vectors, SimulatorError> {
+        self.vectors
+            .iter()
+            .map(|(cycle, vector)| {
+                let vector = vector
+                    .iter()
+                    .map(|(signal, value)| Ok((resolve_signal(id_ports, signal)?, *value)))
+                    .collect::<Result<_, SimulatorError>>()?;
+                Ok((*cycle, vector))
+            })
+            .collect()
+    }
+}
+
+impl Simulator {
+    /// drive the simulation by stimulus, and reset the simulator,
+    /// returns an error if a signal is not found in the model
+    pub fn set_stimulus(
+        &mut self,
+        stimulus: Stimulus,
+        clock: &mut usize,
+    ) -> Result<(), SimulatorError> {
+        self.stimulus = stimulus.resolve(&self.id_ports)?;
+        self.reset(clock);
+        Ok(())
+    }
+
+    /// drive the simulation by stimulus file, see `Stimulus::load_file`
+    pub fn load_stimulus(
+        &mut self,
+        path: &PathBuf,
+        clock: &mut usize,
+    ) -> Result<(), SimulatorError> {
+        let stimulus = Stimulus::load_file(path)?;
+        self.set_stimulus(stimulus, clock)
+    }
+
+    // apply the stimulus vectors of the cycle
+    pub(crate) fn apply_stimulus(&mut self, cycle: usize) {
+        if let Some(vector) = self.stimulus.get(&cycle) {
+            for (input, value) in vector.clone() {
+                self.set_id_index_value(&input.id, input.index, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("42"), Some(SignalValue::Data(42)));
+        assert_eq!(parse_value("0x2a"), Some(SignalValue::Data(42)));
+        assert_eq!(parse_value("0b101010"), Some(SignalValue::Data(42)));
+        assert_eq!(parse_value("X"), Some(SignalValue::Unknown));
+        assert_eq!(parse_value("z"), Some(SignalValue::HighZ));
+        assert_eq!(parse_value("4x"), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        let csv = Stimulus::parse_csv("cycle,po1.0,po2\n1,42,0x539\n\n3,,X\n").unwrap();
+        let json = Stimulus::parse_json(
+            r#"{ "1": { "po1.0": 42, "po2": "0x539" }, "3": { "po2": "X" } }"#,
+        )
+        .unwrap();
+        assert_eq!(csv, json);
+        assert_eq!(
+            csv.vectors[&1],
+            vec![
+                ("po1.0".to_string(), SignalValue::Data(42)),
+                ("po2".to_string(), SignalValue::Data(0x539)),
+            ]
+        );
+        assert_eq!(
+            csv.vectors[&3],
+            vec![("po2".to_string(), SignalValue::Unknown)]
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        use crate::common::ComponentStore;
+        use crate::components::ProbeOut;
+        use std::rc::Rc;
+
+        let cs = ComponentStore {
+            store: vec![Rc::new(ProbeOut::new("po1")), Rc::new(ProbeOut::new("po2"))],
+        };
+        let mut clock = 0;
+        let simulator = Simulator::new(&cs, &mut clock);
+
+        // signals given by name, index or id
+        let stimulus = Stimulus::parse_csv("cycle,po1.out,po2.0,po1\n1,1,2,3\n").unwrap();
+        assert_eq!(
+            stimulus.resolve(&simulator.id_ports).unwrap()[&1],
+            vec![
+                (Input::new("po1", 0), SignalValue::Data(1)),
+                (Input::new("po2", 0), SignalValue::Data(2)),
+                (Input::new("po1", 0), SignalValue::Data(3)),
+            ]
+        );
+
+        let stimulus = Stimulus::parse_json(r#"{ "1": { "po1.in": 1 } }"#).unwrap();
+        assert_eq!(
+            stimulus.resolve(&simulator.id_ports),
+            Err(SimulatorError::UnknownSignal("po1.in".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let col = |source: &str| match Stimulus::parse_csv(source) {
+            Err(SimulatorError::Parse { line: 2, col, .. }) => col,
+            result => panic!("unexpected {:?}", result),
+        };
+        assert_eq!(col("cycle,po1\n1,4x\n"), 3);
+        assert_eq!(col("cycle,po1\none,4\n"), 1);
+        assert_eq!(col("cycle,po1\n1,4,5\n"), 5);
+        assert!(matches!(
+            Stimulus::parse_json("{\n \"1\": { \"po1\": true } }"),
+            Err(SimulatorError::Parse { line: 2, .. })
+        ));
+        assert!(matches!(
+            Stimulus::parse_json("{ \"1\": { \"po1\": \"4x\" } }"),
+            Err(SimulatorError::Parse { line: 1, .. })
+        ));
+    }
+}
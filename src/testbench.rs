@@ -0,0 +1,268 @@
+use crate::common::{fmt_value, SignalValue, Simulator, SimulatorError};
+use crate::simulator::output_name;
+use crate::stimulus::Stimulus;
+use serde::Deserialize;
+use std::fmt;
+use std::{fs::File, io::prelude::*, path::PathBuf};
+
+/// Testbench, stimulus and expected (golden) values of chosen signals by clock cycle.
+///
+/// Given either as JSON:
+///
+/// ```json
+/// {
+///   "stimulus": { "1": { "po1": 42, "po2": 1337 } },
+///   "expect": { "1": { "add.sum": 1379, "add.overflow": 0 } }
+/// }
+/// ```
+///
+/// or as CSV (see `Stimulus`), where columns of expected values are marked by `=`:
+///
+/// ```text
+/// cycle,po1,po2,=add.sum,=add.overflow
+/// 1,42,1337,1379,0
+/// ```
+///
+/// Expected values are compared exactly, an expected `X` only matches an unknown value.
+/// Signals not to be compared at a cycle are left out (or left empty).
+/// A testbench without expected values is rejected, as it would pass without checking anything.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Testbench {
+    #[serde(default)]
+    pub four_state: bool,
+    #[serde(default)]
+    pub stimulus: Stimulus,
+    // expected values by clock cycle
+    #[serde(default)]
+    pub expect: Stimulus,
+}
+
+/// Outcome of running a testbench
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestReport {
+    // last clock cycle simulated
+    pub cycles: usize,
+    // number of signal values compared
+    pub checks: usize,
+    // first mismatching cycle, None if the testbench passed
+    pub mismatch: Option<Mismatch>,
+}
+
+/// Expected and actual values of the signals compared at a cycle
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    pub cycle: usize,
+    pub diff: Vec<SignalDiff>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignalDiff {
+    // output as `id.name`
+    pub signal: String,
+    pub bit_width: u32,
+    pub expected: SignalValue,
+    pub actual: SignalValue,
+}
+
+// diff style, mismatching signals as expected (-) and actual (+) values
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Mismatch at cycle {}", self.cycle)?;
+        for d in &self.diff {
+            if d.expected == d.actual {
+                write!(f, "\n  {} = {}", d.signal, fmt_value(d.actual, d.bit_width))?;
+            } else {
+                write!(
+                    f,
+                    "\n- {} = {}",
+                    d.signal,
+                    fmt_value(d.expected, d.bit_width)
+                )?;
+                write!(f, "\n+ {} = {}", d.signal, fmt_value(d.actual, d.bit_width))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Testbench {
+    /// testbench from CSV, columns of expected values are marked by `=`
+    pub fn parse_csv(source: &str) -> Result<Self, SimulatorError> {
+        let mut testbench = Testbench::default();
+        for (cycle, vector) in Stimulus::parse_csv(source)?.vectors {
+            let (expect, stimulus): (Vec<_>, Vec<_>) = vector
+                .into_iter()
+                .partition(|(signal, _)| signal.starts_with('='));
+            let expect: Vec<_> = expect
+                .into_iter()
+                .map(|(signal, value)| (signal[1..].to_string(), value))
+                .collect();
+            if !stimulus.is_empty() {
+                testbench.stimulus.vectors.insert(cycle, stimulus);
+            }
+            if !expect.is_empty() {
+                testbench.expect.vectors.insert(cycle, expect);
+            }
+        }
+        testbench.check("no expected values, mark their columns by `=`")
+    }
+
+    /// testbench from JSON
+    pub fn parse_json(source: &str) -> Result<Self, SimulatorError> {
+        let testbench: Testbench =
+            serde_json::from_str(source).map_err(|err| SimulatorError::Parse {
+                line: err.line(),
+                col: err.column(),
+                msg: err.to_string(),
+            })?;
+        testbench.check("no expected values, give them by `expect`")
+    }
+
+    // reject a testbench without expected values
+    fn check(self, msg: &str) -> Result<Self, SimulatorError> {
+        if self.expect.vectors.values().all(|vector| vector.is_empty()) {
+            return Err(SimulatorError::Parse {
+                line: 1,
+                col: 1,
+                msg: msg.to_string(),
+            });
+        }
+        Ok(self)
+    }
+
+    /// load testbench file, CSV if the extension is `.csv`, JSON otherwise
+    pub fn load_file(path: &PathBuf) -> Result<Self, SimulatorError> {
+        let mut file =
+            File::open(path).map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))?;
+        let mut source = String::new();
+        file.read_to_string(&mut source)
+            .map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))?;
+
+        if matches!(path.extension().and_then(|ext| ext.to_str()), Some("csv")) {
+            Testbench::parse_csv(&source)
+        } else {
+            Testbench::parse_json(&source)
+        }
+    }
+}
+
+impl Simulator {
+    /// run the testbench from reset, up to the last cycle with expected values,
+    /// stops at the first mismatching cycle, returns an error if a signal is not found in the model
+    pub fn run_testbench(
+        &mut self,
+        testbench: &Testbench,
+        clock: &mut usize,
+    ) -> Result<TestReport, SimulatorError> {
+        let expect = testbench.expect.resolve(&self.id_ports)?;
+        self.four_state = testbench.four_state;
+        self.set_stimulus(testbench.stimulus.clone(), clock)?;
+
+        let mut checks = 0;
+        for (cycle, expected) in &expect {
+            while self.cycle < *cycle {
+                self.clock(clock);
+            }
+            let diff: Vec<SignalDiff> = expected
+                .iter()
+                .map(|(signal, expected)| SignalDiff {
+                    signal: output_name(&self.id_ports, signal),
+                    bit_width: self.get_input_width(signal),
+                    expected: *expected,
+                    actual: self
+                        .get_input_value_at(*cycle, signal)
+                        .unwrap_or(SignalValue::Unknown),
+                })
+                .collect();
+            checks += diff.len();
+            if diff.iter().any(|d| d.expected != d.actual) {
+                return Ok(TestReport {
+                    cycles: self.cycle,
+                    checks,
+                    mismatch: Some(Mismatch {
+                        cycle: *cycle,
+                        diff,
+                    }),
+                });
+            }
+        }
+        Ok(TestReport {
+            cycles: self.cycle,
+            checks,
+            mismatch: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let csv =
+            Testbench::parse_csv("cycle,po1,po2,=add.0,=add.overflow\n1,42,1337,1379,0\n").unwrap();
+        let json = Testbench::parse_json(
+            r#"{
+                "stimulus": { "1": { "po1": 42, "po2": 1337 } },
+                "expect": { "1": { "add.0": 1379, "add.overflow": 0 } }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(csv, json);
+        assert_eq!(
+            csv.expect.vectors[&1],
+            vec![
+                ("add.0".to_string(), SignalValue::Data(1379)),
+                ("add.overflow".to_string(), SignalValue::Data(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        // unknown fields, e.g., misspelled expectations
+        let err = Testbench::parse_json(r#"{ "expected": { "1": { "add.0": 1379 } } }"#);
+        let msg = match err {
+            Err(SimulatorError::Parse { msg, .. }) => msg,
+            err => panic!("unexpected {:?}", err),
+        };
+        assert!(msg.contains("unknown field `expected`"));
+        // no expected values
+        assert_eq!(
+            Testbench::parse_csv("cycle,po1,add.0\n1,42,1379\n"),
+            Err(SimulatorError::Parse {
+                line: 1,
+                col: 1,
+                msg: "no expected values, mark their columns by `=`".to_string()
+            })
+        );
+        assert!(Testbench::parse_json(r#"{ "stimulus": { "1": { "po1": 42 } } }"#).is_err());
+    }
+
+    #[test]
+    fn test_mismatch_fmt() {
+        let mismatch = Mismatch {
+            cycle: 3,
+            diff: vec![
+                SignalDiff {
+                    signal: "add.sum".to_string(),
+                    bit_width: 8,
+                    expected: SignalValue::Data(4),
+                    actual: SignalValue::Unknown,
+                },
+                SignalDiff {
+                    signal: "add.overflow".to_string(),
+                    bit_width: 1,
+                    expected: SignalValue::Data(0),
+                    actual: SignalValue::Data(0),
+                },
+            ],
+        };
+        assert_eq!(
+            mismatch.to_string(),
+            "Mismatch at cycle 3\n- add.sum = 0x04\n+ add.sum = X\n  add.overflow = 0x0"
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use crate::common::{ComponentStore, IdPorts, Input, OutputType, Ports, Simulator, SimulatorError};
+use crate::simulator::{self, scoped_id_ports};
+use crate::validate::Bounds;
+use petgraph::{algo::toposort, graph::NodeIndex, Direction, Graph};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// A combinatorial path, starting at the output of a sequential component
+/// (or of a component without inputs), ending at an input of a sequential component
+/// (or at a component whose outputs are not read).
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimingPath {
+    // end point of the path, as `id.port` for inputs of sequential components, else the id
+    pub end: String,
+    // components along the path (id), with their propagation delay
+    pub steps: Vec<(String, f32)>,
+}
+
+impl TimingPath {
+    /// total propagation delay of the path in ns
+    pub fn delay(&self) -> f32 {
+        self.steps.iter().map(|(_, delay)| delay).sum()
+    }
+}
+
+impl fmt::Display for TimingPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1} ns to {}", self.delay(), self.end)?;
+        let mut arrival = 0.0;
+        for (id, delay) in &self.steps {
+            arrival += delay;
+            write!(f, "\n  {} +{:.1} ns = {:.1} ns", id, delay, arrival)?;
+        }
+        Ok(())
+    }
+}
+
+/// Timing analysis of a model (see `Component::delay`).
+///
+/// The register-to-register paths of the model are found over the dependency graph,
+/// the longest (critical) path bounds the clock period.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Timing {
+    // a path for each end point, the longest (critical) path first
+    pub paths: Vec<TimingPath>,
+}
+
+impl Timing {
+    /// the longest path, None if the model has no paths
+    pub fn critical_path(&self) -> Option<&TimingPath> {
+        self.paths.first()
+    }
+
+    /// minimum clock period in ns
+    pub fn period(&self) -> f32 {
+        self.critical_path().map_or(0.0, |path| path.delay())
+    }
+
+    /// maximum clock frequency in MHz, None if the model has no delays
+    pub fn max_frequency(&self) -> Option<f32> {
+        let period = self.period();
+        (period > 0.0).then(|| 1000.0 / period)
+    }
+
+    /// components (id) on the critical path
+    pub fn critical_ids(&self) -> Vec<String> {
+        self.critical_path()
+            .map(|path| path.steps.iter().map(|(id, _)| id.clone()).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.max_frequency() {
+            Some(frequency) => write!(
+                f,
+                "critical path {:.1} ns, maximum clock frequency {:.1} MHz",
+                self.period(),
+                frequency
+            ),
+            None => write!(f, "no propagation delay"),
+        }
+    }
+}
+
+// component (or subcircuit of components) by id is on the critical path (component ids)
+pub(crate) fn on_critical(critical: &[String], id: &str) -> bool {
+    critical
+        .iter()
+        .any(|c| c == id || c.starts_with(&format!("{}/", id)))
+}
+
+/// timing analysis over the dependency graph, given the ports and the propagation delay
+/// of each component (by id), no paths are found if the graph has combinatorial loops
+pub fn timing(
+    graph: &Graph<String, Input>,
+    id_ports: &IdPorts,
+    delays: &HashMap<String, f32>,
+) -> Timing {
+    let top = match toposort(graph, None) {
+        Ok(top) => top,
+        Err(_) => return Timing::default(),
+    };
+    let nodes: HashMap<&String, NodeIndex> = graph
+        .node_indices()
+        .map(|node| (&graph[node], node))
+        .collect();
+    // inputs sampled on the clock edge, all inputs of sequential components
+    // and the clocked inputs of combinatorial components
+    let sampled = |ports: &Ports| -> Vec<usize> {
+        match ports.out_type {
+            OutputType::Sequential => (0..ports.inputs.len()).collect(),
+            OutputType::Combinatorial => ports.clocked_inputs.clone(),
+        }
+    };
+    // components read on the clock edge
+    let read: HashSet<&String> = id_ports
+        .values()
+        .flat_map(|ports| sampled(ports).into_iter().map(|i| &ports.inputs[i].id))
+        .collect();
+
+    // latest arrival at the outputs of each component, and the component it is reached from,
+    // paths start at sequential components (which have no incoming edges)
+    let mut arrival = vec![0.0; graph.node_count()];
+    let mut from: Vec<Option<NodeIndex>> = vec![None; graph.node_count()];
+    for node in &top {
+        let latest = graph
+            .neighbors_directed(*node, Direction::Incoming)
+            .map(|source| (arrival[source.index()], source))
+            .fold(
+                None,
+                |latest: Option<(f32, NodeIndex)>, (t, source)| match latest {
+                    Some((latest_t, _)) if latest_t >= t => latest,
+                    _ => Some((t, source)),
+                },
+            );
+        arrival[node.index()] = latest.map_or(0.0, |(t, _)| t) + delays[&graph[*node]];
+        from[node.index()] = latest.map(|(_, source)| source);
+    }
+
+    let path = |end: String, mut node: NodeIndex| {
+        let mut steps = vec![];
+        loop {
+            steps.push((graph[node].clone(), delays[&graph[node]]));
+            match from[node.index()] {
+                Some(source) => node = source,
+                None => break,
+            }
+        }
+        steps.reverse();
+        TimingPath { end, steps }
+    };
+
+    // paths end at the inputs sampled on the clock edge,
+    // and at combinatorial components with a delay whose outputs are not read
+    let mut paths = vec![];
+    for node in &top {
+        let (id, ports) = (&graph[*node], &id_ports[&graph[*node]]);
+        for i in sampled(ports) {
+            if let Some(source) = nodes.get(&ports.inputs[i].id) {
+                paths.push(path(format!("{}.{}", id, ports.input_names[i]), *source));
+            }
+        }
+        if ports.out_type == OutputType::Combinatorial
+            && delays[id] > 0.0
+            && !read.contains(id)
+            && graph
+                .neighbors_directed(*node, Direction::Outgoing)
+                .next()
+                .is_none()
+        {
+            paths.push(path(id.clone(), *node));
+        }
+    }
+    // longest first, in order of evaluation otherwise
+    paths.sort_by(|a, b| b.delay().total_cmp(&a.delay()));
+    Timing { paths }
+}
+
+impl Simulator {
+    /// timing analysis of the simulated model (see `Component::delay`)
+    pub fn timing(&self) -> Timing {
+        let delays = self
+            .component_ids
+            .iter()
+            .cloned()
+            .zip(self.ordered_components.iter().map(|c| c.delay()))
+            .collect();
+        timing(&self.graph, &self.id_ports, &delays)
+    }
+
+    /// position (center) and size of the shown components on the critical path, for
+    /// highlighting, subcircuits are highlighted if any of their components are on the path
+    pub fn critical_bounds(&self) -> Vec<Bounds> {
+        let critical = self.timing().critical_ids();
+        self.bounds
+            .iter()
+            .zip(&self.component_ids)
+            .filter(|(_, id)| on_critical(&critical, id))
+            .filter_map(|(bounds, _)| *bounds)
+            .collect()
+    }
+}
+
+impl ComponentStore {
+    /// timing analysis of the model, with subcircuits expanded (see `Component::delay`)
+    pub fn timing(&self) -> Result<Timing, SimulatorError> {
+        let (scopes, components) = self.flatten()?;
+        let id_ports: Vec<_> = scopes
+            .iter()
+            .zip(&components)
+            .map(|(scope, c)| scoped_id_ports(scope, c.get_id_ports()))
+            .collect();
+        let delays = id_ports
+            .iter()
+            .zip(&components)
+            .map(|((id, _), c)| (id.clone(), c.delay()))
+            .collect();
+        let graph = simulator::dependency_graph(&id_ports);
+        Ok(timing(&graph, &id_ports.into_iter().collect(), &delays))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::{Add, Constant, ProbeOut, Register, Sext};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_timing() {
+        // two registers, r1 -> add -> sext -> r2 and r2 -> r1, ProbeOut has no delay
+        let cs = ComponentStore {
+            store: vec![
+                Rc::new(Register {
+                    id: "r1".to_string(),
+                    pos: (0.0, 0.0),
+                    r_in: Input::new("r2", 0),
+                    bit_width: 32,
+                    reset_value: None,
+                    delay: Some(1.0),
+                }),
+                Rc::new(Constant {
+                    id: "c".to_string(),
+                    pos: (0.0, 0.0),
+                    value: 1,
+                    bit_width: 32,
+                }),
+                Rc::new(Add {
+                    id: "add".to_string(),
+                    pos: (0.0, 0.0),
+                    a_in: Input::new("r1", 0),
+                    b_in: Input::new("c", 0),
+                    bit_width: 32,
+                    delay: None,
+                }),
+                Rc::new(Sext {
+                    id: "sext".to_string(),
+                    pos: (0.0, 0.0),
+                    sext_in: Input::new("add", 0),
+                    in_size: 32,
+                    out_size: 32,
+                    delay: Some(0.5),
+                }),
+                Rc::new(Register {
+                    id: "r2".to_string(),
+                    pos: (0.0, 0.0),
+                    r_in: Input::new("sext", 0),
+                    bit_width: 32,
+                    reset_value: None,
+                    delay: None,
+                }),
+                Rc::new(ProbeOut::new("po")),
+            ],
+        };
+        let mut clock = 0;
+        let simulator = Simulator::new(&cs, &mut clock);
+        let timing = simulator.timing();
+        assert_eq!(timing, cs.timing().unwrap());
+
+        // r1 (1.0) -> add (2.0 by default) -> sext (0.5)
+        assert_eq!(timing.paths.len(), 2);
+        assert_eq!(timing.period(), 3.5);
+        assert_eq!(timing.critical_ids(), vec!["r1", "add", "sext"]);
+        assert_eq!(
+            timing.critical_path().unwrap().to_string(),
+            "3.5 ns to r2.r_in\n  r1 +1.0 ns = 1.0 ns\n  add +2.0 ns = 3.0 ns\n  sext +0.5 ns = 3.5 ns"
+        );
+        // r2 (0.5 by default) -> r1
+        assert_eq!(timing.paths[1].end, "r1.r_in");
+        assert_eq!(timing.paths[1].delay(), 0.5);
+
+        // the critical path is highlighted in the dependency graph, components and edges
+        let dot = simulator::dot_string(
+            &simulator.graph,
+            &simulator.id_ports,
+            &timing.critical_ids(),
+        );
+        assert_eq!(dot.matches("color = blue").count(), 5);
+        // and in the GUI
+        assert_eq!(
+            simulator.critical_bounds(),
+            vec![((0.0, 0.0), (40.0, 80.0)); 3]
+        );
+
+        assert_eq!(timing.max_frequency().unwrap().round(), 286.0);
+        assert_eq!(
+            timing.to_string(),
+            "critical path 3.5 ns, maximum clock frequency 285.7 MHz"
+        );
+    }
+
+    #[test]
+    fn test_timing_no_delay() {
+        let cs = ComponentStore {
+            store: vec![Rc::new(ProbeOut::new("po"))],
+        };
+        let timing = cs.timing().unwrap();
+        assert_eq!(timing.paths, vec![]);
+        assert_eq!(timing.max_frequency(), None);
+        assert_eq!(timing.to_string(), "no propagation delay");
+    }
+}
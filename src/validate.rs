@@ -0,0 +1,421 @@
+use crate::common::{ComponentStore, IdPorts, Input, Ports, SimulatorError};
+use crate::simulator::{combinational_loops, dependency_graph, output_name, scoped_id_ports};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+
+// Distance within which positions are considered the same
+const TOLERANCE: f32 = 1.0;
+
+// Size of components not declaring a `width` and `height`
+const DEFAULT_SIZE: (f32, f32) = (40.0, 80.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    // The model cannot be simulated
+    Error,
+    // The model can be simulated, but is likely not what was intended
+    Warning,
+}
+
+/// A problem found in a model, by `ComponentStore::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub severity: Severity,
+    pub component: String,
+    pub msg: String,
+}
+
+impl Problem {
+    pub fn error(component: &str, msg: String) -> Self {
+        Problem {
+            severity: Severity::Error,
+            component: component.to_string(),
+            msg,
+        }
+    }
+
+    pub fn warning(component: &str, msg: String) -> Self {
+        Problem {
+            severity: Severity::Warning,
+            component: component.to_string(),
+            msg,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}: {}", severity, self.component, self.msg)
+    }
+}
+
+// position and size of a component, from its serialized fields `pos`, `width` and `height`
+struct Placement {
+    id: String,
+    pos: (f32, f32),
+    size: (f32, f32),
+    // end point relative to `pos`, for wires
+    delta: Option<(f32, f32)>,
+}
+
+fn pair(value: &Value) -> Option<(f32, f32)> {
+    Some((
+        value.get(0)?.as_f64()? as f32,
+        value.get(1)?.as_f64()? as f32,
+    ))
+}
+
+fn placement(id: &str, json: &Value) -> Option<Placement> {
+    let size = match (json["width"].as_f64(), json["height"].as_f64()) {
+        (Some(width), Some(height)) => (width as f32, height as f32),
+        _ => DEFAULT_SIZE,
+    };
+    Some(Placement {
+        id: id.to_string(),
+        pos: pair(&json["pos"])?,
+        size,
+        delta: pair(&json["delta"]),
+    })
+}
+
+/// Position (center) and size of a component.
+pub type Bounds = ((f32, f32), (f32, f32));
+
+/// position (center) and size of a serialized component, for highlighting,
+/// None for wires and components without a position
+pub fn bounds(json: &Value) -> Option<Bounds> {
+    placement("", json)
+        .filter(|p| p.delta.is_none())
+        .map(|p| (p.pos, p.size))
+}
+
+// inputs held by a serialized component, as (field, input),
+// fields holding an input or a list of inputs
+fn held_inputs(json: &Value) -> Vec<(String, Input)> {
+    let as_input = |value: &Value| match value {
+        Value::Object(object) if object.len() == 2 => {
+            serde_json::from_value::<Input>(value.clone()).ok()
+        }
+        _ => None,
+    };
+    let mut inputs = vec![];
+    if let Value::Object(fields) = json {
+        for (field, value) in fields {
+            match value {
+                Value::Array(values) => inputs.extend(
+                    values
+                        .iter()
+                        .filter_map(as_input)
+                        .map(|i| (field.clone(), i)),
+                ),
+                value => inputs.extend(as_input(value).map(|i| (field.clone(), i))),
+            }
+        }
+    }
+    inputs
+}
+
+// held inputs not matched by a declared input, each declared input matches one held input,
+// by the name of the field, or else by the signal read
+fn undeclared_inputs(held: Vec<(String, Input)>, ports: &Ports) -> Vec<(String, Input)> {
+    let mut declared: Vec<Option<&Input>> = ports.inputs.iter().map(Some).collect();
+    let mut unmatched = vec![];
+    for (field, input) in held {
+        match ports.input_names.iter().position(|name| *name == field) {
+            Some(i) if declared[i] == Some(&input) => declared[i] = None,
+            _ => unmatched.push((field, input)),
+        }
+    }
+    unmatched.retain(
+        |(_, input)| match declared.iter().position(|d| *d == Some(input)) {
+            Some(i) => {
+                declared[i] = None;
+                false
+            }
+            None => true,
+        },
+    );
+    unmatched
+}
+
+// distance from the point to the line segment from `a` to `b`
+fn distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length).clamp(0.0, 1.0)
+    };
+    let (x, y) = (a.0 + t * dx - p.0, a.1 + t * dy - p.1);
+    (x * x + y * y).sqrt()
+}
+
+impl ComponentStore {
+    /// check the model for problems without simulating it, all problems found are reported,
+    /// errors first, followed by warnings
+    ///
+    /// Subcircuits are expanded, such that connections to subcircuits are checked,
+    /// problems are reported for the components of the model only (not those of subcircuits).
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = vec![];
+
+        // ports of the model, with subcircuits expanded (if possible)
+        let id_ports: IdPorts = match self.flatten() {
+            Ok((scopes, components)) => {
+                let flat: Vec<_> = scopes
+                    .iter()
+                    .zip(&components)
+                    .map(|(scope, c)| (scope.is_empty(), scoped_id_ports(scope, c.get_id_ports())))
+                    .collect();
+                validate_connections(&flat, &mut problems)
+            }
+            Err(err) => {
+                problems.push(match err {
+                    SimulatorError::Subcircuit { component, msg } => {
+                        Problem::error(&component, msg)
+                    }
+                    err => Problem::error("", err.to_string()),
+                });
+                self.store.iter().map(|c| c.get_id_ports()).collect()
+            }
+        };
+
+        let mut placements = vec![];
+        for c in &self.store {
+            let (id, ports) = c.get_id_ports();
+            // component specific checks
+            problems.extend(c.validate(&id_ports));
+
+            let json = serde_json::to_value(&**c).unwrap_or_default();
+            // inputs not declared as ports, the component is not evaluated when they change
+            for (field, input) in undeclared_inputs(held_inputs(&json), &ports) {
+                problems.push(Problem::warning(
+                    &id,
+                    format!(
+                        "{} reads {} without declaring it as an input",
+                        field,
+                        output_name(&id_ports, &input)
+                    ),
+                ));
+            }
+            placements.extend(placement(&id, &json));
+        }
+        validate_placements(&placements, &mut problems);
+
+        problems.sort_by_key(|p| p.severity);
+        problems
+    }
+}
+
+// inputs referring to unknown ids, out of range indices, or too wide outputs,
+// combinatorial loops, duplicate ids and unused outputs, checked for the components
+// at the top level (marked `true`), returns the ports of all components
+fn validate_connections(flat: &[(bool, (String, Ports))], problems: &mut Vec<Problem>) -> IdPorts {
+    let mut id_ports = IdPorts::new();
+    for (_, (id, ports)) in flat {
+        if id_ports.insert(id.clone(), ports.clone()).is_some() {
+            problems.push(Problem::error(
+                id,
+                "identifier is defined twice".to_string(),
+            ));
+        }
+    }
+    let top_level: Vec<_> = flat
+        .iter()
+        .filter(|(top, _)| *top)
+        .map(|(_, c)| c)
+        .collect();
+
+    for (id, ports) in &top_level {
+        for (i, input) in ports.inputs.iter().enumerate() {
+            let name = ports.input_names.get(i).map_or("", |name| name.as_str());
+            let source = match id_ports.get(&input.id) {
+                Some(source) => source,
+                None => {
+                    problems.push(Problem::error(
+                        id,
+                        format!("{} reads {}, there is no such component", name, input.id),
+                    ));
+                    continue;
+                }
+            };
+            match source.output_widths.get(input.index) {
+                None => problems.push(Problem::error(
+                    id,
+                    format!(
+                        "{} reads {} at index {}, where {} has only {} outputs",
+                        name,
+                        input.id,
+                        input.index,
+                        input.id,
+                        source.outputs.len()
+                    ),
+                )),
+                Some(bit_width) if Some(bit_width) > ports.input_widths.get(i) => {
+                    problems.push(Problem::error(
+                        id,
+                        format!(
+                            "{} reads {} of width {}, where at most {} bits are accepted",
+                            name,
+                            output_name(&id_ports, input),
+                            bit_width,
+                            ports.input_widths[i]
+                        ),
+                    ))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let all: Vec<_> = flat.iter().map(|(_, c)| c.clone()).collect();
+    for cycle in combinational_loops(&dependency_graph(&all), &id_ports) {
+        problems.push(Problem::error(
+            &cycle.ids[0],
+            format!("combinatorial loop {}", cycle),
+        ));
+    }
+
+    // outputs read by any component (including those of subcircuits)
+    let read: HashSet<(&str, usize)> = all
+        .iter()
+        .flat_map(|(_, ports)| &ports.inputs)
+        .map(|input| (input.id.as_str(), input.index))
+        .collect();
+    for (id, ports) in &top_level {
+        for (index, name) in ports.output_names.iter().enumerate() {
+            if !read.contains(&(id.as_str(), index)) {
+                problems.push(Problem::warning(id, format!("output {} is not used", name)));
+            }
+        }
+    }
+    id_ports
+}
+
+// true if the bounding boxes of the components intersect (more than touch)
+fn overlap(a: &Placement, b: &Placement) -> bool {
+    (a.pos.0 - b.pos.0).abs() < (a.size.0 + b.size.0) / 2.0 - TOLERANCE
+        && (a.pos.1 - b.pos.1).abs() < (a.size.1 + b.size.1) / 2.0 - TOLERANCE
+}
+
+// overlapping components, and wires whose end points do not touch a component or another wire
+// (ports have no position of their own, a wire may end anywhere on the component it connects)
+fn validate_placements(placements: &[Placement], problems: &mut Vec<Problem>) {
+    let (wires, components): (Vec<_>, Vec<_>) = placements.iter().partition(|p| p.delta.is_some());
+
+    for (i, c) in components.iter().enumerate() {
+        if let Some(other) = components[..i].iter().find(|other| overlap(other, c)) {
+            problems.push(Problem::warning(&c.id, format!("overlaps {}", other.id)));
+        }
+    }
+
+    let end_points = |w: &Placement| {
+        let delta = w.delta.unwrap_or_default();
+        (w.pos, (w.pos.0 + delta.0, w.pos.1 + delta.1))
+    };
+    for w in &wires {
+        let (start, end) = end_points(w);
+        for p in [start, end] {
+            let on_component = components.iter().any(|c| {
+                (p.0 - c.pos.0).abs() <= c.size.0 / 2.0 + TOLERANCE
+                    && (p.1 - c.pos.1).abs() <= c.size.1 / 2.0 + TOLERANCE
+            });
+            let on_wire = wires.iter().any(|other| {
+                let (a, b) = end_points(other);
+                other.id != w.id && distance(p, a, b) <= TOLERANCE
+            });
+            if !on_component && !on_wire {
+                problems.push(Problem::warning(
+                    &w.id,
+                    format!(
+                        "end point ({}, {}) does not touch any component or wire",
+                        p.0, p.1
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::{port_names, OutputType};
+
+    #[test]
+    fn test_undeclared_inputs() {
+        let ports = Ports {
+            inputs: vec![Input::new("c", 0), Input::new("c", 0)],
+            input_widths: vec![32; 2],
+            input_names: port_names(&["a", "b"]),
+            clocked_inputs: vec![],
+            out_type: OutputType::Combinatorial,
+            outputs: vec![],
+            output_widths: vec![],
+            output_names: vec![],
+        };
+        // matched by field, or else by signal, each declared input matches once
+        let held = |fields: &[(&str, &str)]| {
+            fields
+                .iter()
+                .map(|(field, id)| (field.to_string(), Input::new(id, 0)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            undeclared_inputs(held(&[("a", "c"), ("b", "c")]), &ports),
+            vec![]
+        );
+        assert_eq!(
+            undeclared_inputs(held(&[("x", "c"), ("b", "c")]), &ports),
+            vec![]
+        );
+        assert_eq!(
+            undeclared_inputs(held(&[("a", "c"), ("b", "c"), ("size", "c")]), &ports),
+            held(&[("size", "c")])
+        );
+        assert_eq!(
+            undeclared_inputs(held(&[("a", "c"), ("b", "d")]), &ports),
+            held(&[("b", "d")])
+        );
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance((5.0, 3.0), (0.0, 0.0), (10.0, 0.0)), 3.0);
+        assert_eq!(distance((-4.0, 3.0), (0.0, 0.0), (10.0, 0.0)), 5.0);
+        assert_eq!(distance((1.0, 1.0), (1.0, 1.0), (1.0, 1.0)), 0.0);
+    }
+
+    #[test]
+    fn test_overlap() {
+        let c = |pos, size| Placement {
+            id: String::new(),
+            pos,
+            size,
+            delta: None,
+        };
+        let a = c((0.0, 0.0), (40.0, 80.0));
+        assert!(overlap(&a, &c((0.0, 0.0), (10.0, 10.0))));
+        // boxes overlapping, with different centres
+        assert!(overlap(&a, &c((30.0, 50.0), (40.0, 40.0))));
+        // boxes side by side, touching
+        assert!(!overlap(&a, &c((40.0, 0.0), (40.0, 80.0))));
+        assert!(!overlap(&a, &c((0.0, 100.0), (40.0, 80.0))));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let json = serde_json::json!({"pos": [10.0, 20.0], "width": 30.0, "height": 40.0});
+        assert_eq!(bounds(&json), Some(((10.0, 20.0), (30.0, 40.0))));
+        let json = serde_json::json!({"pos": [10.0, 20.0]});
+        assert_eq!(bounds(&json), Some(((10.0, 20.0), DEFAULT_SIZE)));
+        let json = serde_json::json!({"pos": [10.0, 20.0], "delta": [0.0, 10.0]});
+        assert_eq!(bounds(&json), None);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::common::{SignalValue, Simulator, SimulatorError};
+use std::{fs::File, io::prelude::*, path::PathBuf};
+
+// VCD identifier code of the signal at `index`, base 94 over the printable characters
+fn identifier(mut index: usize) -> String {
+    let mut code = String::new();
+    loop {
+        code.push((b'!' + (index % 94) as u8) as char);
+        index /= 94;
+        if index == 0 {
+            return code;
+        }
+        index -= 1;
+    }
+}
+
+// VCD references are whitespace separated
+fn reference(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .collect()
+}
+
+// VCD value change, scalar for 1 bit signals, binary vector otherwise
+fn value_change(value: SignalValue, bit_width: u32, code: &str) -> String {
+    let bits = match value {
+        SignalValue::Data(value) => format!("{:b}", value),
+        SignalValue::Unknown => "x".to_string(),
+        SignalValue::HighZ => "z".to_string(),
+    };
+    if bit_width == 1 {
+        format!("{}{}", bits, code)
+    } else {
+        format!("b{} {}", bits, code)
+    }
+}
+
+impl Simulator {
+    /// write the simulation history as a VCD (Value Change Dump) waveform,
+    /// one variable per component output (named as the output within a scope per component),
+    /// one time unit per clock cycle
+    pub fn write_vcd(&self, out: &mut impl Write) -> Result<(), SimulatorError> {
+        let io_err = |err: std::io::Error| SimulatorError::Io(err.to_string());
+
+        writeln!(out, "$version SyncRim {} $end", env!("CARGO_PKG_VERSION")).map_err(io_err)?;
+        writeln!(out, "$timescale 1ns $end").map_err(io_err)?;
+        writeln!(out, "$scope module syncrim $end").map_err(io_err)?;
+        for id in &self.component_ids {
+            let start_index = self.get_id_start_index(id);
+            writeln!(out, "$scope module {} $end", reference(id)).map_err(io_err)?;
+            for (index, name) in self.id_ports[id].output_names.iter().enumerate() {
+                writeln!(
+                    out,
+                    "$var wire {} {} {} $end",
+                    self.get_width(start_index + index),
+                    identifier(start_index + index),
+                    reference(name)
+                )
+                .map_err(io_err)?;
+            }
+            writeln!(out, "$upscope $end").map_err(io_err)?;
+        }
+        writeln!(out, "$upscope $end").map_err(io_err)?;
+        writeln!(out, "$enddefinitions $end").map_err(io_err)?;
+
+        let (initial, changes) = self.history.changes(&self.sim_state);
+        let start = if self.history.is_empty() {
+            self.cycle
+        } else {
+            self.history.first()
+        };
+        writeln!(out, "#{}", start).map_err(io_err)?;
+        writeln!(out, "$dumpvars").map_err(io_err)?;
+        for (index, value) in initial.iter().enumerate() {
+            writeln!(
+                out,
+                "{}",
+                value_change(*value, self.get_width(index), &identifier(index))
+            )
+            .map_err(io_err)?;
+        }
+        writeln!(out, "$end").map_err(io_err)?;
+
+        for (cycle, changed) in changes {
+            writeln!(out, "#{}", cycle).map_err(io_err)?;
+            for (index, value) in changed {
+                writeln!(
+                    out,
+                    "{}",
+                    value_change(value, self.get_width(index), &identifier(index))
+                )
+                .map_err(io_err)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// save the simulation history as a VCD waveform file, see `write_vcd`
+    pub fn save_vcd(&self, path: &PathBuf) -> Result<(), SimulatorError> {
+        let mut file =
+            File::create(path).map_err(|err| SimulatorError::Io(format!("{:?}: {}", path, err)))?;
+        self.write_vcd(&mut file).map_err(|err| match err {
+            SimulatorError::Io(err) => SimulatorError::Io(format!("{:?}: {}", path, err)),
+            err => err,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::counter;
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(identifier(0), "!");
+        assert_eq!(identifier(93), "~");
+        assert_eq!(identifier(94), "!!");
+        assert_eq!(identifier(95), "\"!");
+        assert_eq!(identifier(94 + 94 * 94), "!!!");
+    }
+
+    #[test]
+    fn test_value_change() {
+        assert_eq!(value_change(SignalValue::Data(1), 1, "!"), "1!");
+        assert_eq!(value_change(SignalValue::Data(5), 8, "!"), "b101 !");
+        assert_eq!(value_change(SignalValue::Unknown, 8, "#"), "bx #");
+        assert_eq!(value_change(SignalValue::HighZ, 1, "#"), "z#");
+    }
+
+    #[test]
+    fn test_vcd() {
+        let cs = counter(4);
+        let mut clock = 0;
+        let mut simulator = Simulator::new(&cs, &mut clock);
+        simulator.clock(&mut clock);
+        simulator.clock(&mut clock);
+
+        let mut out = vec![];
+        simulator.write_vcd(&mut out).unwrap();
+        let vcd = String::from_utf8(out).unwrap();
+        let (definitions, changes) = vcd.split_once("$enddefinitions $end\n").unwrap();
+
+        // one variable per output, scoped by component id
+        assert!(definitions.contains(
+            "$scope module add $end\n$var wire 4 # sum $end\n$var wire 1 $ overflow $end\n$upscope $end\n"
+        ));
+        assert_eq!(
+            changes,
+            "#0\n$dumpvars\nb0 !\nb0 \"\nb0 #\n0$\n$end\n\
+             #1\nb1 !\nb1 #\n\
+             #2\nb1 \"\nb10 #\n\
+             #3\nb10 \"\nb11 #\n"
+        );
+    }
+}
@@ -1,7 +1,13 @@
 use std::rc::Rc;
 use syncrim::{
-    common::{ComponentStore, Input, Signal, Simulator},
+    common::{
+        resolve_signal, ComponentStore, Input, ResetMode, Signal, SignalValue, Simulator,
+        SimulatorError,
+    },
     components::*,
+    condition::Condition,
+    stimulus::Stimulus,
+    testbench::Testbench,
 };
 
 #[test]
@@ -15,6 +21,8 @@ fn test_add() {
                 pos: (0.0, 0.0),
                 a_in: Input::new("po1", 0),
                 b_in: Input::new("po2", 0),
+                bit_width: 32,
+                delay: None,
             }),
         ],
     };
@@ -44,13 +52,1003 @@ fn test_add() {
 
     // trigger positive overflow
     println!("<setup for clock 3>");
-    simulator.set_id_index("po1", 0, Signal::MAX / 2);
+    simulator.set_id_index("po1", 0, u32::MAX as Signal / 2);
     simulator.set_id_index("po2", 0, 1);
     println!("sim_state {:?}", simulator.sim_state);
     println!("<clock>");
     simulator.clock(&mut clock);
     println!("sim_state {:?}", simulator.sim_state);
     assert_eq!(clock, 3);
+    assert_eq!(simulator.get_input_val(add_val), u32::MAX as Signal / 2 + 1);
+    assert_eq!(simulator.get_input_val(add_overflow), true as Signal);
+}
+
+#[test]
+fn test_add_bit_width() {
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut {
+                id: "po1".to_string(),
+                bit_width: 8,
+            }),
+            Rc::new(ProbeOut {
+                id: "po2".to_string(),
+                bit_width: 8,
+            }),
+            Rc::new(Add {
+                id: "add".to_string(),
+                pos: (0.0, 0.0),
+                a_in: Input::new("po1", 0),
+                b_in: Input::new("po2", 0),
+                bit_width: 8,
+                delay: None,
+            }),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+
+    // outputs
+    let add_val = &Input::new("add", 0);
+    let add_overflow = &Input::new("add", 1);
+
+    // 8 bit signed positive overflow (100 + 100 = 200, wraps to -56)
+    simulator.set_id_index("po1", 0, 100);
+    simulator.set_id_index("po2", 0, 100);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(add_val), 200);
+    assert_eq!(simulator.get_input_val(add_overflow), true as Signal);
+
+    // -1 + -1 = -2, no overflow
+    simulator.set_id_index("po1", 0, 0xff);
+    simulator.set_id_index("po2", 0, 0xff);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(add_val), 0xfe);
+    assert_eq!(simulator.get_input_val(add_overflow), false as Signal);
+}
+
+#[test]
+fn test_add_wide() {
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut {
+                id: "po1".to_string(),
+                bit_width: 128,
+            }),
+            Rc::new(ProbeOut {
+                id: "po2".to_string(),
+                bit_width: 128,
+            }),
+            Rc::new(Add {
+                id: "add".to_string(),
+                pos: (0.0, 0.0),
+                a_in: Input::new("po1", 0),
+                b_in: Input::new("po2", 0),
+                bit_width: 128,
+                delay: None,
+            }),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+
+    // outputs
+    let add_val = &Input::new("add", 0);
+    let add_overflow = &Input::new("add", 1);
+
+    // carry across the 64 bit boundary
+    simulator.set_id_index("po1", 0, u64::MAX as Signal);
+    simulator.set_id_index("po2", 0, 1);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(add_val), 1 << 64);
+    assert_eq!(simulator.get_input_val(add_overflow), false as Signal);
+
+    // 128 bit signed positive overflow
+    simulator.set_id_index("po1", 0, Signal::MAX / 2);
+    simulator.set_id_index("po2", 0, 1);
+    simulator.clock(&mut clock);
     assert_eq!(simulator.get_input_val(add_val), Signal::MAX / 2 + 1);
     assert_eq!(simulator.get_input_val(add_overflow), true as Signal);
 }
+
+#[test]
+fn test_sext_wide() {
+    // 1 bit extended to 128 bits, 128 bits passed through,
+    // and the low 8 bits of a 128 bit input extended to 16 bits
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut {
+                id: "po1".to_string(),
+                bit_width: 1,
+            }),
+            Rc::new(ProbeOut {
+                id: "po2".to_string(),
+                bit_width: 128,
+            }),
+            Rc::new(Sext {
+                id: "sext1".to_string(),
+                pos: (0.0, 0.0),
+                sext_in: Input::new("po1", 0),
+                in_size: 1,
+                out_size: 128,
+                delay: None,
+            }),
+            Rc::new(Sext {
+                id: "sext128".to_string(),
+                pos: (0.0, 0.0),
+                sext_in: Input::new("po2", 0),
+                in_size: 128,
+                out_size: 128,
+                delay: None,
+            }),
+            Rc::new(Sext {
+                id: "sext8".to_string(),
+                pos: (0.0, 0.0),
+                sext_in: Input::new("po2", 0),
+                in_size: 8,
+                out_size: 16,
+                delay: None,
+            }),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+
+    simulator.set_id_index("po1", 0, 1);
+    simulator.set_id_index("po2", 0, Signal::MAX / 2 + 1);
+    simulator.clock(&mut clock);
+    assert_eq!(
+        simulator.get_input_val(&Input::new("sext1", 0)),
+        Signal::MAX
+    );
+    assert_eq!(
+        simulator.get_input_val(&Input::new("sext128", 0)),
+        Signal::MAX / 2 + 1
+    );
+    assert_eq!(simulator.get_input_val(&Input::new("sext8", 0)), 0);
+
+    simulator.set_id_index("po2", 0, 0x1_0000_0180);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(&Input::new("sext8", 0)), 0xff80);
+
+    simulator.set_id_index("po1", 0, 0);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(&Input::new("sext1", 0)), 0);
+}
+
+#[test]
+fn test_register_chain() {
+    let cs = ComponentStore {
+        store: vec![
+            // registers deliberately listed in reverse order
+            Rc::new(Register {
+                id: "r3".to_string(),
+                pos: (0.0, 0.0),
+                r_in: Input::new("r2", 0),
+                bit_width: 32,
+                reset_value: None,
+                delay: None,
+            }),
+            Rc::new(Register {
+                id: "r2".to_string(),
+                pos: (0.0, 0.0),
+                r_in: Input::new("r1", 0),
+                bit_width: 32,
+                reset_value: None,
+                delay: None,
+            }),
+            Rc::new(Register {
+                id: "r1".to_string(),
+                pos: (0.0, 0.0),
+                r_in: Input::new("po1", 0),
+                bit_width: 32,
+                reset_value: None,
+                delay: None,
+            }),
+            Rc::new(ProbeOut::new("po1")),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+
+    assert_eq!(clock, 1);
+
+    // outputs
+    let r1 = &Input::new("r1", 0);
+    let r2 = &Input::new("r2", 0);
+    let r3 = &Input::new("r3", 0);
+
+    // reset
+    assert_eq!(simulator.get_input_val(r1), 0);
+    assert_eq!(simulator.get_input_val(r2), 0);
+    assert_eq!(simulator.get_input_val(r3), 0);
+
+    // each register should shift its input exactly one stage per clock
+    println!("<setup for clock 2>");
+    simulator.set_id_index("po1", 0, 1);
+    simulator.clock(&mut clock);
+    assert_eq!(clock, 2);
+    assert_eq!(simulator.get_input_val(r1), 1);
+    assert_eq!(simulator.get_input_val(r2), 0);
+    assert_eq!(simulator.get_input_val(r3), 0);
+
+    println!("<setup for clock 3>");
+    simulator.set_id_index("po1", 0, 2);
+    simulator.clock(&mut clock);
+    assert_eq!(clock, 3);
+    assert_eq!(simulator.get_input_val(r1), 2);
+    assert_eq!(simulator.get_input_val(r2), 1);
+    assert_eq!(simulator.get_input_val(r3), 0);
+
+    println!("<setup for clock 4>");
+    simulator.clock(&mut clock);
+    assert_eq!(clock, 4);
+    assert_eq!(simulator.get_input_val(r1), 2);
+    assert_eq!(simulator.get_input_val(r2), 2);
+    assert_eq!(simulator.get_input_val(r3), 1);
+}
+
+#[test]
+fn test_register_reset() {
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(Register {
+                id: "r1".to_string(),
+                pos: (0.0, 0.0),
+                r_in: Input::new("po1", 0),
+                bit_width: 32,
+                reset_value: Some(5),
+                delay: None,
+            }),
+            Rc::new(Register {
+                id: "r2".to_string(),
+                pos: (0.0, 0.0),
+                r_in: Input::new("r1", 0),
+                bit_width: 32,
+                reset_value: None,
+                delay: None,
+            }),
+            Rc::new(Register {
+                id: "r3".to_string(),
+                pos: (0.0, 0.0),
+                r_in: Input::new("r3", 0),
+                bit_width: 8,
+                reset_value: Some(0x1ff),
+                delay: None,
+            }),
+            Rc::new(ProbeOut::new("po1")),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+
+    // outputs
+    let r1 = &Input::new("r1", 0);
+    let r2 = &Input::new("r2", 0);
+
+    // power-on, r1 is set to its reset value
+    assert_eq!(clock, 1);
+    assert_eq!(simulator.get_input_val(r1), 5);
+    assert_eq!(simulator.get_input_val(r2), 0);
+
+    // a reset value wider than the register is masked, and warned about
+    assert_eq!(simulator.get_input_val(&Input::new("r3", 0)), 0xff);
+    assert!(cs.validate().iter().any(|p| p.to_string()
+        == "warning: r3: reset_value 0x1ff does not fit in 8 bits, it is masked to 0xff"));
+
+    simulator.set_id_index("po1", 0, 1);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(r1), 1);
+    assert_eq!(simulator.get_input_val(r2), 5);
+
+    // reset returns to power-on
+    simulator.reset(&mut clock);
+    assert_eq!(clock, 1);
+    assert_eq!(simulator.get_input_val(r1), 5);
+    assert_eq!(simulator.get_input_val(r2), 0);
+
+    // in four-state mode, registers without a reset value are unknown
+    simulator.set_four_state(true, &mut clock);
+    assert_eq!(simulator.get_input_value(r1), SignalValue::Data(5));
+    assert_eq!(simulator.get_input_value(r2), SignalValue::Unknown);
+}
+
+#[test]
+fn test_register_swap() {
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(Register {
+                id: "ra".to_string(),
+                pos: (0.0, 0.0),
+                r_in: Input::new("rb", 0),
+                bit_width: 32,
+                reset_value: None,
+                delay: None,
+            }),
+            Rc::new(Register {
+                id: "rb".to_string(),
+                pos: (0.0, 0.0),
+                r_in: Input::new("ra", 0),
+                bit_width: 32,
+                reset_value: None,
+                delay: None,
+            }),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+
+    assert_eq!(clock, 1);
+
+    // outputs
+    let ra = &Input::new("ra", 0);
+    let rb = &Input::new("rb", 0);
+
+    // initialize register state
+    simulator.set_id_index("ra", 0, 1);
+    simulator.set_id_index("rb", 0, 2);
+
+    // registers should swap values on each clock
+    simulator.clock(&mut clock);
+    assert_eq!(clock, 2);
+    assert_eq!(simulator.get_input_val(ra), 2);
+    assert_eq!(simulator.get_input_val(rb), 1);
+
+    simulator.clock(&mut clock);
+    assert_eq!(clock, 3);
+    assert_eq!(simulator.get_input_val(ra), 1);
+    assert_eq!(simulator.get_input_val(rb), 2);
+
+    // stepping back restores the swapped state
+    simulator.un_clock(&mut clock);
+    assert_eq!(clock, 2);
+    assert_eq!(simulator.get_input_val(ra), 2);
+    assert_eq!(simulator.get_input_val(rb), 1);
+}
+
+#[test]
+fn test_four_state() {
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut::new("po1")),
+            Rc::new(ProbeOut::new("po2")),
+            Rc::new(ProbeOut::new("sel")),
+            Rc::new(Add {
+                id: "add".to_string(),
+                pos: (0.0, 0.0),
+                a_in: Input::new("po1", 0),
+                b_in: Input::new("po2", 0),
+                bit_width: 32,
+                delay: None,
+            }),
+            Rc::new(Register {
+                id: "reg".to_string(),
+                pos: (0.0, 0.0),
+                r_in: Input::new("add", 0),
+                bit_width: 32,
+                reset_value: None,
+                delay: None,
+            }),
+            Rc::new(Mux {
+                id: "mux".to_string(),
+                pos: (0.0, 0.0),
+                select: Input::new("sel", 0),
+                m_in: vec![Input::new("po1", 0), Input::new("reg", 0)],
+                bit_width: 32,
+                delay: None,
+            }),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+
+    // outputs
+    let add_val = &Input::new("add", 0);
+    let add_overflow = &Input::new("add", 1);
+    let reg = &Input::new("reg", 0);
+    let mux = &Input::new("mux", 0);
+
+    // two-state mode, all signals start at 0
+    assert_eq!(simulator.get_input_value(add_val), SignalValue::Data(0));
+    assert_eq!(simulator.get_input_value(reg), SignalValue::Data(0));
+
+    // four-state mode, all signals start unknown
+    simulator.set_four_state(true, &mut clock);
+    assert_eq!(clock, 1);
+    assert_eq!(simulator.get_input_value(add_val), SignalValue::Unknown);
+    assert_eq!(
+        simulator.get_input_value(add_overflow),
+        SignalValue::Unknown
+    );
+    assert_eq!(simulator.get_input_value(reg), SignalValue::Unknown);
+    assert_eq!(simulator.get_input_value(mux), SignalValue::Unknown);
+    // unknown values read as 0
+    assert_eq!(simulator.get_input_val(add_val), 0);
+
+    // a single known operand is not enough
+    simulator.set_id_index("po1", 0, 1);
+    simulator.set_id_index("sel", 0, 1);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_value(add_val), SignalValue::Unknown);
+    assert_eq!(simulator.get_input_value(reg), SignalValue::Unknown);
+    assert_eq!(simulator.get_input_value(mux), SignalValue::Unknown);
+
+    // the register samples the unknown sum on the clock edge
+    simulator.set_id_index("po2", 0, 2);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_value(add_val), SignalValue::Data(3));
+    assert_eq!(
+        simulator.get_input_value(add_overflow),
+        SignalValue::Data(0)
+    );
+    assert_eq!(simulator.get_input_value(reg), SignalValue::Unknown);
+
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_value(reg), SignalValue::Data(3));
+    assert_eq!(simulator.get_input_value(mux), SignalValue::Data(3));
+
+    // high-impedance inputs propagate as unknown
+    simulator.set_id_index_value("po2", 0, SignalValue::HighZ);
+    simulator.set_id_index("sel", 0, 0);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_value(add_val), SignalValue::Unknown);
+    assert_eq!(simulator.get_input_value(mux), SignalValue::Data(1));
+    simulator.set_id_index_value("sel", 0, SignalValue::HighZ);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_value(mux), SignalValue::Unknown);
+
+    // a select of no input is unknown, 0 in two-state mode
+    simulator.set_id_index("sel", 0, 2);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_value(mux), SignalValue::Unknown);
+    simulator.set_four_state(false, &mut clock);
+    simulator.set_id_index("sel", 0, 2);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_value(mux), SignalValue::Data(0));
+}
+
+#[test]
+fn test_stimulus() {
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut::new("po1")),
+            Rc::new(ProbeOut::new("po2")),
+            Rc::new(Add {
+                id: "add".to_string(),
+                pos: (0.0, 0.0),
+                a_in: Input::new("po1", 0),
+                b_in: Input::new("po2", 0),
+                bit_width: 32,
+                delay: None,
+            }),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+    let add_val = &Input::new("add", 0);
+
+    // vectors are applied on the clock into the cycle, and kept until changed
+    let stimulus = Stimulus::parse_csv("cycle,po1,po2.out\n0,1,2\n2,42,1337\n4,,1\n").unwrap();
+    simulator.set_stimulus(stimulus, &mut clock).unwrap();
+    assert_eq!(clock, 1);
+    let mut sums = vec![simulator.get_input_val(add_val)];
+    for _ in 0..4 {
+        simulator.clock(&mut clock);
+        sums.push(simulator.get_input_val(add_val));
+    }
+    assert_eq!(sums, vec![3, 1379, 1379, 43, 43]);
+
+    // re-applied after stepping back
+    simulator.un_clock(&mut clock);
+    simulator.un_clock(&mut clock);
+    assert_eq!(simulator.get_input_val(add_val), 1379);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(add_val), 43);
+
+    // signals not found in the model
+    let stimulus = Stimulus::parse_csv("cycle,po3\n1,1\n").unwrap();
+    assert_eq!(
+        simulator.set_stimulus(stimulus, &mut clock),
+        Err(SimulatorError::UnknownSignal("po3".to_string()))
+    );
+}
+
+#[test]
+fn test_resolve_signal() {
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut::new("po1")),
+            Rc::new(ProbeOut::new("po.2")),
+            Rc::new(Add {
+                id: "add".to_string(),
+                pos: (0.0, 0.0),
+                a_in: Input::new("po1", 0),
+                b_in: Input::new("po.2", 0),
+                bit_width: 32,
+                delay: None,
+            }),
+        ],
+    };
+    let mut clock = 0;
+    let simulator = Simulator::new(&cs, &mut clock);
+    let resolve = |signal: &str| resolve_signal(&simulator.id_ports, signal);
+
+    // by name or index, index 0 if only the id is given
+    assert_eq!(resolve("add.overflow"), Ok(Input::new("add", 1)));
+    assert_eq!(resolve("add.1"), Ok(Input::new("add", 1)));
+    assert_eq!(resolve("add"), Ok(Input::new("add", 0)));
+    // ids may contain dots
+    assert_eq!(resolve("po.2"), Ok(Input::new("po.2", 0)));
+    assert_eq!(resolve("po.2.out"), Ok(Input::new("po.2", 0)));
+
+    for signal in ["add.2", "add.carry", "po3"] {
+        assert_eq!(
+            resolve(signal),
+            Err(SimulatorError::UnknownSignal(signal.to_string()))
+        );
+    }
+}
+
+#[test]
+fn test_testbench() {
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut::new("po1")),
+            Rc::new(ProbeOut::new("po2")),
+            Rc::new(Add {
+                id: "add".to_string(),
+                pos: (0.0, 0.0),
+                a_in: Input::new("po1", 0),
+                b_in: Input::new("po2", 0),
+                bit_width: 32,
+                delay: None,
+            }),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+
+    // test_add, as a testbench
+    let testbench = Testbench::parse_csv(
+        "cycle,po1,po2,=add.sum,=add.overflow\n\
+         1,,,0,0\n\
+         2,42,1337,1379,0\n\
+         3,0x7fffffff,1,0x80000000,1\n",
+    )
+    .unwrap();
+    let report = simulator.run_testbench(&testbench, &mut clock).unwrap();
+    assert_eq!(report.mismatch, None);
+    assert_eq!((report.cycles, report.checks), (3, 6));
+
+    // stops at the first mismatching cycle
+    let testbench = Testbench::parse_json(
+        r#"{
+            "four_state": true,
+            "stimulus": { "2": { "po1": 1 } },
+            "expect": { "2": { "add.sum": 1 }, "3": { "add.0": 1 } }
+        }"#,
+    )
+    .unwrap();
+    let report = simulator.run_testbench(&testbench, &mut clock).unwrap();
+    let mismatch = report.mismatch.unwrap();
+    assert_eq!((report.cycles, mismatch.cycle), (2, 2));
+    assert_eq!(mismatch.diff[0].actual, SignalValue::Unknown);
+    assert_eq!(
+        mismatch.to_string(),
+        "Mismatch at cycle 2\n- add.sum = 0x00000001\n+ add.sum = X"
+    );
+}
+
+// adder model, with inputs a and b
+fn adder() -> ComponentStore {
+    ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut::new("a")),
+            Rc::new(ProbeOut::new("b")),
+            Rc::new(Add {
+                id: "add".to_string(),
+                pos: (0.0, 0.0),
+                a_in: Input::new("a", 0),
+                b_in: Input::new("b", 0),
+                bit_width: 32,
+                delay: None,
+            }),
+        ],
+    }
+}
+
+#[test]
+fn test_subcircuit() {
+    // delay model, with input d, the adder nested in a subcircuit
+    let delay = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut::new("d")),
+            Rc::new(Register {
+                id: "reg".to_string(),
+                pos: (0.0, 0.0),
+                r_in: Input::new("inc", 0),
+                bit_width: 32,
+                reset_value: None,
+                delay: None,
+            }),
+            Rc::new(Constant {
+                id: "one".to_string(),
+                pos: (0.0, 0.0),
+                value: 1,
+                bit_width: 32,
+            }),
+            Rc::new(Subcircuit::new(
+                "inc",
+                (0.0, 0.0),
+                adder(),
+                vec![("a", Input::new("d", 0)), ("b", Input::new("one", 0))],
+                vec!["add.sum"],
+            )),
+        ],
+    };
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut::new("po1")),
+            Rc::new(ProbeOut::new("po2")),
+            Rc::new(ProbeOut::new("po3")),
+            // the adder instantiated twice
+            Rc::new(Subcircuit::new(
+                "add1",
+                (0.0, 0.0),
+                adder(),
+                vec![("a", Input::new("po1", 0)), ("b", Input::new("po2", 0))],
+                vec!["add.sum"],
+            )),
+            Rc::new(Subcircuit::new(
+                "add2",
+                (0.0, 0.0),
+                adder(),
+                vec![("a", Input::new("add1", 0)), ("b", Input::new("po3", 0))],
+                vec!["add.sum", "add.overflow"],
+            )),
+            Rc::new(Subcircuit::new(
+                "delay",
+                (0.0, 0.0),
+                delay,
+                vec![("d", Input::new("add2", 0))],
+                vec!["reg"],
+            )),
+        ],
+    };
+    let mut clock = 0;
+    let mut simulator = Simulator::new(&cs, &mut clock);
+
+    // inner components are simulated with namespaced ids
+    assert!(simulator.component_ids.contains(&"add1/add".to_string()));
+    assert!(simulator
+        .component_ids
+        .contains(&"delay/inc/add".to_string()));
+    assert_eq!(simulator.get_input_width(&Input::new("add2", 1)), 1);
+    // the ports are resolved from the model, also when not expanded
+    let (_, ports) = cs.store[4].get_id_ports();
+    assert_eq!(
+        ports.inputs,
+        vec![Input::new("add2/add", 0), Input::new("add2/add", 1)]
+    );
+    assert_eq!(ports.output_widths, vec![32, 1]);
+
+    simulator.set_id_index("po1", 0, 1);
+    simulator.set_id_index("po2", 0, 2);
+    simulator.set_id_index("po3", 0, 3);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(&Input::new("add1", 0)), 3);
+    assert_eq!(simulator.get_input_val(&Input::new("add2", 0)), 6);
+    assert_eq!(simulator.get_input_val(&Input::new("add1/add", 0)), 3);
+    // inner inputs are driven from the outer signals
+    assert_eq!(simulator.get_input_val(&Input::new("add2/a", 0)), 3);
+    // and named by their path in conditions
+    let condition = Condition::parse("add1/add.sum == 3 && delay/inc/add.0 != 3").unwrap();
+    assert_eq!(condition.check(&simulator), Ok(()));
+    assert!(condition.eval(&simulator));
+    assert_eq!(simulator.get_input_val(&Input::new("delay", 0)), 1);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(&Input::new("delay/inc", 0)), 7);
+    assert_eq!(simulator.get_input_val(&Input::new("delay", 0)), 7);
+
+    // the model of a subcircuit, opened standalone by the GUIs
+    let model = |id: &str| {
+        let i = simulator
+            .component_ids
+            .iter()
+            .position(|c| c == id)
+            .unwrap();
+        simulator.ordered_components[i].model()
+    };
+    let inner = Simulator::new(&model("delay").unwrap().unwrap(), &mut clock);
+    assert!(inner.component_ids.contains(&"inc/add".to_string()));
+    assert!(model("po1").is_none());
+    assert!(model("add1/add").is_none());
+
+    // round trip, embedded models are saved with the model
+    let cs = ComponentStore::try_load(&cs.to_json()).unwrap();
+    let simulator = Simulator::new(&cs, &mut clock);
+    assert_eq!(simulator.component_ids.len(), 19);
+}
+
+#[test]
+fn test_subcircuit_path() {
+    // top.json -> sub/delay.json -> sub/adder.json, paths relative to the referring model
+    let dir = std::env::temp_dir().join("syncrim_test_subcircuit_path");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    adder().save_file(&dir.join("sub/adder.json"));
+
+    let file_subcircuit = |id: &str, path: &str, inputs: Vec<(&str, Input)>, output: &str| {
+        let mut subcircuit = Subcircuit::new(id, (0.0, 0.0), adder(), inputs, vec![output]);
+        subcircuit.path = Some(path.to_string());
+        subcircuit.model = None;
+        Rc::new(subcircuit)
+    };
+    let delay = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut::new("d")),
+            file_subcircuit(
+                "inc",
+                "adder.json",
+                vec![("a", Input::new("d", 0)), ("b", Input::new("d", 0))],
+                "add.sum",
+            ),
+        ],
+    };
+    delay.save_file(&dir.join("sub/delay.json"));
+    let top = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut::new("po1")),
+            file_subcircuit(
+                "delay",
+                "sub/delay.json",
+                vec![("d", Input::new("po1", 0))],
+                "inc",
+            ),
+            // embedded models refer to files relative to the model they are embedded in
+            Rc::new(Subcircuit::new(
+                "embedded",
+                (0.0, 0.0),
+                delay,
+                vec![("d", Input::new("po1", 0))],
+                vec!["inc"],
+            )),
+        ],
+    };
+    top.save_file(&dir.join("top.json"));
+
+    // embedded in top.json, adder.json is looked up next to it (and not found)
+    let cs = ComponentStore::try_load_file(&dir.join("top.json")).unwrap();
+    assert!(matches!(
+        Simulator::try_new(&cs, &mut 0),
+        Err(SimulatorError::Subcircuit { component, msg })
+            if component == "inc" && msg.contains(&format!("{:?}", dir.join("adder.json")))
+    ));
+
+    let mut top = ComponentStore::try_load_file(&dir.join("top.json")).unwrap();
+    top.store.pop();
+    top.save_file(&dir.join("top.json"));
+    let cs = ComponentStore::try_load_file(&dir.join("top.json")).unwrap();
+    let mut clock = 0;
+    let mut simulator = Simulator::try_new(&cs, &mut clock).unwrap();
+    simulator.set_id_index("po1", 0, 21);
+    simulator.clock(&mut clock);
+    assert_eq!(simulator.get_input_val(&Input::new("delay", 0)), 42);
+}
+
+#[test]
+fn test_subcircuit_error() {
+    let subcircuit = |inputs: Vec<(&str, Input)>, outputs: Vec<&str>| ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut::new("po1")),
+            Rc::new(Subcircuit::new("sub", (0.0, 0.0), adder(), inputs, outputs)),
+        ],
+    };
+    let mut clock = 0;
+
+    let cs = subcircuit(vec![("c", Input::new("po1", 0))], vec![]);
+    assert_eq!(
+        Simulator::try_new(&cs, &mut clock).err(),
+        Some(SimulatorError::Subcircuit {
+            component: "sub".to_string(),
+            msg: "input c not found".to_string()
+        })
+    );
+
+    let cs = subcircuit(vec![], vec!["add.2"]);
+    assert_eq!(
+        Simulator::try_new(&cs, &mut clock).err(),
+        Some(SimulatorError::Subcircuit {
+            component: "sub".to_string(),
+            msg: "output add.2 not found".to_string()
+        })
+    );
+
+    // outputs not found in the model, the ports fall back to the outputs as given
+    let cs = subcircuit(vec![], vec!["add.carry"]);
+    let (_, ports) = cs.store[1].get_id_ports();
+    assert!(ports.inputs.is_empty());
+    assert_eq!(ports.output_names, vec!["add.carry".to_string()]);
+    assert_eq!(
+        Simulator::try_new(&cs, &mut clock).err(),
+        Some(SimulatorError::Subcircuit {
+            component: "sub".to_string(),
+            msg: "output add.carry not found".to_string()
+        })
+    );
+
+    // the outer signals are not visible inside the subcircuit
+    let mut adder = adder();
+    adder.store.push(Rc::new(Probe {
+        id: "probe".to_string(),
+        pos: (0.0, 0.0),
+        input: Input::new("po1", 0),
+    }));
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut::new("po1")),
+            Rc::new(Subcircuit::new("sub", (0.0, 0.0), adder, vec![], vec![])),
+        ],
+    };
+    assert!(matches!(
+        Simulator::try_new(&cs, &mut clock),
+        Err(SimulatorError::UnknownSource { component, .. }) if component == "sub/probe"
+    ));
+}
+
+#[test]
+fn test_validate() {
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(Constant {
+                id: "c1".to_string(),
+                pos: (100.0, 100.0),
+                value: 1,
+                bit_width: 2,
+            }),
+            Rc::new(Constant {
+                id: "c2".to_string(),
+                pos: (100.0, 200.0),
+                value: 0,
+                bit_width: 32,
+            }),
+            Rc::new(Mux {
+                id: "mux".to_string(),
+                pos: (200.0, 150.0),
+                select: Input::new("c1", 0),
+                m_in: vec![
+                    Input::new("c1", 0),
+                    Input::new("c1", 0),
+                    Input::new("c2", 0),
+                ],
+                bit_width: 16,
+                delay: None,
+            }),
+            Rc::new(Sext {
+                id: "sext".to_string(),
+                pos: (300.0, 150.0),
+                sext_in: Input::new("mux", 0),
+                in_size: 16,
+                out_size: 8,
+                delay: None,
+            }),
+            Rc::new(Probe {
+                id: "p1".to_string(),
+                pos: (400.0, 100.0),
+                input: Input::new("missing", 0),
+            }),
+            Rc::new(Probe {
+                id: "p2".to_string(),
+                pos: (400.0, 200.0),
+                input: Input::new("c1", 1),
+            }),
+            Rc::new(Wire {
+                id: "w1".to_string(),
+                pos: (110.0, 100.0),
+                delta: (80.0, 0.0),
+                input: Input::new("c1", 0),
+            }),
+            Rc::new(Mem {
+                id: "mem".to_string(),
+                pos: (400.0, 200.0),
+                width: 100.0,
+                height: 50.0,
+                big_endian: true,
+                data: Input::new("c2", 0),
+                addr: Input::new("c2", 0),
+                ctrl: Input::new("c2", 0),
+                sign_extend: Input::new("c2", 0),
+                size: Input::new("c2", 0),
+                memory: Memory::new(),
+                reset: ResetMode::Reload,
+                delay: None,
+            }),
+        ],
+    };
+    // all problems are reported, errors first
+    let problems: Vec<String> = cs.validate().iter().map(|p| p.to_string()).collect();
+    assert_eq!(
+        problems,
+        vec![
+            "error: mux: in2 reads c2.out of width 32, where at most 16 bits are accepted",
+            "error: p1: input reads missing, there is no such component",
+            "error: p2: input reads c1 at index 1, where c1 has only 1 outputs",
+            "error: sext: in_size 16 is larger than out_size 8",
+            "warning: sext: output out is not used",
+            "warning: mem: output data is not used",
+            "warning: mem: output err is not used",
+            "warning: mux: 3 inputs, where the select signal of width 2 may select up to 4",
+            "warning: mem: overlaps p2",
+            "warning: w1: end point (190, 100) does not touch any component or wire",
+        ]
+    );
+
+    // a select wider than needed (of the default width) is not expected to select every input
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut::new("po1")),
+            Rc::new(Mux {
+                id: "mux".to_string(),
+                pos: (100.0, 0.0),
+                select: Input::new("po1", 0),
+                m_in: vec![Input::new("po1", 0); 3],
+                bit_width: 32,
+                delay: None,
+            }),
+        ],
+    };
+    let problems: Vec<String> = cs.validate().iter().map(|p| p.to_string()).collect();
+    assert_eq!(problems, vec!["warning: mux: output out is not used"]);
+
+    // connections to subcircuits are checked, problems within subcircuits are not reported
+    let cs = ComponentStore {
+        store: vec![
+            Rc::new(ProbeOut::new("po1")),
+            Rc::new(Subcircuit::new(
+                "sub",
+                (0.0, 0.0),
+                adder(),
+                vec![("a", Input::new("po2", 0))],
+                vec!["add.sum"],
+            )),
+            Rc::new(Probe {
+                id: "probe".to_string(),
+                pos: (100.0, 0.0),
+                input: Input::new("sub", 0),
+            }),
+        ],
+    };
+    let problems: Vec<String> = cs.validate().iter().map(|p| p.to_string()).collect();
+    assert_eq!(
+        problems,
+        vec![
+            "error: sub/a: in reads po2, there is no such component",
+            "warning: po1: output out is not used",
+        ]
+    );
+}
+
+#[test]
+fn test_migrate() {
+    // models saved before versioning, by the sext and data_mem examples
+    let dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/models");
+    let path = std::env::temp_dir().join("syncrim_test_migrate.json");
+
+    assert_eq!(syncrim::cli::migrate(&dir.join("sext.json"), &path), Ok(0));
+    let cs = ComponentStore::load_file(&path);
+    assert_eq!(
+        syncrim::migration::model_version(&cs.to_json()),
+        Ok(syncrim::migration::MODEL_VERSION)
+    );
+    let mut clock = 0;
+    let mut simulator = Simulator::try_new(&cs, &mut clock).unwrap();
+    simulator.clock(&mut clock);
+    // 0x8000 (of a 32 bit constant) extended from 16 to 24 bits
+    assert_eq!(simulator.get_input_val(&Input::new("sxt0", 0)), 0xff8000);
+
+    assert_eq!(syncrim::cli::migrate(&dir.join("mem.json"), &path), Ok(0));
+    let cs = ComponentStore::load_file(&path);
+    let mut simulator = Simulator::try_new(&cs, &mut clock).unwrap();
+    simulator.clock(&mut clock);
+    // an aligned byte write
+    assert_eq!(simulator.get_input_val(&Input::new("mem", 1)), 0);
+}